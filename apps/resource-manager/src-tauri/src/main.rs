@@ -8,6 +8,21 @@ use tauri::Manager;
 /// bundled-resources 根目录缓存
 static BUNDLED_RESOURCES_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
 
+/// 启动参数 --data-dir 传入的数据目录
+static LAUNCH_DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 各资源类型在 bundled-resources 下的子目录名
+const BUNDLED_SUB_DIRS: &[&str] = &["prompt-templates", "document-templates"];
+
+/// 与 --data-dir 同级的其他资源类型目录
+///
+/// 例如 --data-dir 为 .../bundled-resources/prompt-templates 时，
+/// document-templates 对应 .../bundled-resources/document-templates。
+fn sibling_data_dir(sub: &str) -> Option<PathBuf> {
+    let dir = LAUNCH_DATA_DIR.get()?.parent()?.join(sub);
+    dir.is_dir().then_some(dir)
+}
+
 /// 在 Tauri setup 中初始化 bundled-resources 路径（优先使用 Tauri resource_dir，跨平台安全）
 fn init_bundled_resources_dir(app: &tauri::App) {
     // 1. 优先使用 Tauri resource_dir（release 模式，跨平台正确解析）
@@ -64,7 +79,8 @@ fn main() {
             "--data-dir" => {
                 if let Some(dir) = args.get(i + 1) {
                     eprintln!("[DEBUG] 接收 --data-dir: {}", dir);
-                    data_dir_state.set(dir.clone());
+                    data_dir_state.init(dir.clone());
+                    let _ = LAUNCH_DATA_DIR.set(PathBuf::from(dir));
                     i += 1;
                 }
             }
@@ -89,8 +105,20 @@ fn main() {
         .plugin(tauri_plugin_fs::init())
         .manage(data_dir_state)
         .manage(resource_type_state)
+        .manage(aidocplus_manager_rust::tasks::TaskRegistry::default())
+        .manage(aidocplus_manager_rust::tasks::RequestRegistry::default())
         .setup(|app| {
             init_bundled_resources_dir(app);
+            // bundled-resources 只读资源同样允许管理器访问
+            if let Some(Some(dir)) = BUNDLED_RESOURCES_DIR.get() {
+                app.state::<DataDirState>().1.add_root(dir);
+            }
+            // 同级的其他资源类型目录同样允许切换过去
+            for sub in BUNDLED_SUB_DIRS {
+                if let Some(dir) = sibling_data_dir(sub) {
+                    app.state::<DataDirState>().1.add_root(&dir);
+                }
+            }
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -99,11 +127,19 @@ fn main() {
             cmd_get_bundled_sub_dir,
             // 复用所有管理器命令
             aidocplus_manager_rust::commands::cmd_get_data_dir,
+            aidocplus_manager_rust::commands::cmd_set_data_dir,
+            aidocplus_manager_rust::commands::cmd_choose_export_path,
+            aidocplus_manager_rust::commands::cmd_choose_import_file,
+            aidocplus_manager_rust::commands::cmd_choose_folder,
             aidocplus_manager_rust::commands::cmd_scan_resources,
             aidocplus_manager_rust::commands::cmd_read_manifest,
             aidocplus_manager_rust::commands::cmd_save_manifest,
             aidocplus_manager_rust::commands::cmd_create_resource,
+            aidocplus_manager_rust::commands::cmd_generate_resource_id,
+            aidocplus_manager_rust::commands::cmd_validate_resource_id,
             aidocplus_manager_rust::commands::cmd_delete_resource,
+            aidocplus_manager_rust::commands::cmd_duplicate_resource,
+            aidocplus_manager_rust::commands::cmd_rename_resource_id,
             aidocplus_manager_rust::commands::cmd_batch_delete_resources,
             aidocplus_manager_rust::commands::cmd_reorder_resources,
             aidocplus_manager_rust::commands::cmd_reindex_all_orders,
@@ -111,27 +147,72 @@ fn main() {
             aidocplus_manager_rust::commands::cmd_save_content_file,
             aidocplus_manager_rust::commands::cmd_read_meta,
             aidocplus_manager_rust::commands::cmd_save_meta,
+            aidocplus_manager_rust::commands::cmd_rename_category,
+            aidocplus_manager_rust::commands::cmd_delete_category,
+            aidocplus_manager_rust::commands::cmd_merge_categories,
+            aidocplus_manager_rust::commands::cmd_reorder_categories,
             aidocplus_manager_rust::commands::cmd_export_resources,
+            aidocplus_manager_rust::commands::cmd_cancel_export,
             aidocplus_manager_rust::commands::cmd_import_resources,
+            aidocplus_manager_rust::commands::cmd_export_json_templates,
+            aidocplus_manager_rust::commands::cmd_import_json_templates,
+            aidocplus_manager_rust::commands::cmd_inspect_package,
+            aidocplus_manager_rust::commands::cmd_export_bundle,
+            aidocplus_manager_rust::commands::cmd_import_bundle,
+            aidocplus_manager_rust::commands::cmd_export_json_templates_bundle,
+            aidocplus_manager_rust::commands::cmd_import_json_templates_bundle,
+            aidocplus_manager_rust::commands::cmd_import_markdown,
+            aidocplus_manager_rust::commands::cmd_import_json_templates_markdown,
+            aidocplus_manager_rust::commands::cmd_export_json_templates_markdown,
+            aidocplus_manager_rust::commands::cmd_export_sheet,
+            aidocplus_manager_rust::commands::cmd_import_sheet,
+            aidocplus_manager_rust::commands::cmd_export_json_templates_sheet,
+            aidocplus_manager_rust::commands::cmd_import_json_templates_sheet,
             aidocplus_manager_rust::commands::cmd_batch_set_enabled,
             aidocplus_manager_rust::commands::cmd_batch_move_category,
             aidocplus_manager_rust::commands::cmd_ai_generate,
             aidocplus_manager_rust::commands::cmd_ai_generate_stream,
+            aidocplus_manager_rust::commands::cmd_ai_generate_with_service,
+            aidocplus_manager_rust::commands::cmd_ai_generate_stream_with_service,
+            aidocplus_manager_rust::commands::cmd_ai_cancel,
+            aidocplus_manager_rust::commands::cmd_ai_test_service,
+            aidocplus_manager_rust::commands::cmd_ai_list_models,
             aidocplus_manager_rust::commands::cmd_run_build_script,
             aidocplus_manager_rust::commands::cmd_load_ai_config,
             aidocplus_manager_rust::commands::cmd_save_ai_config,
             aidocplus_manager_rust::commands::cmd_load_shared_ai_services,
             aidocplus_manager_rust::commands::cmd_load_local_ai_services,
             aidocplus_manager_rust::commands::cmd_save_local_ai_services,
+            aidocplus_manager_rust::commands::cmd_export_ai_services,
+            aidocplus_manager_rust::commands::cmd_import_ai_services,
+            aidocplus_manager_rust::commands::cmd_load_ai_network_settings,
+            aidocplus_manager_rust::commands::cmd_save_ai_network_settings,
+            aidocplus_manager_rust::commands::cmd_ai_usage_summary,
+            aidocplus_manager_rust::commands::cmd_load_ai_price_table,
+            aidocplus_manager_rust::commands::cmd_save_ai_price_table,
+            aidocplus_manager_rust::commands::cmd_load_ai_limits,
+            aidocplus_manager_rust::commands::cmd_save_ai_limits,
+            aidocplus_manager_rust::commands::cmd_ai_budget_usage,
+            aidocplus_manager_rust::commands::cmd_ai_parse_output,
+            aidocplus_manager_rust::commands::cmd_ai_key_status,
+            aidocplus_manager_rust::commands::cmd_ai_unlock_keys,
+            aidocplus_manager_rust::commands::cmd_ai_lock_keys,
+            aidocplus_manager_rust::commands::cmd_ai_set_passphrase,
             aidocplus_manager_rust::commands::cmd_scan_json_resources,
             aidocplus_manager_rust::commands::cmd_read_json_categories,
             aidocplus_manager_rust::commands::cmd_read_json_template,
             aidocplus_manager_rust::commands::cmd_save_json_template,
             aidocplus_manager_rust::commands::cmd_create_json_template,
             aidocplus_manager_rust::commands::cmd_delete_json_template,
+            aidocplus_manager_rust::commands::cmd_duplicate_json_template,
+            aidocplus_manager_rust::commands::cmd_rename_json_template_id,
             aidocplus_manager_rust::commands::cmd_batch_delete_json_templates,
             aidocplus_manager_rust::commands::cmd_move_json_template,
             aidocplus_manager_rust::commands::cmd_save_json_category,
+            aidocplus_manager_rust::commands::cmd_rename_json_category,
+            aidocplus_manager_rust::commands::cmd_delete_json_category,
+            aidocplus_manager_rust::commands::cmd_merge_json_categories,
+            aidocplus_manager_rust::commands::cmd_reorder_json_categories,
         ])
         .run(tauri::generate_context!())
         .expect("资源管理器启动失败");
@@ -151,16 +232,17 @@ fn cmd_get_home_dir() -> Result<String, String> {
         .ok_or_else(|| "无法获取用户主目录".to_string())
 }
 
+/// 资源类型的数据目录：优先使用 bundled-resources 下的子目录，找不到时使用 --data-dir 的同级目录
 #[tauri::command]
 fn cmd_get_bundled_sub_dir(sub: String) -> Option<String> {
-    let bundled = BUNDLED_RESOURCES_DIR.get()
-        .unwrap_or(&None);
-    bundled.as_ref().and_then(|b| {
-        let path = b.join(&sub);
-        if path.exists() {
-            Some(path.to_string_lossy().to_string())
-        } else {
-            None
-        }
-    })
+    if !BUNDLED_SUB_DIRS.contains(&sub.as_str()) {
+        return None;
+    }
+    let bundled = BUNDLED_RESOURCES_DIR.get().unwrap_or(&None);
+    bundled
+        .as_ref()
+        .map(|b| b.join(&sub))
+        .filter(|path| path.exists())
+        .or_else(|| sibling_data_dir(&sub))
+        .map(|path| path.to_string_lossy().to_string())
 }
@@ -3,7 +3,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { ManagerApp } from '@aidocplus/manager-ui';
 import { ALL_RESOURCE_TYPES, type ResourceTypeKey, type ResourceTypeMeta } from './configs';
 
-// 资源类型 → bundled-resources 子目录名
+// 资源类型 → bundled-resources 子目录名（需与后端 BUNDLED_SUB_DIRS 一致）
 const BUNDLED_SUB_DIRS: Record<string, string> = {
   'prompt-templates': 'prompt-templates',
   'doc-templates': 'document-templates',
@@ -60,19 +60,10 @@ export function App() {
       if (meta.key === initialResourceType && externalDataDir) {
         config.defaultDataDir = externalDataDir;
       }
-      // 其他类型：使用 bundled-resources 路径
+      // 其他类型：使用 bundled-resources 路径（找不到时后端返回 --data-dir 的同级目录）
       else if (bundledDirs[meta.key]) {
         config.defaultDataDir = bundledDirs[meta.key];
       }
-      // fallback：从 externalDataDir 推导兄弟资源类型的路径
-      // 例如 externalDataDir = .../bundled-resources/prompt-templates
-      // 则 doc-templates 的路径 = .../bundled-resources/document-templates
-      else if (externalDataDir && BUNDLED_SUB_DIRS[meta.key]) {
-        // 跨平台：同时匹配 / 和 \（Windows 路径使用反斜杠）
-        const sep = externalDataDir.includes('\\') ? '\\' : '/';
-        const parentDir = externalDataDir.replace(/[/\\][^/\\]+[/\\]?$/, '');
-        config.defaultDataDir = `${parentDir}${sep}${BUNDLED_SUB_DIRS[meta.key]}`;
-      }
 
       return config;
     },
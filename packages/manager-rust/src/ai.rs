@@ -1,47 +1,47 @@
+use crate::ai_provider::{
+    AICompletion, ProviderKind, ProviderRequest, StreamEvent, StreamFraming, TokenUsage,
+};
+use crate::ai_limits;
+use crate::ai_usage::{self, UsageContext, UsageOutcome, UsageRecord};
+use crate::http_client;
+use crate::sse::{LineDecoder, SseDecoder};
 use crate::types::AIServiceConfig;
-use serde_json::json;
-use tauri::{Emitter, Window};
+use serde::Serialize;
+use std::time::Instant;
+use tokio::sync::Notify;
 
-/// 构建请求体（与主程序保持一致，使用 json! 宏动态构建）
-fn build_request_body(
-    config: &AIServiceConfig,
-    system_prompt: &str,
-    user_prompt: &str,
-    stream: bool,
-) -> serde_json::Value {
-    let mut body = json!({
-        "messages": [
-            { "role": "system", "content": system_prompt },
-            { "role": "user", "content": user_prompt }
-        ],
-        "model": config.model,
-        "temperature": config.temperature as f64,
-        "stream": stream
-    });
-    if config.max_tokens > 0 {
-        body["max_tokens"] = json!(config.max_tokens);
-    }
-    body
+/// 按提供商协议构建 HTTP 请求，body 为 Null 时发送 GET
+pub(crate) fn http_request(
+    client: &reqwest::Client,
+    request: &ProviderRequest,
+) -> reqwest::RequestBuilder {
+    let mut builder = if request.body.is_null() {
+        client.get(&request.url)
+    } else {
+        client.post(&request.url).body(request.body.to_string())
+    };
+    for (name, value) in &request.headers {
+        builder = builder.header(*name, value);
+    }
+    builder
 }
 
-/// 调用 AI API 生成资源（非流式）
-pub async fn ai_generate(
+/// 用共享客户端发送请求，连接失败、超时、429 与 5xx 时按网络设置重试
+async fn send_request(request: &ProviderRequest) -> Result<reqwest::Response, String> {
+    let (settings, client) = http_client::shared_client()?;
+    http_client::send_with_retry(&settings, || http_request(&client, request)).await
+}
+
+/// 调用 AI API 生成资源（非流式），返回内容与用量
+pub async fn ai_complete(
     config: &AIServiceConfig,
     system_prompt: &str,
     user_prompt: &str,
-) -> Result<String, String> {
-    let client = reqwest::Client::new();
-    let url = format!("{}/chat/completions", config.base_url.trim_end_matches('/'));
-    let request_body = build_request_body(config, system_prompt, user_prompt, false);
-
-    let response = client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", config.api_key))
-        .body(request_body.to_string())
-        .send()
-        .await
-        .map_err(|e| format!("AI 请求失败: {}", e))?;
+) -> Result<AICompletion, String> {
+    let provider = ProviderKind::from_config(config);
+    let request = provider.build_request(config, system_prompt, user_prompt, false);
+
+    let response = send_request(&request).await?;
 
     if !response.status().is_success() {
         let status = response.status();
@@ -54,108 +54,490 @@ pub async fn ai_generate(
         .await
         .map_err(|e| format!("解析 AI 响应失败: {}", e))?;
 
-    resp.get("choices")
-        .and_then(|c| c.get(0))
-        .and_then(|c| c.get("message"))
-        .and_then(|m| m.get("content"))
-        .and_then(|c| c.as_str())
-        .map(|s| s.to_string())
-        .ok_or_else(|| "AI 未返回内容".to_string())
+    provider.parse_response(&resp)
 }
 
-/// 调用 AI API 生成资源（流式 SSE）
-pub async fn ai_generate_stream(
-    window: Window,
+/// 非流式生成：检查预算与限速，可取消，并记录用量
+///
+/// `cancel` 被通知时（包括限速排队期间）丢弃 HTTP 请求并返回 `[CANCELLED]` 开头的错误。
+pub async fn ai_complete_tracked(
+    config: &AIServiceConfig,
+    context: &UsageContext,
+    system_prompt: &str,
+    user_prompt: &str,
+    cancel: &Notify,
+) -> Result<AICompletion, String> {
+    let estimate = ai_limits::UsageEstimate::new(config, system_prompt, user_prompt);
+    let mut admission = None;
+    let mut started = None;
+    let result = tokio::select! {
+        result = async {
+            admission = Some(ai_limits::admit(context, &estimate).await?);
+            started = Some(Instant::now());
+            ai_complete(config, system_prompt, user_prompt).await
+        } => Some(result),
+        _ = cancel.notified() => None,
+    };
+
+    // 未通过预算检查或排队期间取消的请求没有发出，不记录用量
+    if let Some(started) = started {
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let (usage, outcome, error) = match &result {
+            Some(Ok(completion)) => (completion.usage.as_ref(), UsageOutcome::Success, None),
+            Some(Err(e)) => (None, UsageOutcome::Error, Some(e.as_str())),
+            None => (None, UsageOutcome::Cancelled, None),
+        };
+        ai_usage::record(&UsageRecord::new(
+            config, context, false, usage, latency_ms, outcome, error,
+        ));
+    }
+    // 用量已记录，释放预算预留
+    drop(admission);
+    result.unwrap_or_else(|| Err("[CANCELLED] AI 请求已取消".to_string()))
+}
+
+/// 调用 AI API 生成资源（非流式）
+pub async fn ai_generate(
     config: &AIServiceConfig,
     system_prompt: &str,
     user_prompt: &str,
-    event_name: &str,
 ) -> Result<String, String> {
-    let client = reqwest::Client::new();
-    let url = format!("{}/chat/completions", config.base_url.trim_end_matches('/'));
-    let request_body = build_request_body(config, system_prompt, user_prompt, true);
+    ai_complete(config, system_prompt, user_prompt)
+        .await
+        .map(|c| c.content)
+}
 
-    eprintln!("[AI Stream] 请求 URL: {}", url);
+// ============================================================
+// 流式生成
+// ============================================================
+
+/// 流式生成事件，通过每个请求独立的 Channel 发送给前端
+///
+/// 除 `delta` 外的三种事件都是最终事件，`content` 为已收到的全部内容（取消或出错时为部分内容）。
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AIStreamEvent {
+    Delta {
+        content: String,
+    },
+    Done {
+        content: String,
+        usage: Option<TokenUsage>,
+    },
+    Cancelled {
+        content: String,
+        usage: Option<TokenUsage>,
+    },
+    Error {
+        content: String,
+        error: String,
+    },
+}
+
+/// 调用 AI API 生成资源（流式 SSE / NDJSON）
+///
+/// 每个增量通过 `on_delta` 回调，返回最终事件；`cancel` 被通知时立即丢弃 HTTP 连接并返回
+/// `Cancelled`。发送前检查预算与限速（排队期间同样可以取消），结束时按最终事件记录用量。
+pub async fn ai_generate_stream(
+    config: &AIServiceConfig,
+    context: &UsageContext,
+    system_prompt: &str,
+    user_prompt: &str,
+    cancel: &Notify,
+    on_delta: &mut (dyn FnMut(&str) + Send),
+) -> AIStreamEvent {
+    let mut content = String::new();
+    let mut usage = TokenUsage::default();
+    let estimate = ai_limits::UsageEstimate::new(config, system_prompt, user_prompt);
+    let mut admission = None;
+    let mut started = None;
+
+    let result = tokio::select! {
+        result = async {
+            admission = Some(ai_limits::admit(context, &estimate).await?);
+            started = Some(Instant::now());
+            read_stream(
+                config,
+                system_prompt,
+                user_prompt,
+                &mut content,
+                &mut usage,
+                on_delta,
+            )
+            .await
+        } => Some(result),
+        _ = cancel.notified() => None,
+    };
+
+    let usage = (!usage.is_empty()).then_some(usage);
+    if let Some(started) = started {
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let (outcome, error) = match &result {
+            Some(Ok(())) => (UsageOutcome::Success, None),
+            Some(Err(e)) => (UsageOutcome::Error, Some(e.as_str())),
+            None => (UsageOutcome::Cancelled, None),
+        };
+        ai_usage::record(&UsageRecord::new(
+            config,
+            context,
+            true,
+            usage.as_ref(),
+            latency_ms,
+            outcome,
+            error,
+        ));
+    }
+    drop(admission);
+
+    match result {
+        Some(Ok(())) => AIStreamEvent::Done { content, usage },
+        Some(Err(error)) => {
+            eprintln!("[AI Stream] {}", error);
+            AIStreamEvent::Error { content, error }
+        }
+        None => {
+            eprintln!("[AI Stream] 请求已取消，已接收 {} 字符", content.chars().count());
+            AIStreamEvent::Cancelled { content, usage }
+        }
+    }
+}
+
+/// 发送流式请求并读取到结束，内容与用量写入调用方持有的缓冲区，以便取消或出错时保留部分结果
+async fn read_stream(
+    config: &AIServiceConfig,
+    system_prompt: &str,
+    user_prompt: &str,
+    full_content: &mut String,
+    usage: &mut TokenUsage,
+    on_delta: &mut (dyn FnMut(&str) + Send),
+) -> Result<(), String> {
+    let provider = ProviderKind::from_config(config);
+    let request = provider.build_request(config, system_prompt, user_prompt, true);
+
+    eprintln!("[AI Stream] 请求 URL: {} ({:?})", request.url, provider);
     eprintln!("[AI Stream] 模型: {}, max_tokens: {}", config.model, config.max_tokens);
     eprintln!("[AI Stream] 正在发送请求...");
 
-    let response = client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", config.api_key))
-        .body(request_body.to_string())
-        .send()
-        .await
-        .map_err(|e| {
-            let err = format!("AI 请求失败: {}", e);
-            eprintln!("[AI Stream] {}", err);
-            err
-        })?;
+    let response = send_request(&request).await?;
 
     eprintln!("[AI Stream] 收到响应，状态码: {}", response.status());
 
     if !response.status().is_success() {
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
-        let err = format!("AI API 返回错误 {}: {}", status, body);
-        eprintln!("[AI Stream] {}", err);
-        return Err(err);
+        return Err(format!("AI API 返回错误 {}: {}", status, body));
     }
 
-    let mut full_content = String::new();
     let mut stream = response.bytes_stream();
 
     use futures_util::StreamExt;
 
-    let mut buffer = String::new();
-
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| format!("读取流失败: {}", e))?;
-        let text = String::from_utf8_lossy(&chunk);
-        buffer.push_str(&text);
+    let mut decoder = FrameDecoder::new(provider.stream_framing());
+    let mut closed = false;
 
-        // 按行处理 SSE 数据
-        while let Some(line_end) = buffer.find('\n') {
-            let line = buffer[..line_end].trim().to_string();
-            buffer = buffer[line_end + 1..].to_string();
-
-            if line.is_empty() || !line.starts_with("data: ") {
-                continue;
+    while !closed {
+        let frames = match stream.next().await {
+            Some(chunk) => {
+                let chunk = chunk.map_err(|e| format!("读取流失败: {}", e))?;
+                decoder.feed(&chunk)
             }
-
-            let data = &line[6..];
-            if data == "[DONE]" {
-                break;
+            // 连接关闭时处理最后一个没有以空行/换行结束的事件
+            None => {
+                closed = true;
+                decoder.finish()
             }
+        };
 
-            if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(data) {
-                if let Some(delta) = parsed
-                    .get("choices")
-                    .and_then(|c| c.get(0))
-                    .and_then(|c| c.get("delta"))
-                    .and_then(|d| d.get("content"))
-                    .and_then(|c| c.as_str())
-                {
-                    full_content.push_str(delta);
-                    let _ = window.emit(
-                        event_name,
-                        serde_json::json!({
-                            "type": "delta",
-                            "content": delta
-                        }),
-                    );
+        for (event_type, data) in frames {
+            for event in provider.parse_stream_event(event_type.as_deref(), &data) {
+                match event {
+                    StreamEvent::Delta(delta) => {
+                        full_content.push_str(&delta);
+                        on_delta(&delta);
+                    }
+                    StreamEvent::Usage(u) => usage.merge(&u),
+                    // 提供商已声明结束，不再等待连接关闭
+                    StreamEvent::Done => return Ok(()),
+                    StreamEvent::Error(err) => return Err(format!("AI API 返回错误: {}", err)),
                 }
             }
         }
     }
 
-    let _ = window.emit(
-        event_name,
-        serde_json::json!({
-            "type": "done",
-            "content": full_content
-        }),
-    );
+    Ok(())
+}
+
+/// 按提供商的分帧方式把字节流切分为 `(event, data)`
+enum FrameDecoder {
+    Sse(SseDecoder),
+    Ndjson(LineDecoder),
+}
+
+type Frame = (Option<String>, String);
+
+impl FrameDecoder {
+    fn new(framing: StreamFraming) -> Self {
+        match framing {
+            StreamFraming::Sse => Self::Sse(SseDecoder::new()),
+            StreamFraming::Ndjson => Self::Ndjson(LineDecoder::new()),
+        }
+    }
+
+    fn feed(&mut self, chunk: &[u8]) -> Vec<Frame> {
+        match self {
+            Self::Sse(decoder) => decoder
+                .feed(chunk)
+                .into_iter()
+                .map(|e| (e.event, e.data))
+                .collect(),
+            Self::Ndjson(decoder) => decoder.feed(chunk).into_iter().map(|l| (None, l)).collect(),
+        }
+    }
+
+    fn finish(&mut self) -> Vec<Frame> {
+        match self {
+            Self::Sse(decoder) => decoder
+                .finish()
+                .into_iter()
+                .map(|e| (e.event, e.data))
+                .collect(),
+            Self::Ndjson(decoder) => decoder.finish().into_iter().map(|l| (None, l)).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config(provider: &str, base_url: String, api_key: &str) -> AIServiceConfig {
+        AIServiceConfig {
+            provider: provider.to_string(),
+            base_url,
+            api_key: api_key.to_string(),
+            model: "test-model".to_string(),
+            max_tokens: 0,
+            temperature: 0.2,
+        }
+    }
+
+    type StreamOutput = (String, Vec<String>, TokenUsage);
+
+    async fn stream(config: &AIServiceConfig) -> Result<StreamOutput, String> {
+        let mut content = String::new();
+        let mut usage = TokenUsage::default();
+        let mut deltas = Vec::new();
+        let mut on_delta = |d: &str| deltas.push(d.to_string());
+        read_stream(config, "sys", "user", &mut content, &mut usage, &mut on_delta).await?;
+        Ok((content, deltas, usage))
+    }
 
-    Ok(full_content)
+    fn sse(body: &str, content_type: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_raw(body.as_bytes().to_vec(), content_type)
+    }
+
+    #[tokio::test]
+    async fn complete_sends_provider_auth_headers() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer sk-openai"))
+            .and(body_partial_json(json!({ "model": "test-model", "stream": false })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": "openai" } }],
+                "usage": { "prompt_tokens": 3, "completion_tokens": 1 }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .and(header("x-api-key", "sk-ant"))
+            .and(header("anthropic-version", "2023-06-01"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "content": [{ "type": "text", "text": "anthropic" }],
+                "usage": { "input_tokens": 5, "output_tokens": 2 }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1beta/models/test-model:generateContent"))
+            .and(header("x-goog-api-key", "sk-gem"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "candidates": [{ "content": { "parts": [{ "text": "gemini" }] } }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "message": { "content": "ollama" }, "done": true
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let uri = server.uri();
+        let cases = [
+            ("openai", format!("{}/v1", uri), "sk-openai", "openai"),
+            ("anthropic", format!("{}/v1/", uri), "sk-ant", "anthropic"),
+            ("gemini", format!("{}/v1beta", uri), "sk-gem", "gemini"),
+            ("ollama", format!("{}/api", uri), "", "ollama"),
+        ];
+        for (provider, base_url, key, expected) in cases {
+            let completion = ai_complete(&config(provider, base_url, key), "sys", "user")
+                .await
+                .unwrap_or_else(|e| panic!("{}: {}", provider, e));
+            assert_eq!(completion.content, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn complete_reports_http_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("invalid key"))
+            .mount(&server)
+            .await;
+        let cfg = config("openai", format!("{}/v1", server.uri()), "bad");
+        let err = ai_complete(&cfg, "sys", "user").await.unwrap_err();
+        assert!(err.contains("401") && err.contains("invalid key"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn stream_openai_sse() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            ": keep-alive\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\r\n\r\n",
+            "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":4,\"completion_tokens\":2}}\n\n",
+            "data: [DONE]\n\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer sk-openai"))
+            .and(body_partial_json(json!({
+                "stream": true,
+                "stream_options": { "include_usage": true }
+            })))
+            .respond_with(sse(body, "text/event-stream"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cfg = config("openai", format!("{}/v1", server.uri()), "sk-openai");
+        let (content, deltas, usage) = stream(&cfg).await.unwrap();
+        assert_eq!(content, "Hello");
+        assert_eq!(deltas, vec!["Hel", "lo"]);
+        assert_eq!(usage.input_tokens, Some(4));
+        assert_eq!(usage.output_tokens, Some(2));
+    }
+
+    #[tokio::test]
+    async fn stream_anthropic_sse() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",",
+            "\"message\":{\"usage\":{\"input_tokens\":7,\"output_tokens\":1}}}\n\n",
+            "event: ping\ndata: {\"type\":\"ping\"}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",",
+            "\"delta\":{\"type\":\"text_delta\",\"text\":\"你好\"}}\n\n",
+            "event: message_delta\n",
+            "data: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":3}}\n\n",
+            "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .and(header("x-api-key", "sk-ant"))
+            .and(header("anthropic-version", "2023-06-01"))
+            .respond_with(sse(body, "text/event-stream"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cfg = config("anthropic", format!("{}/v1", server.uri()), "sk-ant");
+        let (content, _, usage) = stream(&cfg).await.unwrap();
+        assert_eq!(content, "你好");
+        assert_eq!(usage.input_tokens, Some(7));
+        assert_eq!(usage.output_tokens, Some(3));
+    }
+
+    #[tokio::test]
+    async fn stream_gemini_sse_without_trailing_blank_line() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"a\"}]}}]}\r\n\r\n",
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"b\"}]}}],",
+            "\"usageMetadata\":{\"promptTokenCount\":2,\"candidatesTokenCount\":2}}",
+        );
+        Mock::given(method("POST"))
+            .and(path("/v1beta/models/test-model:streamGenerateContent"))
+            .and(header("x-goog-api-key", "sk-gem"))
+            .respond_with(sse(body, "text/event-stream"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cfg = config("gemini", format!("{}/v1beta", server.uri()), "sk-gem");
+        let (content, _, usage) = stream(&cfg).await.unwrap();
+        assert_eq!(content, "ab");
+        assert_eq!(usage.output_tokens, Some(2));
+    }
+
+    #[tokio::test]
+    async fn stream_ollama_ndjson() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "{\"message\":{\"content\":\"x\"},\"done\":false}\n",
+            "{\"message\":{\"content\":\"y\"},\"done\":false}\n",
+            "{\"message\":{\"content\":\"\"},\"done\":true,",
+            "\"prompt_eval_count\":1,\"eval_count\":2}\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(header("authorization", "Bearer sk-ollama"))
+            .respond_with(sse(body, "application/x-ndjson"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cfg = config("ollama", server.uri(), "sk-ollama");
+        let (content, deltas, usage) = stream(&cfg).await.unwrap();
+        assert_eq!(content, "xy");
+        assert_eq!(deltas.len(), 2);
+        assert_eq!(usage.output_tokens, Some(2));
+    }
+
+    #[tokio::test]
+    async fn stream_reports_error_events_and_keeps_partial_content() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"part\"}}]}\n\n",
+            "data: {\"error\":{\"message\":\"rate limited\"}}\n\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(sse(body, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let cfg = config("openai", format!("{}/v1", server.uri()), "k");
+        let mut content = String::new();
+        let mut usage = TokenUsage::default();
+        let mut on_delta = |_: &str| {};
+        let err = read_stream(&cfg, "sys", "user", &mut content, &mut usage, &mut on_delta)
+            .await
+            .unwrap_err();
+        assert!(err.contains("rate limited"), "{}", err);
+        assert_eq!(content, "part");
+    }
 }
@@ -0,0 +1,352 @@
+//! AI 服务连接测试与模型列表
+//!
+//! 连接测试发送一个最小的生成请求，把失败原因归类为可操作的提示，
+//! 避免用户在生成对话框中途才发现 Base URL 或 Key 配置错误。
+
+use crate::ai::http_request;
+use crate::ai_provider::ProviderKind;
+use crate::http_client;
+use crate::types::AIServiceConfig;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// 连接测试与模型列表请求的超时
+const TEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 测试请求的输出上限，只需确认模型能够返回内容
+const TEST_MAX_TOKENS: u32 = 16;
+
+/// 错误详情（响应体）保留的最大字符数
+const MAX_DETAIL_CHARS: usize = 500;
+
+// ============================================================
+// 类型定义
+// ============================================================
+
+/// 失败类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AIFailureKind {
+    /// Base URL 格式错误
+    InvalidUrl,
+    /// 域名解析失败
+    Dns,
+    /// 无法建立连接
+    Connect,
+    /// TLS 握手或证书校验失败
+    Tls,
+    Timeout,
+    /// 401
+    Unauthorized,
+    /// 403
+    Forbidden,
+    /// 404，多为 Base URL 路径错误
+    NotFound,
+    /// 模型不存在或无权使用
+    ModelNotFound,
+    /// 429 / 402，请求过于频繁或额度不足
+    RateLimited,
+    /// 其他 4xx
+    BadRequest,
+    /// 5xx
+    Server,
+    /// 网络设置无效（代理地址或 CA 证书）
+    ClientConfig,
+    /// 返回的不是预期格式（例如 Base URL 指向了网页）
+    InvalidResponse,
+}
+
+/// 一次失败的分类结果
+#[derive(Debug, Clone)]
+pub struct AIFailure {
+    pub kind: AIFailureKind,
+    pub status: Option<u16>,
+    pub detail: String,
+}
+
+/// 连接测试结果
+#[derive(Debug, Clone, Serialize)]
+pub struct AIServiceTestResult {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<AIFailureKind>,
+    /// 面向用户的结果说明（失败时给出处理建议）
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// 原始错误信息（截断后的响应体或网络错误）
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub detail: String,
+    #[serde(rename = "latencyMs")]
+    pub latency_ms: u64,
+}
+
+impl AIFailure {
+    fn new(kind: AIFailureKind, status: Option<u16>, detail: impl Into<String>) -> Self {
+        let detail: String = detail.into();
+        let detail = if detail.chars().count() > MAX_DETAIL_CHARS {
+            let truncated: String = detail.chars().take(MAX_DETAIL_CHARS).collect();
+            format!("{}…", truncated)
+        } else {
+            detail
+        };
+        Self { kind, status, detail }
+    }
+
+    /// 面向用户的处理建议
+    pub fn message(&self, model: &str) -> String {
+        let status = self.status.map(|s| s.to_string()).unwrap_or_default();
+        match self.kind {
+            AIFailureKind::InvalidUrl => {
+                "API 地址无效，请检查 Base URL 格式（需以 http:// 或 https:// 开头）".to_string()
+            }
+            AIFailureKind::Dns => "无法解析服务器域名，请检查 Base URL 拼写与网络连接".to_string(),
+            AIFailureKind::Connect => {
+                "无法连接到服务器，请检查 Base URL、端口以及网络或代理设置".to_string()
+            }
+            AIFailureKind::Tls => {
+                "TLS 握手或证书校验失败，请确认地址的 http/https 协议正确，自签名证书需配置 CA"
+                    .to_string()
+            }
+            AIFailureKind::Timeout => "请求超时，服务器响应过慢或网络不稳定".to_string(),
+            AIFailureKind::Unauthorized => "API Key 无效或已过期（401），请检查 Key".to_string(),
+            AIFailureKind::Forbidden => {
+                "没有访问权限（403），请确认 Key 的权限、账户状态或地区限制".to_string()
+            }
+            AIFailureKind::NotFound => {
+                "接口地址不存在（404），Base URL 可能填写错误（例如缺少或多写了 /v1）".to_string()
+            }
+            AIFailureKind::ModelNotFound => {
+                format!("模型「{}」不存在或当前 Key 无权使用，请检查模型名称", model)
+            }
+            AIFailureKind::RateLimited => {
+                format!("请求过于频繁或额度已用尽（{}），请稍后重试或检查账户余额", status)
+            }
+            AIFailureKind::BadRequest => format!("请求被拒绝（{}），请检查模型名称与参数", status),
+            AIFailureKind::Server => format!("服务端错误（{}），请稍后重试", status),
+            AIFailureKind::ClientConfig => "网络设置无效，请检查代理地址与 CA 证书".to_string(),
+            AIFailureKind::InvalidResponse => {
+                "服务器返回的不是有效的 AI 接口响应，Base URL 可能指向了网页而非 API".to_string()
+            }
+        }
+    }
+
+    fn into_error(self, model: &str) -> String {
+        if self.detail.is_empty() {
+            self.message(model)
+        } else {
+            format!("{}\n{}", self.message(model), self.detail)
+        }
+    }
+}
+
+// ============================================================
+// 失败分类
+// ============================================================
+
+/// 按网络错误分类
+pub fn classify_transport_error(err: &reqwest::Error) -> AIFailure {
+    // reqwest 的错误信息只有顶层描述，具体原因在 source 链中
+    let mut chain = err.to_string();
+    let mut source = std::error::Error::source(err);
+    while let Some(inner) = source {
+        chain.push_str(": ");
+        chain.push_str(&inner.to_string());
+        source = inner.source();
+    }
+    let lower = chain.to_lowercase();
+    let contains_any = |needles: &[&str]| needles.iter().any(|n| lower.contains(n));
+
+    let kind = if err.is_builder() || contains_any(&["relative url", "invalid url"]) {
+        AIFailureKind::InvalidUrl
+    } else if err.is_timeout() {
+        AIFailureKind::Timeout
+    } else if contains_any(&[
+        "dns error",
+        "failed to lookup address",
+        "name or service not known",
+        "nodename nor servname",
+        "no such host",
+    ]) {
+        AIFailureKind::Dns
+    } else if contains_any(&["certificate", "tls", "ssl", "handshake"]) {
+        AIFailureKind::Tls
+    } else {
+        AIFailureKind::Connect
+    };
+    AIFailure::new(kind, None, chain)
+}
+
+/// 按 HTTP 状态码与响应体分类
+pub fn classify_status(status: u16, body: &str) -> AIFailure {
+    let lower = body.to_lowercase();
+    let mentions_model = lower.contains("model")
+        && [
+            "not found",
+            "not_found",
+            "does not exist",
+            "not exist",
+            "invalid model",
+            "unknown model",
+            "not supported",
+            "no access",
+        ]
+        .iter()
+        .any(|n| lower.contains(n));
+
+    let kind = match status {
+        401 => AIFailureKind::Unauthorized,
+        403 => AIFailureKind::Forbidden,
+        402 | 429 => AIFailureKind::RateLimited,
+        400 | 404 | 422 if mentions_model => AIFailureKind::ModelNotFound,
+        404 | 405 => AIFailureKind::NotFound,
+        400..=499 => AIFailureKind::BadRequest,
+        _ => AIFailureKind::Server,
+    };
+    AIFailure::new(kind, Some(status), body.trim())
+}
+
+/// 共享客户端（与生成请求相同的代理、证书与连接超时），诊断请求不重试
+fn test_client() -> Result<reqwest::Client, AIFailure> {
+    http_client::shared_client()
+        .map(|(_, client)| client)
+        .map_err(|e| AIFailure::new(AIFailureKind::ClientConfig, None, e))
+}
+
+// ============================================================
+// 连接测试
+// ============================================================
+
+/// 发送最小的生成请求测试服务是否可用
+pub async fn test_service(config: &AIServiceConfig) -> AIServiceTestResult {
+    if config.model.trim().is_empty() {
+        return AIServiceTestResult {
+            ok: false,
+            kind: Some(AIFailureKind::ModelNotFound),
+            message: "请先填写模型名称".to_string(),
+            status: None,
+            detail: String::new(),
+            latency_ms: 0,
+        };
+    }
+    let started = Instant::now();
+    let result = probe(config).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok(()) => AIServiceTestResult {
+            ok: true,
+            kind: None,
+            message: format!("连接成功，模型「{}」可用", config.model),
+            status: None,
+            detail: String::new(),
+            latency_ms,
+        },
+        Err(failure) => AIServiceTestResult {
+            ok: false,
+            kind: Some(failure.kind),
+            message: failure.message(&config.model),
+            status: failure.status,
+            detail: failure.detail,
+            latency_ms,
+        },
+    }
+}
+
+async fn probe(config: &AIServiceConfig) -> Result<(), AIFailure> {
+    let mut config = config.clone();
+    config.max_tokens = TEST_MAX_TOKENS;
+
+    let provider = ProviderKind::from_config(&config);
+    let request = provider.build_request(&config, "Reply with OK.", "ping", false);
+    let client = test_client()?;
+    let response = http_request(&client, &request)
+        .timeout(TEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| classify_transport_error(&e))?;
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| classify_transport_error(&e))?;
+    if !status.is_success() {
+        return Err(classify_status(status.as_u16(), &body));
+    }
+    let value: serde_json::Value = serde_json::from_str(&body)
+        .map_err(|_| AIFailure::new(AIFailureKind::InvalidResponse, None, body.clone()))?;
+    provider
+        .parse_response(&value)
+        .map(|_| ())
+        .map_err(|e| AIFailure::new(AIFailureKind::InvalidResponse, None, e))
+}
+
+// ============================================================
+// 模型列表
+// ============================================================
+
+/// 查询提供商的模型列表
+pub async fn list_models(config: &AIServiceConfig) -> Result<Vec<String>, String> {
+    let provider = ProviderKind::from_config(config);
+    let request = provider.build_models_request(config);
+    let client = test_client().map_err(|f| f.into_error(&config.model))?;
+    let response = http_request(&client, &request)
+        .timeout(TEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| classify_transport_error(&e).into_error(&config.model))?;
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| classify_transport_error(&e).into_error(&config.model))?;
+    if !status.is_success() {
+        return Err(classify_status(status.as_u16(), &body).into_error(&config.model));
+    }
+    let value: serde_json::Value = serde_json::from_str(&body).map_err(|_| {
+        AIFailure::new(AIFailureKind::InvalidResponse, None, body.clone()).into_error(&config.model)
+    })?;
+    provider.parse_models(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use AIFailureKind::*;
+
+    #[test]
+    fn classify_status_maps_status_and_body() {
+        let cases = [
+            (401, r#"{"error": "invalid api key"}"#, Unauthorized),
+            (403, "forbidden", Forbidden),
+            (402, "insufficient balance", RateLimited),
+            (429, "rate limit exceeded", RateLimited),
+            (404, r#"{"error": {"message": "The model `gpt-9` does not exist"}}"#, ModelNotFound),
+            (404, r#"{"error": "model 'llama9' not found"}"#, ModelNotFound),
+            (404, "<html>404 page not found</html>", NotFound),
+            (404, "", NotFound),
+            (405, "method not allowed", NotFound),
+            (400, r#"{"error": {"message": "Invalid model: foo", "type": "invalid_request"}}"#,
+                ModelNotFound),
+            (400, "unknown model requested", ModelNotFound),
+            (422, "model not supported", ModelNotFound),
+            (400, "max_tokens is too large", BadRequest),
+            (400, "the model field is required", BadRequest),
+            (418, "", BadRequest),
+            (500, "internal error: model not found", Server),
+            (502, "bad gateway", Server),
+            (503, "", Server),
+        ];
+        for (status, body, kind) in cases {
+            let failure = classify_status(status, body);
+            assert_eq!(failure.kind, kind, "{} {}", status, body);
+            assert_eq!(failure.status, Some(status));
+        }
+    }
+
+    #[test]
+    fn classify_status_keeps_trimmed_body_as_detail() {
+        assert_eq!(classify_status(500, "  oops \n").detail, "oops");
+    }
+}
@@ -0,0 +1,566 @@
+//! AI 调用预算与限速
+//!
+//! 每个服务可设置每日 / 每月的 token 与费用预算，以及每分钟请求数上限
+//! （`~/.aidocplus/manager-ai-limits.json`）。预算按 [`ai_usage`] 的用量记录加上进行中请求的预留
+//! 统计，本次请求会超出预算时拒绝（调用方确认后可强制继续）；超过每分钟请求数的调用排队等待，
+//! 而不是一起发往提供商。
+
+use crate::ai_services::aidocplus_dir;
+use crate::ai_usage::{self, UsageContext};
+use crate::fs_utils::write_atomic;
+use crate::types::AIServiceConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 预算已用完时错误信息的前缀，前端据此提示用户确认是否强制继续
+pub const BUDGET_ERROR: &str = "[BUDGET]";
+
+/// 限速的时间窗口
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// 每个服务最近一分钟内的请求时间
+static RATE_WINDOWS: Mutex<Option<HashMap<String, VecDeque<Instant>>>> = Mutex::new(None);
+
+/// 已放行、尚未结束的请求所预留的用量
+static IN_FLIGHT: Mutex<Option<InFlight>> = Mutex::new(None);
+
+/// 排队结束后读取用量期间有请求结束时，重新读取的最多次数
+const MAX_USAGE_RELOADS: u32 = 3;
+
+/// 估算输入 token 时每个 token 对应的字节数（中文约 3 字节一个 token，英文偏保守）
+const BYTES_PER_TOKEN: usize = 3;
+
+// ============================================================
+// 类型定义
+// ============================================================
+
+/// 单个服务的预算与限速，为空或 0 表示不限制
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceLimits {
+    /// 服务 ID；为空时作用于直接传入配置（未指定服务）的调用
+    #[serde(rename = "serviceId")]
+    pub service_id: String,
+    #[serde(default, rename = "dailyTokens")]
+    pub daily_tokens: Option<u64>,
+    #[serde(default, rename = "monthlyTokens")]
+    pub monthly_tokens: Option<u64>,
+    /// 按价格表估算的费用上限（价格表的货币单位）
+    #[serde(default, rename = "dailyCost")]
+    pub daily_cost: Option<f64>,
+    #[serde(default, rename = "monthlyCost")]
+    pub monthly_cost: Option<f64>,
+    #[serde(default, rename = "requestsPerMinute")]
+    pub requests_per_minute: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AILimits {
+    #[serde(default)]
+    pub services: Vec<ServiceLimits>,
+}
+
+/// 请求发出前对本次用量的估算，用于预算检查与预留
+#[derive(Debug, Clone, Default)]
+pub struct UsageEstimate {
+    pub model: String,
+    pub input_tokens: u64,
+    /// 输出上限（max_tokens）；为 0 时由模型决定，无法预估
+    pub output_tokens: u64,
+}
+
+/// 预留的用量
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Reservation {
+    requests: u32,
+    tokens: u64,
+    cost: f64,
+}
+
+/// 进行中请求的预留状态
+#[derive(Debug, Default)]
+struct InFlight {
+    /// 按服务 ID 汇总的预留，没有进行中的请求时不保留条目
+    reserved: HashMap<String, Reservation>,
+    /// 预留的释放次数；释放前用量记录已写入，次数变化说明此前读取的用量可能已过期
+    releases: u64,
+}
+
+/// 预算检查所需的已用量与币种（读取用量记录与价格表，不持有任何锁）
+struct BudgetSnapshot {
+    usage: BudgetUsage,
+    currency: String,
+}
+
+impl BudgetSnapshot {
+    fn load(service_id: &str) -> Result<Self, String> {
+        Ok(Self {
+            usage: budget_usage(service_id)?,
+            currency: ai_usage::load_price_table()?.currency,
+        })
+    }
+}
+
+/// 已放行请求的预算预留，drop 时释放
+///
+/// 调用方应在写入用量记录之后再释放，避免预留释放与记录写入之间的请求漏算这部分用量。
+#[must_use]
+#[derive(Debug)]
+pub struct Admission {
+    service_id: String,
+    reservation: Reservation,
+}
+
+/// 服务当前的预算使用情况
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BudgetUsage {
+    #[serde(rename = "serviceId")]
+    pub service_id: String,
+    #[serde(rename = "dailyTokens")]
+    pub daily_tokens: u64,
+    #[serde(rename = "monthlyTokens")]
+    pub monthly_tokens: u64,
+    #[serde(rename = "dailyCost")]
+    pub daily_cost: f64,
+    #[serde(rename = "monthlyCost")]
+    pub monthly_cost: f64,
+}
+
+// ============================================================
+// 设置读写
+// ============================================================
+
+/// 预算与限速设置（~/.aidocplus/manager-ai-limits.json）
+pub fn limits_path() -> Result<PathBuf, String> {
+    Ok(aidocplus_dir()?.join("manager-ai-limits.json"))
+}
+
+pub fn load_limits() -> Result<AILimits, String> {
+    let path = limits_path()?;
+    if !path.exists() {
+        return Ok(AILimits::default());
+    }
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("读取 AI 预算设置失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析 AI 预算设置失败: {}", e))
+}
+
+pub fn save_limits(limits: &AILimits) -> Result<(), String> {
+    for item in &limits.services {
+        let costs = [item.daily_cost, item.monthly_cost];
+        if costs.iter().flatten().any(|c| !c.is_finite() || *c < 0.0) {
+            return Err(format!("服务「{}」的费用预算无效", item.service_id));
+        }
+    }
+    let path = limits_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    let content = serde_json::to_string_pretty(limits)
+        .map_err(|e| format!("序列化 AI 预算设置失败: {}", e))?;
+    write_atomic(&path, &content).map_err(|e| format!("写入 AI 预算设置失败: {}", e))
+}
+
+impl AILimits {
+    fn find(&self, service_id: &str) -> Option<&ServiceLimits> {
+        self.services.iter().find(|s| s.service_id == service_id)
+    }
+}
+
+impl ServiceLimits {
+    fn has_budget(&self) -> bool {
+        self.daily_tokens.is_some_and(|v| v > 0)
+            || self.monthly_tokens.is_some_and(|v| v > 0)
+            || self.daily_cost.is_some_and(|v| v > 0.0)
+            || self.monthly_cost.is_some_and(|v| v > 0.0)
+    }
+}
+
+// ============================================================
+// 预算
+// ============================================================
+
+/// 统计服务今日与本月（本地时间）已用的 token 与估算费用
+pub fn budget_usage(service_id: &str) -> Result<BudgetUsage, String> {
+    let records = ai_usage::load_records()?;
+    let prices = ai_usage::load_price_table()?;
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let month = &today[..7];
+
+    let mut usage = BudgetUsage {
+        service_id: service_id.to_string(),
+        ..Default::default()
+    };
+    for record in records.iter().filter(|r| r.service_id == service_id) {
+        if !record.timestamp.starts_with(month) {
+            continue;
+        }
+        let input = record.input_tokens.unwrap_or(0) as u64;
+        let output = record.output_tokens.unwrap_or(0) as u64;
+        let cost = prices.cost(&record.model, input, output).unwrap_or(0.0);
+        usage.monthly_tokens += input + output;
+        usage.monthly_cost += cost;
+        if record.timestamp.starts_with(&today) {
+            usage.daily_tokens += input + output;
+            usage.daily_cost += cost;
+        }
+    }
+    Ok(usage)
+}
+
+/// 检查服务的预算，任一预算已用完（含进行中请求的预留）时返回 [`BUDGET_ERROR`] 开头的错误
+pub fn check_budget(service_id: &str) -> Result<(), String> {
+    match load_limits()?.find(service_id).filter(|l| l.has_budget()) {
+        Some(limits) => check_limits(
+            service_id,
+            limits,
+            &BudgetSnapshot::load(service_id)?,
+            &in_flight(service_id),
+            &Reservation::default(),
+        ),
+        None => Ok(()),
+    }
+}
+
+/// 已用量加上进行中请求的预留已达到上限，或再加上本次估算会超出上限时拒绝
+fn check_limits(
+    service_id: &str,
+    limits: &ServiceLimits,
+    snapshot: &BudgetSnapshot,
+    in_flight: &Reservation,
+    estimate: &Reservation,
+) -> Result<(), String> {
+    let BudgetSnapshot { usage, currency } = snapshot;
+    let pending = in_flight.requests > 0 || estimate.requests > 0;
+
+    let token_checks = [
+        ("今日 token", usage.daily_tokens, limits.daily_tokens),
+        ("本月 token", usage.monthly_tokens, limits.monthly_tokens),
+    ];
+    for (label, used, limit) in token_checks {
+        let committed = used + in_flight.tokens;
+        let exceeded = |l: &u64| *l > 0 && (committed >= *l || committed + estimate.tokens > *l);
+        if let Some(limit) = limit.filter(exceeded) {
+            let used = if pending {
+                format!("{}，进行中 {}，本次预计 {}", used, in_flight.tokens, estimate.tokens)
+            } else {
+                used.to_string()
+            };
+            return Err(budget_error(service_id, label, &used, &limit.to_string()));
+        }
+    }
+    let cost_checks = [
+        ("今日费用", usage.daily_cost, limits.daily_cost),
+        ("本月费用", usage.monthly_cost, limits.monthly_cost),
+    ];
+    for (label, used, limit) in cost_checks {
+        let committed = used + in_flight.cost;
+        let exceeded = |l: &f64| *l > 0.0 && (committed >= *l || committed + estimate.cost > *l);
+        if let Some(limit) = limit.filter(exceeded) {
+            let used = if pending {
+                format!(
+                    "{:.4} {}，进行中 {:.4}，本次预计 {:.4}",
+                    used, currency, in_flight.cost, estimate.cost
+                )
+            } else {
+                format!("{:.4} {}", used, currency)
+            };
+            return Err(budget_error(
+                service_id,
+                label,
+                &used,
+                &format!("{:.4} {}", limit, currency),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn budget_error(service_id: &str, label: &str, used: &str, limit: &str) -> String {
+    let service = if service_id.is_empty() { "未指定服务" } else { service_id };
+    format!(
+        "{} AI 服务「{}」的{}预算不足（已用 {} / 上限 {}），可在设置中调整预算或确认后继续",
+        BUDGET_ERROR, service, label, used, limit
+    )
+}
+
+// ============================================================
+// 进行中请求的预留
+// ============================================================
+
+impl UsageEstimate {
+    /// 输入按提示词字节数粗略估算，输出按 max_tokens 计
+    pub fn new(config: &AIServiceConfig, system_prompt: &str, user_prompt: &str) -> Self {
+        let bytes = system_prompt.len() + user_prompt.len();
+        Self {
+            model: config.model.clone(),
+            input_tokens: bytes.div_ceil(BYTES_PER_TOKEN) as u64,
+            output_tokens: config.max_tokens as u64,
+        }
+    }
+
+    fn reservation(&self) -> Result<Reservation, String> {
+        let tokens = self.input_tokens + self.output_tokens;
+        let cost = ai_usage::load_price_table()?
+            .cost(&self.model, self.input_tokens, self.output_tokens)
+            .unwrap_or(0.0);
+        Ok(Reservation { requests: 1, tokens, cost })
+    }
+}
+
+/// 服务当前进行中请求的预留总量
+fn in_flight(service_id: &str) -> Reservation {
+    IN_FLIGHT
+        .lock()
+        .ok()
+        .and_then(|guard| guard.as_ref().and_then(|s| s.reserved.get(service_id).copied()))
+        .unwrap_or_default()
+}
+
+/// 预留的累计释放次数
+fn in_flight_releases() -> u64 {
+    IN_FLIGHT.lock().ok().and_then(|guard| guard.as_ref().map(|s| s.releases)).unwrap_or(0)
+}
+
+impl Drop for Admission {
+    fn drop(&mut self) {
+        if self.reservation.requests == 0 {
+            return;
+        }
+        let Ok(mut guard) = IN_FLIGHT.lock() else {
+            return;
+        };
+        let Some(state) = guard.as_mut() else {
+            return;
+        };
+        state.releases += 1;
+        if let Some(entry) = state.reserved.get_mut(&self.service_id) {
+            entry.requests = entry.requests.saturating_sub(self.reservation.requests);
+            entry.tokens = entry.tokens.saturating_sub(self.reservation.tokens);
+            entry.cost = (entry.cost - self.reservation.cost).max(0.0);
+            if entry.requests == 0 {
+                state.reserved.remove(&self.service_id);
+            }
+        }
+    }
+}
+
+// ============================================================
+// 限速
+// ============================================================
+
+/// 按每分钟请求数限速：窗口已满时等待最早的请求移出窗口后再放行
+pub async fn acquire_rate(service_id: &str, requests_per_minute: u32) {
+    if requests_per_minute == 0 {
+        return;
+    }
+    let mut announced = false;
+    loop {
+        let wait = {
+            let mut guard = match RATE_WINDOWS.lock() {
+                Ok(guard) => guard,
+                Err(_) => return,
+            };
+            let window = guard
+                .get_or_insert_with(HashMap::new)
+                .entry(service_id.to_string())
+                .or_default();
+            match take_rate_slot(window, Instant::now(), requests_per_minute) {
+                Some(wait) => wait,
+                None => return,
+            }
+        };
+        if !announced {
+            eprintln!(
+                "[AI] 服务「{}」已达到每分钟 {} 次请求上限，排队 {} ms",
+                service_id,
+                requests_per_minute,
+                wait.as_millis()
+            );
+            announced = true;
+        }
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// 在窗口中占用一个请求位置；窗口已满时不占用，返回需要等待的时间（等到最早的请求满一分钟）
+fn take_rate_slot(
+    window: &mut VecDeque<Instant>,
+    now: Instant,
+    requests_per_minute: u32,
+) -> Option<Duration> {
+    while window
+        .front()
+        .is_some_and(|t| now.duration_since(*t) >= RATE_WINDOW)
+    {
+        window.pop_front();
+    }
+    if window.len() < requests_per_minute as usize {
+        window.push_back(now);
+        return None;
+    }
+    Some(RATE_WINDOW - now.duration_since(window[0]))
+}
+
+/// 请求发出前的检查：先检查预算（`allow_over_budget` 时跳过），再按限速排队，
+/// 排队结束后重新检查预算并预留本次的估算用量
+///
+/// 预算检查计入进行中请求的预留，并发的一批请求不会一起越过预算。返回的 [`Admission`]
+/// 在请求结束、用量记录写入之后释放。排队期间调用方可以丢弃返回的 future 来取消。
+pub async fn admit(context: &UsageContext, estimate: &UsageEstimate) -> Result<Admission, String> {
+    let service_id = context.service_id.clone();
+    let limits = load_limits()?;
+    let Some(limits) = limits.find(&service_id) else {
+        return Ok(Admission {
+            service_id,
+            reservation: Reservation::default(),
+        });
+    };
+    let reservation = if limits.has_budget() {
+        estimate.reservation()?
+    } else {
+        Reservation::default()
+    };
+    let check = !context.allow_over_budget && limits.has_budget();
+    if check {
+        let snapshot = BudgetSnapshot::load(&service_id)?;
+        check_limits(&service_id, limits, &snapshot, &in_flight(&service_id), &reservation)?;
+    }
+    acquire_rate(&service_id, limits.requests_per_minute.unwrap_or(0)).await;
+
+    // 排队期间其他请求可能已预留或记录了用量：重新检查，并在同一把锁内完成预留。
+    // 用量记录在锁外读取，锁内只做预留的加减；读取期间有请求结束（其用量可能既不在读到的
+    // 记录中、也不再计入预留）时重新读取
+    let mut reloads = 0;
+    loop {
+        let releases = in_flight_releases();
+        let snapshot = if check { Some(BudgetSnapshot::load(&service_id)?) } else { None };
+        let mut guard = IN_FLIGHT.lock().map_err(|_| "预算状态异常".to_string())?;
+        let state = guard.get_or_insert_with(InFlight::default);
+        if snapshot.is_some() && state.releases != releases && reloads < MAX_USAGE_RELOADS {
+            reloads += 1;
+            continue;
+        }
+        if let Some(snapshot) = &snapshot {
+            let reserved = state.reserved.get(&service_id).copied().unwrap_or_default();
+            check_limits(&service_id, limits, snapshot, &reserved, &reservation)?;
+        }
+        if reservation.requests > 0 {
+            let entry = state.reserved.entry(service_id.clone()).or_default();
+            entry.requests += reservation.requests;
+            entry.tokens += reservation.tokens;
+            entry.cost += reservation.cost;
+        }
+        return Ok(Admission { service_id, reservation });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(daily_tokens: u64, daily_cost: f64) -> BudgetSnapshot {
+        BudgetSnapshot {
+            usage: BudgetUsage {
+                service_id: "svc".to_string(),
+                daily_tokens,
+                monthly_tokens: daily_tokens,
+                daily_cost,
+                monthly_cost: daily_cost,
+            },
+            currency: "USD".to_string(),
+        }
+    }
+
+    fn reserved(requests: u32, tokens: u64, cost: f64) -> Reservation {
+        Reservation { requests, tokens, cost }
+    }
+
+    #[test]
+    fn check_limits_counts_in_flight_and_estimate() {
+        let limits = ServiceLimits { daily_tokens: Some(1000), ..Default::default() };
+        let check = |used: u64, in_flight: Reservation, estimate: Reservation| {
+            check_limits("svc", &limits, &snapshot(used, 0.0), &in_flight, &estimate)
+        };
+        // 恰好用满上限时放行，超出 1 个 token 即拒绝
+        assert!(check(600, reserved(1, 300, 0.0), reserved(1, 100, 0.0)).is_ok());
+        assert!(check(600, reserved(1, 300, 0.0), reserved(1, 101, 0.0)).is_err());
+        // 已用量加进行中的预留达到上限时，估算为 0 也拒绝
+        assert!(check(600, reserved(2, 400, 0.0), Reservation::default()).is_err());
+        assert!(check(999, Reservation::default(), Reservation::default()).is_ok());
+        assert!(check(1000, Reservation::default(), Reservation::default()).is_err());
+
+        let err = check(600, reserved(1, 300, 0.0), reserved(1, 200, 0.0)).unwrap_err();
+        assert!(err.starts_with(BUDGET_ERROR));
+        assert!(err.contains("进行中 300") && err.contains("本次预计 200"), "{}", err);
+    }
+
+    #[test]
+    fn check_limits_applies_cost_and_monthly_budgets() {
+        let limits = ServiceLimits { monthly_cost: Some(1.0), ..Default::default() };
+        let check = |used: f64, in_flight: f64, estimate: f64| {
+            check_limits(
+                "svc",
+                &limits,
+                &snapshot(0, used),
+                &reserved(1, 0, in_flight),
+                &reserved(1, 0, estimate),
+            )
+        };
+        assert!(check(0.5, 0.25, 0.25).is_ok());
+        assert!(check(0.5, 0.25, 0.3).is_err());
+        assert!(check(0.5, 0.5, 0.0).is_err());
+    }
+
+    #[test]
+    fn check_limits_ignores_unset_and_zero_limits() {
+        let limits = ServiceLimits {
+            daily_tokens: Some(0),
+            monthly_cost: Some(0.0),
+            ..Default::default()
+        };
+        let result = check_limits(
+            "svc",
+            &limits,
+            &snapshot(u64::MAX / 2, 1e9),
+            &reserved(5, 1_000_000, 1e6),
+            &reserved(1, 1_000_000, 1e6),
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn take_rate_slot_waits_for_oldest_request_to_leave_window() {
+        let mut window = VecDeque::new();
+        let start = Instant::now();
+        assert_eq!(take_rate_slot(&mut window, start, 2), None);
+        assert_eq!(take_rate_slot(&mut window, start + Duration::from_secs(10), 2), None);
+
+        let later = start + Duration::from_secs(30);
+        assert_eq!(take_rate_slot(&mut window, later, 2), Some(Duration::from_secs(30)));
+        // 窗口已满时不占用位置
+        assert_eq!(window.len(), 2);
+
+        // 最早的请求满一分钟后移出窗口
+        let expired = start + RATE_WINDOW;
+        assert_eq!(take_rate_slot(&mut window, expired, 2), None);
+        assert_eq!(window.len(), 2);
+        assert_eq!(window.front(), Some(&(start + Duration::from_secs(10))));
+    }
+
+    #[tokio::test]
+    async fn acquire_rate_admits_up_to_limit_without_waiting() {
+        let service = format!("rate-test-{}", uuid::Uuid::new_v4());
+        let started = Instant::now();
+        for _ in 0..3 {
+            acquire_rate(&service, 3).await;
+        }
+        acquire_rate(&service, 0).await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let queued = tokio::time::timeout(Duration::from_millis(50), acquire_rate(&service, 3));
+        assert!(queued.await.is_err(), "第 4 个请求应当排队");
+    }
+}
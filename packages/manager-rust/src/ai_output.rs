@@ -0,0 +1,1128 @@
+//! AI 生成资源的输出解析
+//!
+//! 提示词要求模型输出 JSON 数组（每项包含 manifest 与 contentFiles），但实际输出常带有代码块
+//! 包裹、前后说明文字、尾随逗号、中文引号，或因长度限制被截断；也有模型改为逐个输出
+//! `manifest.json`、`content.md` 等文件代码块。[`parse_ai_output`] 兼容这两种形式，修复常见的
+//! JSON 错误，按资源类型校验并补全 manifest，返回可直接创建的资源及每个资源的警告。
+
+use crate::id_service;
+use crate::types::{ContentFileEntry, GenericManifest};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// 内容文件对象的键名（不同模型的写法）
+const FILES_KEYS: &[&str] = &["contentFiles", "content_files", "files"];
+
+/// manifest 中应为字符串的字段，AI 输出为 null 时移除，数字或布尔值转为字符串
+const STRING_FIELDS: &[&str] = &[
+    "id", "name", "description", "icon", "version", "resourceType", "majorCategory",
+    "subCategory", "source", "createdAt", "updatedAt",
+];
+
+// ============================================================
+// 类型定义
+// ============================================================
+
+/// 资源类型的附属内容文件（与前端 ContentFileSpec 对应）
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AIOutputFile {
+    pub filename: String,
+    /// markdown / json / text
+    #[serde(default, rename = "type")]
+    pub file_type: String,
+    #[serde(default, rename = "defaultContent")]
+    pub default_content: String,
+}
+
+impl AIOutputFile {
+    fn is_json(&self) -> bool {
+        self.file_type == "json" || self.filename.to_ascii_lowercase().ends_with(".json")
+    }
+}
+
+/// 解析所需的资源类型信息
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AIOutputSpec {
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    /// 目标分类，非空时覆盖 AI 输出的 majorCategory
+    #[serde(default, rename = "majorCategory")]
+    pub major_category: String,
+    #[serde(default, rename = "contentFiles")]
+    pub content_files: Vec<AIOutputFile>,
+    /// 新建资源的默认 manifest，用于补全 AI 未输出的字段
+    #[serde(default, rename = "defaultManifest")]
+    pub default_manifest: Map<String, Value>,
+}
+
+/// 解析出的单个资源，可直接用于创建
+#[derive(Debug, Clone, Serialize)]
+pub struct ParsedAIResource {
+    pub manifest: Value,
+    /// 按资源类型的内容文件顺序排列，缺失的文件使用默认内容
+    #[serde(rename = "contentFiles")]
+    pub content_files: Vec<ContentFileEntry>,
+    /// 对该资源所做的修正
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AIParseResult {
+    pub resources: Vec<ParsedAIResource>,
+    /// 整体的修复说明与被跳过的资源
+    pub warnings: Vec<String>,
+}
+
+/// 从输出中提取、尚未校验的资源
+struct RawResource {
+    manifest: Value,
+    files: Vec<(String, Value)>,
+    truncated: bool,
+    warnings: Vec<String>,
+}
+
+impl RawResource {
+    fn new(manifest: Value) -> Self {
+        Self { manifest, files: Vec::new(), truncated: false, warnings: Vec::new() }
+    }
+}
+
+// ============================================================
+// 解析入口
+// ============================================================
+
+/// 解析 AI 输出
+///
+/// 传入 `data_dir` 时，与仓库已有资源重复的 ID 会自动加后缀；同一批输出内的重复 ID 总会被处理。
+pub fn parse_ai_output(
+    text: &str,
+    spec: &AIOutputSpec,
+    data_dir: Option<&str>,
+) -> Result<AIParseResult, String> {
+    let text = text.trim_start_matches('\u{feff}');
+    if text.trim().is_empty() {
+        return Err("AI 输出为空".to_string());
+    }
+
+    let mut warnings = Vec::new();
+    let blocks = fenced_blocks(text);
+    let raw_items = match file_block_items(&blocks, spec, &mut warnings) {
+        Some(items) => items,
+        None => json_items(text, &mut warnings)?,
+    };
+
+    let mut taken = data_dir.map(id_service::existing_ids).unwrap_or_default();
+    let mut resources = Vec::new();
+    for (index, raw) in raw_items.into_iter().enumerate() {
+        match normalize_resource(raw, spec, &mut taken) {
+            Ok(resource) => resources.push(resource),
+            Err(e) => warnings.push(format!("第 {} 个资源已跳过：{}", index + 1, e)),
+        }
+    }
+    if resources.is_empty() {
+        let detail = warnings.last().map(|w| format!("（{}）", w)).unwrap_or_default();
+        return Err(format!("未能从 AI 输出中解析出任何资源{}", detail));
+    }
+    Ok(AIParseResult { resources, warnings })
+}
+
+// ============================================================
+// JSON 形式
+// ============================================================
+
+/// 按 JSON 解析整个输出：数组、`{ "resources": [...] }` 或单个资源对象
+fn json_items(text: &str, warnings: &mut Vec<String>) -> Result<Vec<RawResource>, String> {
+    let start = json_start(text).ok_or("AI 输出中没有找到 JSON 内容")?;
+    let repaired = repair_json(&text[start..]);
+    if !repaired.notes.is_empty() {
+        warnings.push(format!("已自动修复 AI 输出中的 JSON 问题：{}", repaired.notes.join("、")));
+    }
+    let value: Value = serde_json::from_str(&repaired.text)
+        .map_err(|e| format!("AI 输出的 JSON 无法解析: {}", e))?;
+
+    let items = match value {
+        Value::Array(items) => items,
+        Value::Object(mut obj) => {
+            let list = ["resources", "items", "data"]
+                .iter()
+                .find(|k| obj.get(**k).is_some_and(Value::is_array))
+                .and_then(|k| obj.remove(*k));
+            match list {
+                Some(Value::Array(items)) => items,
+                _ => vec![Value::Object(obj)],
+            }
+        }
+        _ => return Err("AI 输出不是 JSON 数组或对象".to_string()),
+    };
+
+    let mut raws: Vec<RawResource> = items.into_iter().map(raw_from_json).collect();
+    if repaired.truncated {
+        if let Some(last) = raws.last_mut() {
+            last.truncated = true;
+        }
+    }
+    Ok(raws)
+}
+
+/// 单个数组元素：`{ manifest, contentFiles }`，或 manifest 字段与文件名键直接平铺的对象
+fn raw_from_json(item: Value) -> RawResource {
+    let Value::Object(mut obj) = item else {
+        return RawResource::new(item);
+    };
+    let files = FILES_KEYS
+        .iter()
+        .find_map(|k| obj.remove(*k))
+        .map(files_from_value)
+        .unwrap_or_default();
+    let manifest = match obj.remove("manifest") {
+        Some(manifest) => manifest,
+        None => Value::Object(obj),
+    };
+    let mut raw = RawResource::new(manifest);
+    raw.files = files;
+    // 平铺形式中以文件名为键的内容（如 "content.md": "..."）
+    if let Value::Object(manifest) = &mut raw.manifest {
+        let file_keys: Vec<String> =
+            manifest.keys().filter(|k| looks_like_filename(k)).cloned().collect();
+        for key in file_keys {
+            if let Some(value) = manifest.remove(&key) {
+                raw.files.push((key, value));
+            }
+        }
+    }
+    raw
+}
+
+/// 内容文件：`{ "content.md": "..." }` 或 `[{ "filename": "...", "content": "..." }]`
+fn files_from_value(value: Value) -> Vec<(String, Value)> {
+    match value {
+        Value::Object(map) => map.into_iter().collect(),
+        Value::Array(list) => list
+            .into_iter()
+            .filter_map(|item| {
+                let mut obj = match item {
+                    Value::Object(obj) => obj,
+                    _ => return None,
+                };
+                let name = ["filename", "name", "path"]
+                    .iter()
+                    .find_map(|k| obj.get(*k).and_then(Value::as_str).map(str::to_string))?;
+                Some((name, obj.remove("content").unwrap_or(Value::Null)))
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn looks_like_filename(key: &str) -> bool {
+    let lower = key.to_ascii_lowercase();
+    [".md", ".json", ".txt"].iter().any(|ext| lower.ends_with(ext))
+}
+
+/// JSON 的起始位置：优先 ```json 代码块内，其次是紧跟对象的 `[`，再次是第一个 `{`，最后是第一个 `[`
+///
+/// 跳过前面的说明文字（其中可能出现方括号）。
+fn json_start(text: &str) -> Option<usize> {
+    let search_from = text
+        .find("```json")
+        .or_else(|| text.find("```JSON"))
+        .map(|i| i + 7)
+        .unwrap_or(0);
+    let rest = &text[search_from..];
+    let array_of_objects = rest.char_indices().find(|(i, c)| {
+        *c == '[' && rest[i + 1..].trim_start().starts_with(['{', ']'])
+    });
+    array_of_objects
+        .map(|(i, _)| i)
+        .or_else(|| rest.find('{'))
+        .or_else(|| rest.find('['))
+        .map(|i| search_from + i)
+}
+
+// ============================================================
+// 文件代码块形式
+// ============================================================
+
+/// Markdown 代码块
+struct FencedBlock<'a> {
+    /// 开始标记后的信息字符串（语言、文件名等）
+    info: &'a str,
+    /// 代码块之前最近的非空行（常为文件名标题）
+    label: &'a str,
+    body: String,
+    /// 到文本末尾仍未闭合（输出被截断）
+    closed: bool,
+}
+
+fn fenced_blocks(text: &str) -> Vec<FencedBlock<'_>> {
+    let mut blocks = Vec::new();
+    let mut label = "";
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        let Some(marker) = fence_marker(trimmed) else {
+            if !trimmed.trim().is_empty() {
+                label = trimmed.trim();
+            }
+            continue;
+        };
+        let info = trimmed[marker.len()..].trim();
+        let mut body = String::new();
+        let mut closed = false;
+        for inner in lines.by_ref() {
+            let inner_trimmed = inner.trim();
+            // 闭合标记：同种字符、不短于开始标记、且没有信息字符串
+            if inner_trimmed.len() >= marker.len()
+                && inner_trimmed.chars().all(|c| marker.starts_with(c))
+            {
+                closed = true;
+                break;
+            }
+            body.push_str(inner);
+            body.push('\n');
+        }
+        blocks.push(FencedBlock { info, label, body, closed });
+        label = "";
+    }
+    blocks
+}
+
+/// 行首的 ``` 或 ~~~ 标记（3 个及以上）
+fn fence_marker(line: &str) -> Option<&str> {
+    let c = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = line.chars().take_while(|x| *x == c).count();
+    (len >= 3).then(|| &line[..len])
+}
+
+/// 代码块对应的文件名：信息字符串（```json manifest.json / title="content.md"）或前一行标题中
+/// 出现的已知文件名
+fn block_filename(block: &FencedBlock, known: &[String]) -> Option<String> {
+    let info = block.info.to_ascii_lowercase();
+    let label = block.label.to_ascii_lowercase();
+    for source in [&info, &label] {
+        let found = known
+            .iter()
+            .filter(|name| source.contains(name.as_str()))
+            .max_by_key(|name| name.len());
+        if let Some(name) = found {
+            return Some(name.clone());
+        }
+    }
+    None
+}
+
+/// 按文件代码块提取资源；没有任何 manifest.json 代码块时返回 None（改按 JSON 解析）
+///
+/// 每个 manifest.json 开始一个新资源，之后的内容文件归入该资源。
+fn file_block_items(
+    blocks: &[FencedBlock],
+    spec: &AIOutputSpec,
+    warnings: &mut Vec<String>,
+) -> Option<Vec<RawResource>> {
+    let known: Vec<String> = std::iter::once(MANIFEST_FILE.to_string())
+        .chain(spec.content_files.iter().map(|f| f.filename.to_ascii_lowercase()))
+        .collect();
+    // 说明文字中提到文件名、实际是 JSON 数组的代码块不算 manifest
+    let named: Vec<(String, &FencedBlock)> = blocks
+        .iter()
+        .filter_map(|b| block_filename(b, &known).map(|name| (name, b)))
+        .filter(|(name, b)| {
+            name != MANIFEST_FILE
+                || !(b.body.trim_start().starts_with('[') || b.body.contains("\"manifest\""))
+        })
+        .collect();
+    if !named.iter().any(|(name, _)| name == MANIFEST_FILE) {
+        return None;
+    }
+
+    let mut items: Vec<RawResource> = Vec::new();
+    for (name, block) in named {
+        if name == MANIFEST_FILE {
+            let repaired = repair_json(block.body.trim());
+            let manifest = serde_json::from_str(&repaired.text).unwrap_or(Value::Null);
+            let mut raw = RawResource::new(manifest);
+            if !repaired.notes.is_empty() {
+                raw.warnings
+                    .push(format!("已自动修复 manifest 的 JSON 问题：{}", repaired.notes.join("、")));
+            }
+            raw.truncated = !block.closed;
+            items.push(raw);
+            continue;
+        }
+        match items.last_mut() {
+            Some(current) if current.files.iter().any(|(n, _)| *n == name) => current
+                .warnings
+                .push(format!("{} 出现多次，已使用第一个", name)),
+            Some(current) => {
+                current.truncated |= !block.closed;
+                current.files.push((name, Value::String(block.body.clone())));
+            }
+            None => warnings.push(format!("{} 出现在 manifest.json 之前，已忽略", name)),
+        }
+    }
+    Some(items)
+}
+
+// ============================================================
+// 校验与补全
+// ============================================================
+
+/// 校验 manifest 并补全字段，整理内容文件
+fn normalize_resource(
+    raw: RawResource,
+    spec: &AIOutputSpec,
+    taken: &mut HashSet<String>,
+) -> Result<ParsedAIResource, String> {
+    let mut warnings = raw.warnings;
+    if raw.truncated {
+        warnings.push("AI 输出在此资源处被截断，内容可能不完整".to_string());
+    }
+    let Value::Object(mut manifest) = raw.manifest else {
+        return Err("manifest 不是 JSON 对象".to_string());
+    };
+
+    for key in STRING_FIELDS {
+        match manifest.get(*key) {
+            Some(Value::Null) => {
+                manifest.remove(*key);
+            }
+            Some(v @ (Value::Number(_) | Value::Bool(_))) => {
+                let s = v.to_string();
+                manifest.insert(key.to_string(), Value::String(s));
+            }
+            _ => {}
+        }
+    }
+
+    // 名称与 ID
+    let name = str_field(&manifest, "name");
+    let raw_id = str_field(&manifest, "id");
+    if name.is_empty() && raw_id.is_empty() {
+        return Err("manifest 缺少 id 与 name".to_string());
+    }
+    if name.is_empty() {
+        warnings.push("缺少名称，已使用 ID 代替".to_string());
+        manifest.insert("name".to_string(), Value::String(raw_id.clone()));
+    }
+    let base = if raw_id.is_empty() {
+        warnings.push("缺少 ID，已根据名称生成".to_string());
+        id_service::slugify_name(&name)
+    } else {
+        id_service::sanitize_id(&raw_id)
+    };
+    let base = if base.is_empty() {
+        let short = uuid::Uuid::new_v4().simple().to_string();
+        format!("{}-{}", id_service::sanitize_id(&spec.resource_type), &short[..8])
+    } else {
+        base
+    };
+    if !raw_id.is_empty() && base != raw_id {
+        warnings.push(format!("ID「{}」不合法，已改为「{}」", raw_id, base));
+    }
+    let id = id_service::make_unique(&base, taken);
+    if id != base {
+        warnings.push(format!("ID「{}」已被占用，已改为「{}」", base, id));
+    }
+    taken.insert(id.clone());
+    manifest.insert("id".to_string(), Value::String(id));
+
+    // 资源类型与分类
+    let resource_type = str_field(&manifest, "resourceType");
+    if !resource_type.is_empty() && resource_type != spec.resource_type {
+        warnings.push(format!(
+            "resourceType「{}」与当前资源类型不符，已改为「{}」",
+            resource_type, spec.resource_type
+        ));
+    }
+    manifest.insert("resourceType".to_string(), Value::String(spec.resource_type.clone()));
+    if !spec.major_category.is_empty() {
+        manifest.insert("majorCategory".to_string(), Value::String(spec.major_category.clone()));
+    }
+
+    normalize_string_list(&mut manifest, "tags", &mut warnings);
+    normalize_string_list(&mut manifest, "roles", &mut warnings);
+    normalize_order(&mut manifest, &mut warnings);
+    if let Some(Value::String(s)) = manifest.get("enabled") {
+        let enabled = !matches!(s.trim(), "false" | "0" | "no");
+        manifest.insert("enabled".to_string(), Value::Bool(enabled));
+    }
+
+    for (key, value) in &spec.default_manifest {
+        manifest.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+    // AI 填写的时间戳不可信（常为占位文字），统一使用当前时间
+    let now = Value::String(chrono::Utc::now().to_rfc3339());
+    manifest.insert("createdAt".to_string(), now.clone());
+    manifest.insert("updatedAt".to_string(), now);
+
+    let manifest = Value::Object(manifest);
+    serde_json::from_value::<GenericManifest>(manifest.clone())
+        .map_err(|e| format!("manifest 格式无效: {}", e))?;
+
+    let content_files = normalize_files(raw.files, spec, &mut warnings);
+    Ok(ParsedAIResource { manifest, content_files, warnings })
+}
+
+fn str_field(manifest: &Map<String, Value>, key: &str) -> String {
+    manifest.get(key).and_then(Value::as_str).unwrap_or("").trim().to_string()
+}
+
+/// tags / roles：字符串按分隔符拆分，数组中的非字符串项转为字符串
+fn normalize_string_list(manifest: &mut Map<String, Value>, key: &str, warnings: &mut Vec<String>) {
+    let list: Vec<String> = match manifest.get(key) {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|v| match v {
+                Value::String(s) => s.trim().to_string(),
+                other => other.to_string(),
+            })
+            .filter(|s| !s.is_empty())
+            .collect(),
+        Some(Value::String(s)) => {
+            warnings.push(format!("{} 不是数组，已按分隔符拆分", key));
+            s.split([',', '，', '、', ';', '；'])
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect()
+        }
+        Some(_) => {
+            warnings.push(format!("{} 格式无效，已清空", key));
+            Vec::new()
+        }
+    };
+    manifest.insert(key.to_string(), Value::from(list));
+}
+
+fn normalize_order(manifest: &mut Map<String, Value>, warnings: &mut Vec<String>) {
+    let order = match manifest.get("order") {
+        None | Some(Value::Null) => 0,
+        Some(Value::Number(n)) => match n.as_i64().and_then(|v| i32::try_from(v).ok()) {
+            Some(v) => v,
+            None => {
+                warnings.push("order 不是整数，已改为 0".to_string());
+                0
+            }
+        },
+        Some(Value::String(s)) => s.trim().parse().unwrap_or_else(|_| {
+            warnings.push("order 不是整数，已改为 0".to_string());
+            0
+        }),
+        Some(_) => {
+            warnings.push("order 不是整数，已改为 0".to_string());
+            0
+        }
+    };
+    manifest.insert("order".to_string(), Value::from(order));
+}
+
+/// 按资源类型整理内容文件：匹配文件名，JSON 文件校验并修复，缺失的使用默认内容
+fn normalize_files(
+    files: Vec<(String, Value)>,
+    spec: &AIOutputSpec,
+    warnings: &mut Vec<String>,
+) -> Vec<ContentFileEntry> {
+    let mut contents: Vec<Option<Value>> = vec![None; spec.content_files.len()];
+    for (name, value) in files {
+        match spec_file_index(&name, spec) {
+            Some(i) if contents[i].is_none() => contents[i] = Some(value),
+            Some(_) => warnings.push(format!("{} 出现多次，已使用第一个", name)),
+            None => warnings.push(format!("忽略了未知文件「{}」", name)),
+        }
+    }
+
+    spec.content_files
+        .iter()
+        .zip(contents)
+        .map(|(file, value)| {
+            let content = match value {
+                Some(Value::String(s)) if !s.trim().is_empty() => Some(strip_fence(&s)),
+                // 模型直接输出了 JSON 对象而非字符串
+                Some(v) if !v.is_null() && !v.is_string() => {
+                    Some(serde_json::to_string_pretty(&v).unwrap_or_default())
+                }
+                _ => None,
+            };
+            let content = match content {
+                Some(c) if file.is_json() => normalize_json_content(&file.filename, c, warnings),
+                Some(c) => c,
+                None => {
+                    warnings.push(format!("缺少 {}，已使用默认内容", file.filename));
+                    file.default_content.clone()
+                }
+            };
+            ContentFileEntry { filename: file.filename.clone(), content }
+        })
+        .collect()
+}
+
+/// 文件名对应的内容文件：忽略大小写，允许省略扩展名；只有一个内容文件时也接受 `content`
+fn spec_file_index(name: &str, spec: &AIOutputSpec) -> Option<usize> {
+    let name = name.trim().to_ascii_lowercase();
+    let files = &spec.content_files;
+    files
+        .iter()
+        .position(|f| f.filename.to_ascii_lowercase() == name)
+        .or_else(|| {
+            files.iter().position(|f| {
+                let lower = f.filename.to_ascii_lowercase();
+                lower.rsplit_once('.').map(|(stem, _)| stem) == Some(name.as_str())
+            })
+        })
+        .or_else(|| (files.len() == 1 && matches!(name.as_str(), "content" | "body")).then_some(0))
+}
+
+/// 去掉整体包裹内容的代码块标记
+fn strip_fence(content: &str) -> String {
+    let trimmed = content.trim();
+    let Some(marker) = fence_marker(trimmed) else {
+        return content.to_string();
+    };
+    let Some((_, body)) = trimmed.split_once('\n') else {
+        return content.to_string();
+    };
+    match body.trim_end().strip_suffix(marker) {
+        Some(inner) if !inner.contains(marker) => inner.trim_end_matches('\n').to_string(),
+        _ => content.to_string(),
+    }
+}
+
+fn normalize_json_content(filename: &str, content: String, warnings: &mut Vec<String>) -> String {
+    if serde_json::from_str::<Value>(&content).is_ok() {
+        return content;
+    }
+    let repaired = repair_json(content.trim());
+    match serde_json::from_str::<Value>(&repaired.text) {
+        Ok(value) => {
+            warnings.push(format!("{} 不是有效的 JSON，已自动修复", filename));
+            serde_json::to_string_pretty(&value).unwrap_or(content)
+        }
+        Err(_) => {
+            warnings.push(format!("{} 不是有效的 JSON，请手动检查", filename));
+            content
+        }
+    }
+}
+
+// ============================================================
+// JSON 修复
+// ============================================================
+
+/// 修复结果
+pub struct RepairedJson {
+    pub text: String,
+    /// 所做修复的说明（去重）
+    pub notes: Vec<&'static str>,
+    /// 输出在结构闭合前结束
+    pub truncated: bool,
+}
+
+/// 容器当前期待的内容
+#[derive(Clone, Copy, PartialEq)]
+enum Expect {
+    /// 对象中的键
+    Key,
+    /// 键之后的冒号
+    Colon,
+    /// 值（对象的值或数组元素）
+    Value,
+    /// 值之后的逗号或闭合括号
+    Next,
+}
+
+struct Frame {
+    close: char,
+    expect: Expect,
+}
+
+/// 修复 AI 常见的 JSON 错误
+///
+/// 处理尾随逗号与多余逗号、缺少的逗号和冒号、中文引号与单引号包裹的字符串、字符串内未转义的
+/// 引号和换行、无效转义、注释、未加引号的键和值、Python 风格的 True/False/None，以及截断时
+/// 未闭合的字符串和括号。第一个顶层值结束后的内容（如说明文字）被忽略。
+pub fn repair_json(input: &str) -> RepairedJson {
+    let mut r = Repairer { out: String::with_capacity(input.len()), notes: Vec::new() };
+    let chars: Vec<char> = input.chars().collect();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut pending_comma = false;
+    let mut started = false;
+    let mut truncated = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        if started && stack.is_empty() {
+            break;
+        }
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => {
+                r.out.push(c);
+                i += 1;
+            }
+            '{' | '[' => {
+                r.before_value(stack.last_mut(), &mut pending_comma);
+                r.out.push(c);
+                stack.push(match c {
+                    '{' => Frame { close: '}', expect: Expect::Key },
+                    _ => Frame { close: ']', expect: Expect::Value },
+                });
+                started = true;
+                i += 1;
+            }
+            '}' | ']' => {
+                if pending_comma {
+                    r.note("尾随逗号");
+                    pending_comma = false;
+                }
+                match stack.last() {
+                    Some(frame) if frame.close == c => {
+                        let frame = stack.pop().expect("frame");
+                        r.finish_frame(&frame);
+                        r.value_done(stack.last_mut());
+                        i += 1;
+                    }
+                    // 括号不匹配：先闭合内层容器，再重新处理当前字符
+                    Some(_) if stack.iter().any(|f| f.close == c) => {
+                        let frame = stack.pop().expect("frame");
+                        r.note("括号不匹配");
+                        r.finish_frame(&frame);
+                        r.value_done(stack.last_mut());
+                    }
+                    _ => {
+                        r.note("多余的括号");
+                        i += 1;
+                    }
+                }
+            }
+            ',' => {
+                match stack.last_mut() {
+                    Some(frame) if frame.expect == Expect::Next => {
+                        pending_comma = true;
+                        frame.expect = if frame.close == '}' { Expect::Key } else { Expect::Value };
+                    }
+                    _ => r.note("多余的逗号"),
+                }
+                i += 1;
+            }
+            ':' => {
+                match stack.last_mut() {
+                    Some(frame) if frame.expect == Expect::Colon => {
+                        r.out.push(':');
+                        frame.expect = Expect::Value;
+                    }
+                    _ => r.note("多余的冒号"),
+                }
+                i += 1;
+            }
+            '/' if matches!(chars.get(i + 1), Some('/') | Some('*')) => {
+                r.note("注释");
+                i = skip_comment(&chars, i);
+            }
+            '`' => {
+                i += 1;
+            }
+            '"' | '“' | '”' | '„' | '\'' | '‘' | '’' => {
+                r.before_value(stack.last_mut(), &mut pending_comma);
+                let (end, closed) = r.read_string(&chars, i);
+                truncated |= !closed;
+                r.value_done(stack.last_mut());
+                i = end;
+            }
+            _ => {
+                let end = bare_token_end(&chars, i);
+                let token: String = chars[i..end].iter().collect();
+                let is_key = stack.last().is_some_and(|f| {
+                    matches!(f.expect, Expect::Key) || (f.close == '}' && f.expect == Expect::Next)
+                });
+                r.before_value(stack.last_mut(), &mut pending_comma);
+                r.push_bare(&token, is_key);
+                r.value_done(stack.last_mut());
+                started = true;
+                i = end;
+            }
+        }
+    }
+
+    if !stack.is_empty() {
+        truncated = true;
+        r.note("输出被截断，已补全未闭合的结构");
+        while let Some(frame) = stack.pop() {
+            r.finish_frame(&frame);
+            r.value_done(stack.last_mut());
+        }
+    }
+    RepairedJson { text: r.out, notes: r.notes, truncated }
+}
+
+struct Repairer {
+    out: String,
+    notes: Vec<&'static str>,
+}
+
+impl Repairer {
+    fn note(&mut self, note: &'static str) {
+        if !self.notes.contains(&note) {
+            self.notes.push(note);
+        }
+    }
+
+    /// 写入值之前补上延后的逗号，或缺少的逗号 / 冒号
+    fn before_value(&mut self, frame: Option<&mut Frame>, pending_comma: &mut bool) {
+        let Some(frame) = frame else {
+            return;
+        };
+        match frame.expect {
+            Expect::Next => {
+                self.note("缺少逗号");
+                self.out.push(',');
+                frame.expect = if frame.close == '}' { Expect::Key } else { Expect::Value };
+            }
+            Expect::Colon => {
+                self.note("缺少冒号");
+                self.out.push(':');
+                frame.expect = Expect::Value;
+            }
+            Expect::Key | Expect::Value => {
+                if *pending_comma {
+                    self.out.push(',');
+                }
+            }
+        }
+        *pending_comma = false;
+    }
+
+    /// 一个键或值写完后推进所在容器的状态
+    fn value_done(&mut self, frame: Option<&mut Frame>) {
+        if let Some(frame) = frame {
+            frame.expect = match frame.expect {
+                Expect::Key => Expect::Colon,
+                _ => Expect::Next,
+            };
+        }
+    }
+
+    /// 闭合容器：只有键或冒号后缺少值时补 null
+    fn finish_frame(&mut self, frame: &Frame) {
+        match frame.expect {
+            Expect::Colon => self.out.push_str(":null"),
+            Expect::Value if frame.close == '}' => self.out.push_str("null"),
+            _ => {}
+        }
+        self.out.push(frame.close);
+    }
+
+    /// 读取字符串（任意引号包裹），输出为标准的双引号字符串；返回结束位置与是否正常闭合
+    ///
+    /// 引号只有在后面紧跟 `,` `:` `}` `]`、换行后的引号或文本结尾时才视为结束，否则视为字符串
+    /// 内未转义的引号（如中文内容中的 "引用"）。
+    fn read_string(&mut self, chars: &[char], start: usize) -> (usize, bool) {
+        let open = chars[start];
+        let closers: &[char] = match open {
+            '"' => &['"'],
+            '“' | '”' | '„' => {
+                self.note("中文引号");
+                &['”', '“', '"']
+            }
+            _ => {
+                self.note("单引号");
+                &['\'', '’', '‘']
+            }
+        };
+        self.out.push('"');
+        let mut i = start + 1;
+        while i < chars.len() {
+            let c = chars[i];
+            if c == '\\' {
+                match chars.get(i + 1) {
+                    Some(n @ ('"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't')) => {
+                        self.out.push('\\');
+                        self.out.push(*n);
+                    }
+                    Some('u') if is_unicode_escape(&chars[i + 2..]) => {
+                        self.out.push_str("\\u");
+                    }
+                    Some('\'') => {
+                        self.note("无效转义");
+                        self.out.push('\'');
+                    }
+                    Some(n) => {
+                        self.note("无效转义");
+                        self.out.push_str("\\\\");
+                        self.push_string_char(*n);
+                    }
+                    None => {
+                        i += 1;
+                        continue;
+                    }
+                }
+                i += 2;
+                continue;
+            }
+            if closers.contains(&c) && closes_string(chars, i + 1) {
+                self.out.push('"');
+                return (i + 1, true);
+            }
+            if closers.contains(&c) {
+                self.note("字符串中未转义的引号");
+            }
+            self.push_string_char(c);
+            i += 1;
+        }
+        self.out.push('"');
+        (i, false)
+    }
+
+    fn push_string_char(&mut self, c: char) {
+        match c {
+            '"' => self.out.push_str("\\\""),
+            '\n' => {
+                self.note("字符串中的换行");
+                self.out.push_str("\\n");
+            }
+            '\r' => self.out.push_str("\\r"),
+            '\t' => self.out.push_str("\\t"),
+            c if (c as u32) < 0x20 => self.out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => self.out.push(c),
+        }
+    }
+
+    /// 未加引号的键或值：字面量与数字原样保留，其余加引号
+    fn push_bare(&mut self, token: &str, is_key: bool) {
+        let literal = match token {
+            "true" | "false" | "null" => Some(token),
+            "True" => Some("true"),
+            "False" => Some("false"),
+            "None" | "undefined" => Some("null"),
+            _ => None,
+        };
+        let is_number =
+            serde_json::from_str::<Value>(token).is_ok_and(|v| v.is_number());
+        match literal {
+            Some(literal) if !is_key => {
+                if literal != token {
+                    self.note("非 JSON 字面量");
+                }
+                self.out.push_str(literal);
+            }
+            _ if is_number && !is_key => self.out.push_str(token),
+            _ => {
+                self.note("未加引号的键或值");
+                self.out.push_str(&Value::String(token.to_string()).to_string());
+            }
+        }
+    }
+}
+
+/// 引号之后的内容是否表明字符串在此结束
+///
+/// 空白之后紧跟另一个值的开头（缺少冒号或逗号）时同样视为结束。
+fn closes_string(chars: &[char], from: usize) -> bool {
+    let mut saw_space = false;
+    let mut saw_newline = false;
+    for &c in &chars[from..] {
+        match c {
+            '\n' => saw_newline = true,
+            c if c.is_whitespace() => saw_space = true,
+            ',' | ':' | '}' | ']' => return true,
+            '"' | '“' | '”' | '\'' => return saw_newline,
+            '{' | '[' | '-' | '0'..='9' => return saw_space || saw_newline,
+            _ => return false,
+        }
+    }
+    true
+}
+
+fn is_unicode_escape(rest: &[char]) -> bool {
+    rest.len() >= 4 && rest[..4].iter().all(char::is_ascii_hexdigit)
+}
+
+/// 跳过 `//` 或 `/* */` 注释，返回注释之后的位置
+fn skip_comment(chars: &[char], start: usize) -> usize {
+    if chars[start + 1] == '/' {
+        let mut i = start + 2;
+        while i < chars.len() && chars[i] != '\n' {
+            i += 1;
+        }
+        return i;
+    }
+    let mut i = start + 2;
+    while i + 1 < chars.len() && !(chars[i] == '*' && chars[i + 1] == '/') {
+        i += 1;
+    }
+    (i + 2).min(chars.len())
+}
+
+/// 未加引号的词的结束位置
+fn bare_token_end(chars: &[char], start: usize) -> usize {
+    let mut i = start;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() || matches!(c, ',' | ':' | '{' | '}' | '[' | ']' | '"' | '`') {
+            break;
+        }
+        i += 1;
+    }
+    // 起始字符本身是分隔符之外的未知字符时至少前进一位
+    i.max(start + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn repaired(input: &str) -> Value {
+        let r = repair_json(input);
+        serde_json::from_str(&r.text)
+            .unwrap_or_else(|e| panic!("{:?} -> {:?}: {}", input, r.text, e))
+    }
+
+    fn spec() -> AIOutputSpec {
+        AIOutputSpec {
+            resource_type: "prompt-templates".to_string(),
+            content_files: vec![AIOutputFile {
+                filename: "content.md".to_string(),
+                file_type: "markdown".to_string(),
+                default_content: String::new(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn repair_json_fixes_common_errors() {
+        let cases: &[(&str, Value)] = &[
+            // 尾随逗号与多余逗号
+            (r#"[1, 2, 3,]"#, json!([1, 2, 3])),
+            (r#"{"a": 1,}"#, json!({"a": 1})),
+            (r#"[1,, 2]"#, json!([1, 2])),
+            // 缺少的逗号和冒号
+            (r#"{"a": 1 "b": 2}"#, json!({"a": 1, "b": 2})),
+            (r#"[{"a": 1} {"a": 2}]"#, json!([{"a": 1}, {"a": 2}])),
+            (r#"{"a" 1}"#, json!({"a": 1})),
+            // 中文引号与单引号
+            ("{\u{201c}name\u{201d}: \u{201c}测试\u{201d}}", json!({"name": "测试"})),
+            (r#"{'name': 'x'}"#, json!({"name": "x"})),
+            // 字符串内未转义的引号和换行
+            (r#"{"a": "他说 "你好" 然后"}"#, json!({"a": "他说 \"你好\" 然后"})),
+            ("{\"a\": \"line1\nline2\"}", json!({"a": "line1\nline2"})),
+            // 无效转义
+            (r#"{"a": "C:\path"}"#, json!({"a": "C:\\path"})),
+            // 注释
+            ("{\"a\": 1, // 注释\n \"b\": /* x */ 2}", json!({"a": 1, "b": 2})),
+            // 未加引号的键和值、Python 风格字面量
+            (r#"{name: hello, ok: True, none: None, no: False}"#,
+                json!({"name": "hello", "ok": true, "none": null, "no": false})),
+            // 顶层值之后的说明文字
+            (r#"{"a": 1} 以上就是结果 {"b": 2}"#, json!({"a": 1})),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(&repaired(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn repair_json_closes_truncated_output() {
+        let cases: &[(&str, Value)] = &[
+            (r#"[{"a": "unfinished"#, json!([{"a": "unfinished"}])),
+            (r#"[{"a": 1}, {"b": [1, 2"#, json!([{"a": 1}, {"b": [1, 2]}])),
+            (r#"{"a": 1, "b":"#, json!({"a": 1, "b": null})),
+            (r#"[1, 2,"#, json!([1, 2])),
+        ];
+        for (input, expected) in cases {
+            let r = repair_json(input);
+            assert!(r.truncated, "input: {}", input);
+            assert_eq!(&repaired(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn repair_json_leaves_valid_json_untouched() {
+        let input = r#"[{"a": "x, y", "b": [1, {"c": null}], "d": "\u4e2d\n"}]"#;
+        let r = repair_json(input);
+        assert!(r.notes.is_empty(), "{:?}", r.notes);
+        assert!(!r.truncated);
+        assert_eq!(repaired(input), serde_json::from_str::<Value>(input).unwrap());
+    }
+
+    #[test]
+    fn json_start_skips_prose_brackets() {
+        let cases: &[(&str, Option<&str>)] = &[
+            ("[说明] 输出如下：[{\"a\": 1}]", Some("[{")),
+            ("参见 [1] 与 [2]：\n{\"a\": 1}", Some("{\"a\"")),
+            ("[\"a\", \"b\"]", Some("[\"a\"")),
+            ("前言 [注]\n```json\n[ {\"a\": 1} ]\n```", Some("[ {")),
+            ("```json\n{\"a\": [1]}\n```", Some("{\"a\"")),
+            ("[]", Some("[]")),
+            ("没有 JSON", None),
+        ];
+        for (text, expected) in cases {
+            let found = json_start(text).map(|i| &text[i..]);
+            match expected {
+                Some(prefix) => assert!(
+                    found.is_some_and(|f| f.starts_with(prefix)),
+                    "{:?} -> {:?}",
+                    text,
+                    found
+                ),
+                None => assert!(found.is_none(), "{:?}", text),
+            }
+        }
+    }
+
+    #[test]
+    fn fenced_blocks_handles_unclosed_and_nested_fences() {
+        let text = "manifest.json\n```json\n{\"id\": \"a\"}\n```\n\n\
+                    content.md\n````markdown\n示例：\n```js\nlet x = 1;\n```\n````\n\
+                    ```text\n被截断";
+        let blocks = fenced_blocks(text);
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].label, "manifest.json");
+        assert!(blocks[0].closed);
+        // 外层 4 个反引号，内层 3 个反引号的代码块属于正文
+        assert_eq!(blocks[1].info, "markdown");
+        assert_eq!(blocks[1].body, "示例：\n```js\nlet x = 1;\n```\n");
+        assert!(blocks[1].closed);
+        assert_eq!(blocks[2].body, "被截断\n");
+        assert!(!blocks[2].closed);
+    }
+
+    #[test]
+    fn parse_ai_output_accepts_json_array_with_repairs() {
+        let text = "好的，以下是资源：\n```json\n[\n\
+                    {\"manifest\": {\"id\": \"a\", \"name\": \"A\",}, \
+                    \"contentFiles\": {\"content.md\": \"# A\"}},\n\
+                    {\"manifest\": {\"id\": \"a\", \"name\": \"B\"}, \
+                    \"contentFiles\": {\"content.md\": \"# B\"}},\n]\n```";
+        let result = parse_ai_output(text, &spec(), None).unwrap();
+        assert_eq!(result.resources.len(), 2);
+        assert!(result.warnings.iter().any(|w| w.contains("已自动修复")));
+        assert_eq!(result.resources[0].manifest["id"], "a");
+        // 同一批输出内重复的 ID 加后缀
+        assert_ne!(result.resources[1].manifest["id"], "a");
+        assert_eq!(result.resources[1].manifest["resourceType"], "prompt-templates");
+        assert_eq!(result.resources[0].content_files[0].content, "# A");
+    }
+
+    #[test]
+    fn parse_ai_output_accepts_file_blocks_and_flags_truncation() {
+        let text = "### manifest.json\n```json\n\
+                    {\"id\": \"x\", \"name\": \"X\", \"tags\": \"a, b\"}\n```\n\
+                    ### content.md\n```markdown\n# 标题\n正文";
+        let result = parse_ai_output(text, &spec(), None).unwrap();
+        let resource = &result.resources[0];
+        assert_eq!(resource.manifest["tags"], json!(["a", "b"]));
+        assert!(resource.content_files[0].content.starts_with("# 标题"));
+        assert!(resource.warnings.iter().any(|w| w.contains("截断")));
+    }
+
+    #[test]
+    fn parse_ai_output_reports_unusable_output() {
+        assert!(parse_ai_output("  ", &spec(), None).is_err());
+        assert!(parse_ai_output("没有任何 JSON", &spec(), None).is_err());
+        assert!(parse_ai_output("[{\"description\": \"无 id 与 name\"}]", &spec(), None).is_err());
+    }
+}
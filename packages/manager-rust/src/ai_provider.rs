@@ -0,0 +1,795 @@
+//! AI 服务提供商适配
+//!
+//! 不同提供商的鉴权头、请求体、响应结构与流式事件格式各不相同，
+//! 这里按 `provider` 字段把它们统一为 [`ProviderRequest`] / [`AICompletion`] / [`StreamEvent`]，
+//! `ai` 模块只负责发送请求与驱动流式读取。
+
+use crate::types::AIServiceConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Anthropic Messages API 版本头
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Anthropic 要求必须提供 max_tokens，配置为 0（由模型决定）时使用此值
+const ANTHROPIC_DEFAULT_MAX_TOKENS: u32 = 8192;
+
+// ============================================================
+// 类型定义
+// ============================================================
+
+/// 协议类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderKind {
+    /// OpenAI `/chat/completions` 及兼容接口（DeepSeek、通义、Kimi 等）
+    OpenAI,
+    /// Anthropic Messages API
+    Anthropic,
+    /// Google Gemini 原生 `generateContent` 接口
+    Gemini,
+    /// Ollama 原生 `/api/chat` 接口
+    Ollama,
+}
+
+/// 流式响应的分帧方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFraming {
+    /// Server-Sent Events（`data:` 行）
+    Sse,
+    /// 每行一个 JSON 对象
+    Ndjson,
+}
+
+/// 已构建好的 HTTP 请求
+#[derive(Debug, Clone)]
+pub struct ProviderRequest {
+    pub url: String,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Value,
+}
+
+/// Token 用量（提供商未返回的字段为 None）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    #[serde(rename = "inputTokens")]
+    pub input_tokens: Option<u32>,
+    #[serde(rename = "outputTokens")]
+    pub output_tokens: Option<u32>,
+}
+
+impl TokenUsage {
+    /// 合并后到的用量（流式响应中输入/输出 token 往往分别在不同事件里给出）
+    pub fn merge(&mut self, other: &TokenUsage) {
+        if other.input_tokens.is_some() {
+            self.input_tokens = other.input_tokens;
+        }
+        if other.output_tokens.is_some() {
+            self.output_tokens = other.output_tokens;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.input_tokens.is_none() && self.output_tokens.is_none()
+    }
+}
+
+/// 一次完整生成的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AICompletion {
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+}
+
+/// 从单个流式事件中解析出的内容
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// 增量文本
+    Delta(String),
+    /// 用量信息
+    Usage(TokenUsage),
+    /// 提供商声明流已结束
+    Done,
+    /// 流中返回的错误
+    Error(String),
+}
+
+// ============================================================
+// 协议选择
+// ============================================================
+
+impl ProviderKind {
+    /// 根据服务的 `provider` 与 base URL 选择协议
+    ///
+    /// Gemini 的 OpenAI 兼容端点（以 `/openai` 结尾）按 OpenAI 协议处理；
+    /// 未知或为空的 provider 一律视为 OpenAI 兼容。
+    pub fn from_provider(provider: &str, base_url: &str) -> Self {
+        let provider = provider.trim().to_ascii_lowercase();
+        let base = base_url.trim().trim_end_matches('/');
+        match provider.as_str() {
+            "anthropic" | "claude" => Self::Anthropic,
+            "gemini" | "google" if !base.ends_with("/openai") => Self::Gemini,
+            "ollama" if !base.ends_with("/v1") => Self::Ollama,
+            _ => Self::OpenAI,
+        }
+    }
+
+    pub fn from_config(config: &AIServiceConfig) -> Self {
+        Self::from_provider(&config.provider, &config.base_url)
+    }
+
+    /// 未配置 base URL 时使用的默认地址
+    pub fn default_base_url(self) -> &'static str {
+        match self {
+            Self::OpenAI => "https://api.openai.com/v1",
+            Self::Anthropic => "https://api.anthropic.com/v1",
+            Self::Gemini => "https://generativelanguage.googleapis.com/v1beta",
+            Self::Ollama => "http://localhost:11434",
+        }
+    }
+
+    pub fn stream_framing(self) -> StreamFraming {
+        match self {
+            Self::Ollama => StreamFraming::Ndjson,
+            _ => StreamFraming::Sse,
+        }
+    }
+
+    fn base_url(self, config: &AIServiceConfig) -> String {
+        let base = config.base_url.trim().trim_end_matches('/');
+        let base = if base.is_empty() { self.default_base_url() } else { base };
+        match self {
+            // 用户可能填写了 `http://host:11434/api`
+            Self::Ollama => base.trim_end_matches("/api").to_string(),
+            _ => base.to_string(),
+        }
+    }
+
+    // ============================================================
+    // 请求构建
+    // ============================================================
+
+    /// 构建请求（URL、鉴权头与请求体）
+    pub fn build_request(
+        self,
+        config: &AIServiceConfig,
+        system_prompt: &str,
+        user_prompt: &str,
+        stream: bool,
+    ) -> ProviderRequest {
+        let base = self.base_url(config);
+        let api_key = config.api_key.trim();
+        let mut headers = vec![("Content-Type", "application/json".to_string())];
+        let temperature = config.temperature as f64;
+
+        let (url, body) = match self {
+            Self::OpenAI => {
+                if !api_key.is_empty() {
+                    headers.push(("Authorization", format!("Bearer {}", api_key)));
+                }
+                let mut body = json!({
+                    "messages": [
+                        { "role": "system", "content": system_prompt },
+                        { "role": "user", "content": user_prompt }
+                    ],
+                    "model": config.model,
+                    "temperature": temperature,
+                    "stream": stream
+                });
+                if config.max_tokens > 0 {
+                    body["max_tokens"] = json!(config.max_tokens);
+                }
+                if stream {
+                    // 默认流式响应不含用量，需显式请求在最后一个分块中返回
+                    body["stream_options"] = json!({ "include_usage": true });
+                }
+                (format!("{}/chat/completions", base), body)
+            }
+            Self::Anthropic => {
+                headers.push(("x-api-key", api_key.to_string()));
+                headers.push(("anthropic-version", ANTHROPIC_VERSION.to_string()));
+                let max_tokens = if config.max_tokens > 0 {
+                    config.max_tokens
+                } else {
+                    ANTHROPIC_DEFAULT_MAX_TOKENS
+                };
+                let mut body = json!({
+                    "model": config.model,
+                    "messages": [{ "role": "user", "content": user_prompt }],
+                    "max_tokens": max_tokens,
+                    "temperature": temperature,
+                    "stream": stream
+                });
+                if !system_prompt.is_empty() {
+                    body["system"] = json!(system_prompt);
+                }
+                (format!("{}/messages", base), body)
+            }
+            Self::Gemini => {
+                headers.push(("x-goog-api-key", api_key.to_string()));
+                let mut generation = json!({ "temperature": temperature });
+                if config.max_tokens > 0 {
+                    generation["maxOutputTokens"] = json!(config.max_tokens);
+                }
+                let mut body = json!({
+                    "contents": [{ "role": "user", "parts": [{ "text": user_prompt }] }],
+                    "generationConfig": generation
+                });
+                if !system_prompt.is_empty() {
+                    body["systemInstruction"] = json!({ "parts": [{ "text": system_prompt }] });
+                }
+                let model = config.model.trim().trim_start_matches("models/");
+                let url = if stream {
+                    format!("{}/models/{}:streamGenerateContent?alt=sse", base, model)
+                } else {
+                    format!("{}/models/{}:generateContent", base, model)
+                };
+                (url, body)
+            }
+            Self::Ollama => {
+                if !api_key.is_empty() {
+                    headers.push(("Authorization", format!("Bearer {}", api_key)));
+                }
+                let mut options = json!({ "temperature": temperature });
+                if config.max_tokens > 0 {
+                    options["num_predict"] = json!(config.max_tokens);
+                }
+                let body = json!({
+                    "model": config.model,
+                    "messages": [
+                        { "role": "system", "content": system_prompt },
+                        { "role": "user", "content": user_prompt }
+                    ],
+                    "stream": stream,
+                    "options": options
+                });
+                (format!("{}/api/chat", base), body)
+            }
+        };
+
+        ProviderRequest { url, headers, body }
+    }
+
+    /// 构建模型列表请求（GET，body 为 Null）
+    pub fn build_models_request(self, config: &AIServiceConfig) -> ProviderRequest {
+        let base = self.base_url(config);
+        let api_key = config.api_key.trim();
+        let mut headers = Vec::new();
+        let url = match self {
+            Self::OpenAI => {
+                if !api_key.is_empty() {
+                    headers.push(("Authorization", format!("Bearer {}", api_key)));
+                }
+                format!("{}/models", base)
+            }
+            Self::Anthropic => {
+                headers.push(("x-api-key", api_key.to_string()));
+                headers.push(("anthropic-version", ANTHROPIC_VERSION.to_string()));
+                format!("{}/models?limit=1000", base)
+            }
+            Self::Gemini => {
+                headers.push(("x-goog-api-key", api_key.to_string()));
+                format!("{}/models?pageSize=1000", base)
+            }
+            Self::Ollama => {
+                if !api_key.is_empty() {
+                    headers.push(("Authorization", format!("Bearer {}", api_key)));
+                }
+                format!("{}/api/tags", base)
+            }
+        };
+        ProviderRequest {
+            url,
+            headers,
+            body: Value::Null,
+        }
+    }
+
+    /// 解析模型列表响应，返回排序去重后的模型 ID
+    pub fn parse_models(self, resp: &Value) -> Result<Vec<String>, String> {
+        if let Some(err) = error_message(resp) {
+            return Err(format!("AI API 返回错误: {}", err));
+        }
+        let str_field = |item: &Value, field: &str| {
+            item.get(field).and_then(|v| v.as_str()).map(|s| s.to_string())
+        };
+        let mut models: Vec<String> = match self {
+            Self::OpenAI | Self::Anthropic => resp
+                .get("data")
+                .and_then(|d| d.as_array())
+                .ok_or("模型列表格式不正确")?
+                .iter()
+                .filter_map(|m| str_field(m, "id"))
+                .collect(),
+            Self::Gemini => resp
+                .get("models")
+                .and_then(|d| d.as_array())
+                .ok_or("模型列表格式不正确")?
+                .iter()
+                // 只保留支持文本生成的模型（排除 embedding 等）
+                .filter(|m| {
+                    m.get("supportedGenerationMethods")
+                        .and_then(|v| v.as_array())
+                        .is_none_or(|methods| {
+                            methods.iter().any(|v| v.as_str() == Some("generateContent"))
+                        })
+                })
+                .filter_map(|m| str_field(m, "name"))
+                .map(|name| name.trim_start_matches("models/").to_string())
+                .collect(),
+            Self::Ollama => resp
+                .get("models")
+                .and_then(|d| d.as_array())
+                .ok_or("模型列表格式不正确")?
+                .iter()
+                .filter_map(|m| str_field(m, "name").or_else(|| str_field(m, "model")))
+                .collect(),
+        };
+        models.sort();
+        models.dedup();
+        Ok(models)
+    }
+
+    // ============================================================
+    // 响应解析
+    // ============================================================
+
+    /// 解析非流式响应
+    pub fn parse_response(self, resp: &Value) -> Result<AICompletion, String> {
+        if let Some(err) = error_message(resp) {
+            return Err(format!("AI API 返回错误: {}", err));
+        }
+        let content = match self {
+            Self::OpenAI => resp
+                .pointer("/choices/0/message/content")
+                .and_then(|c| c.as_str())
+                .map(|s| s.to_string()),
+            Self::Anthropic => resp.get("content").and_then(|c| c.as_array()).map(|blocks| {
+                blocks
+                    .iter()
+                    .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+                    .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                    .collect::<String>()
+            }),
+            Self::Gemini => gemini_text(resp),
+            Self::Ollama => resp
+                .pointer("/message/content")
+                .and_then(|c| c.as_str())
+                .map(|s| s.to_string()),
+        };
+        let content = content.ok_or_else(|| "AI 未返回内容".to_string())?;
+        let usage = self.parse_usage(resp).filter(|u| !u.is_empty());
+        Ok(AICompletion { content, usage })
+    }
+
+    /// 提取用量字段
+    fn parse_usage(self, value: &Value) -> Option<TokenUsage> {
+        let field = |v: Option<&Value>| v.and_then(|n| n.as_u64()).map(|n| n as u32);
+        match self {
+            Self::OpenAI => value.get("usage").filter(|u| u.is_object()).map(|u| TokenUsage {
+                input_tokens: field(u.get("prompt_tokens")),
+                output_tokens: field(u.get("completion_tokens")),
+            }),
+            Self::Anthropic => value.get("usage").map(|u| TokenUsage {
+                input_tokens: field(u.get("input_tokens")),
+                output_tokens: field(u.get("output_tokens")),
+            }),
+            Self::Gemini => value.get("usageMetadata").map(|u| TokenUsage {
+                input_tokens: field(u.get("promptTokenCount")),
+                output_tokens: field(u.get("candidatesTokenCount")),
+            }),
+            Self::Ollama => {
+                let usage = TokenUsage {
+                    input_tokens: field(value.get("prompt_eval_count")),
+                    output_tokens: field(value.get("eval_count")),
+                };
+                Some(usage).filter(|u| !u.is_empty())
+            }
+        }
+    }
+
+    /// 解析一个流式事件
+    ///
+    /// `event` 为 SSE 的 `event:` 字段（NDJSON 时为 None），`data` 为事件数据或一行 JSON。
+    pub fn parse_stream_event(self, event: Option<&str>, data: &str) -> Vec<StreamEvent> {
+        let data = data.trim();
+        if data.is_empty() {
+            return Vec::new();
+        }
+        if data == "[DONE]" {
+            return vec![StreamEvent::Done];
+        }
+        let value: Value = match serde_json::from_str(data) {
+            Ok(v) => v,
+            Err(_) => return Vec::new(),
+        };
+        if let Some(err) = error_message(&value) {
+            return vec![StreamEvent::Error(err)];
+        }
+
+        let mut events = Vec::new();
+        match self {
+            Self::OpenAI => {
+                if let Some(delta) = value
+                    .pointer("/choices/0/delta/content")
+                    .and_then(|c| c.as_str())
+                    .filter(|s| !s.is_empty())
+                {
+                    events.push(StreamEvent::Delta(delta.to_string()));
+                }
+                if let Some(usage) = self.parse_usage(&value) {
+                    events.push(StreamEvent::Usage(usage));
+                }
+            }
+            Self::Anthropic => {
+                let kind = value
+                    .get("type")
+                    .and_then(|t| t.as_str())
+                    .or(event)
+                    .unwrap_or_default();
+                match kind {
+                    "message_start" => {
+                        if let Some(usage) = value.get("message").and_then(|m| self.parse_usage(m))
+                        {
+                            events.push(StreamEvent::Usage(usage));
+                        }
+                    }
+                    "content_block_delta" => {
+                        if let Some(text) = value.pointer("/delta/text").and_then(|t| t.as_str()) {
+                            events.push(StreamEvent::Delta(text.to_string()));
+                        }
+                    }
+                    "message_delta" => {
+                        if let Some(usage) = self.parse_usage(&value) {
+                            events.push(StreamEvent::Usage(usage));
+                        }
+                    }
+                    "message_stop" => events.push(StreamEvent::Done),
+                    _ => {}
+                }
+            }
+            Self::Gemini => {
+                if let Some(text) = gemini_text(&value).filter(|s| !s.is_empty()) {
+                    events.push(StreamEvent::Delta(text));
+                }
+                if let Some(usage) = self.parse_usage(&value) {
+                    events.push(StreamEvent::Usage(usage));
+                }
+            }
+            Self::Ollama => {
+                if let Some(text) = value
+                    .pointer("/message/content")
+                    .and_then(|c| c.as_str())
+                    .filter(|s| !s.is_empty())
+                {
+                    events.push(StreamEvent::Delta(text.to_string()));
+                }
+                if value.get("done").and_then(|d| d.as_bool()) == Some(true) {
+                    if let Some(usage) = self.parse_usage(&value) {
+                        events.push(StreamEvent::Usage(usage));
+                    }
+                    events.push(StreamEvent::Done);
+                }
+            }
+        }
+        events
+    }
+}
+
+/// 拼接 Gemini 第一个候选的全部文本片段
+fn gemini_text(value: &Value) -> Option<String> {
+    value
+        .pointer("/candidates/0/content/parts")
+        .and_then(|p| p.as_array())
+        .map(|parts| {
+            parts
+                .iter()
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect::<String>()
+        })
+}
+
+/// 各提供商的错误结构均为 `{"error": {"message": ...}}` 或 `{"error": "..."}`
+fn error_message(value: &Value) -> Option<String> {
+    let err = value.get("error")?;
+    if err.is_null() {
+        return None;
+    }
+    let message = err
+        .get("message")
+        .and_then(|m| m.as_str())
+        .or_else(|| err.as_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| err.to_string());
+    Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(provider: &str, base_url: &str) -> AIServiceConfig {
+        AIServiceConfig {
+            provider: provider.to_string(),
+            base_url: base_url.to_string(),
+            api_key: "sk-test".to_string(),
+            model: "test-model".to_string(),
+            max_tokens: 0,
+            temperature: 0.5,
+        }
+    }
+
+    fn header<'a>(request: &'a ProviderRequest, name: &str) -> Option<&'a str> {
+        request
+            .headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn usage(input: u32, output: u32) -> TokenUsage {
+        TokenUsage {
+            input_tokens: Some(input),
+            output_tokens: Some(output),
+        }
+    }
+
+    #[test]
+    fn selects_protocol_from_provider_and_base_url() {
+        let kind = ProviderKind::from_provider;
+        assert_eq!(kind("anthropic", ""), ProviderKind::Anthropic);
+        assert_eq!(kind("Claude", ""), ProviderKind::Anthropic);
+        assert_eq!(kind("gemini", ""), ProviderKind::Gemini);
+        assert_eq!(kind("gemini", "https://x/v1beta/openai/"), ProviderKind::OpenAI);
+        assert_eq!(kind("ollama", "http://localhost:11434"), ProviderKind::Ollama);
+        assert_eq!(kind("ollama", "http://localhost:11434/v1"), ProviderKind::OpenAI);
+        assert_eq!(kind("deepseek", ""), ProviderKind::OpenAI);
+        assert_eq!(kind("", ""), ProviderKind::OpenAI);
+    }
+
+    // ============================================================
+    // OpenAI
+    // ============================================================
+
+    #[test]
+    fn openai_build_request() {
+        let mut cfg = config("openai", "https://api.example.com/v1/");
+        cfg.max_tokens = 100;
+        let req = ProviderKind::OpenAI.build_request(&cfg, "sys", "user", true);
+        assert_eq!(req.url, "https://api.example.com/v1/chat/completions");
+        assert_eq!(header(&req, "Authorization"), Some("Bearer sk-test"));
+        assert_eq!(req.body["model"], "test-model");
+        assert_eq!(req.body["messages"][0]["role"], "system");
+        assert_eq!(req.body["messages"][1]["content"], "user");
+        assert_eq!(req.body["max_tokens"], 100);
+        assert_eq!(req.body["stream_options"]["include_usage"], true);
+
+        cfg.api_key = String::new();
+        let req = ProviderKind::OpenAI.build_request(&cfg, "sys", "user", false);
+        assert_eq!(header(&req, "Authorization"), None);
+        assert!(req.body.get("stream_options").is_none());
+    }
+
+    #[test]
+    fn openai_parse_response() {
+        let resp = json!({
+            "choices": [{ "message": { "role": "assistant", "content": "hello" } }],
+            "usage": { "prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15 }
+        });
+        let completion = ProviderKind::OpenAI.parse_response(&resp).unwrap();
+        assert_eq!(completion.content, "hello");
+        assert_eq!(completion.usage, Some(usage(12, 3)));
+
+        let err = json!({ "error": { "message": "invalid api key", "type": "auth" } });
+        let msg = ProviderKind::OpenAI.parse_response(&err).unwrap_err();
+        assert!(msg.contains("invalid api key"), "{}", msg);
+    }
+
+    #[test]
+    fn openai_parse_stream_event() {
+        let p = ProviderKind::OpenAI;
+        let delta = r#"{"choices":[{"index":0,"delta":{"content":"Hi"}}],"usage":null}"#;
+        assert_eq!(p.parse_stream_event(None, delta), vec![StreamEvent::Delta("Hi".into())]);
+        let last = r#"{"choices":[],"usage":{"prompt_tokens":5,"completion_tokens":2}}"#;
+        assert_eq!(p.parse_stream_event(None, last), vec![StreamEvent::Usage(usage(5, 2))]);
+        assert_eq!(p.parse_stream_event(None, "[DONE]"), vec![StreamEvent::Done]);
+        assert_eq!(p.parse_stream_event(None, "  "), vec![]);
+        assert_eq!(p.parse_stream_event(None, "not json"), vec![]);
+    }
+
+    // ============================================================
+    // Anthropic
+    // ============================================================
+
+    #[test]
+    fn anthropic_build_request() {
+        let cfg = config("anthropic", "https://api.anthropic.com/v1");
+        let req = ProviderKind::Anthropic.build_request(&cfg, "sys", "user", false);
+        assert_eq!(req.url, "https://api.anthropic.com/v1/messages");
+        assert_eq!(header(&req, "x-api-key"), Some("sk-test"));
+        assert_eq!(header(&req, "anthropic-version"), Some(ANTHROPIC_VERSION));
+        assert_eq!(header(&req, "Authorization"), None);
+        assert_eq!(req.body["system"], "sys");
+        assert_eq!(req.body["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(req.body["max_tokens"], ANTHROPIC_DEFAULT_MAX_TOKENS);
+
+        let req = ProviderKind::Anthropic.build_request(&cfg, "", "user", true);
+        assert!(req.body.get("system").is_none());
+        assert_eq!(req.body["stream"], true);
+    }
+
+    #[test]
+    fn anthropic_parse_response() {
+        let resp = json!({
+            "type": "message",
+            "content": [
+                { "type": "text", "text": "foo" },
+                { "type": "tool_use", "id": "t1", "name": "x", "input": {} },
+                { "type": "text", "text": "bar" }
+            ],
+            "usage": { "input_tokens": 20, "output_tokens": 4 }
+        });
+        let completion = ProviderKind::Anthropic.parse_response(&resp).unwrap();
+        assert_eq!(completion.content, "foobar");
+        assert_eq!(completion.usage, Some(usage(20, 4)));
+
+        let err = json!({
+            "type": "error",
+            "error": { "type": "overloaded_error", "message": "Overloaded" }
+        });
+        assert!(ProviderKind::Anthropic.parse_response(&err).unwrap_err().contains("Overloaded"));
+    }
+
+    #[test]
+    fn anthropic_parse_stream_event() {
+        let p = ProviderKind::Anthropic;
+        let start = r#"{"type":"message_start",
+            "message":{"usage":{"input_tokens":9,"output_tokens":1}}}"#;
+        assert_eq!(
+            p.parse_stream_event(Some("message_start"), start),
+            vec![StreamEvent::Usage(usage(9, 1))]
+        );
+        let delta = r#"{"type":"content_block_delta","index":0,
+            "delta":{"type":"text_delta","text":"Hi"}}"#;
+        assert_eq!(
+            p.parse_stream_event(Some("content_block_delta"), delta),
+            vec![StreamEvent::Delta("Hi".into())]
+        );
+        // 事件类型缺失时回退到 SSE 的 event 字段
+        let untyped = r#"{"delta":{"type":"text_delta","text":"!"}}"#;
+        assert_eq!(
+            p.parse_stream_event(Some("content_block_delta"), untyped),
+            vec![StreamEvent::Delta("!".into())]
+        );
+        let end = r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"},
+            "usage":{"output_tokens":7}}"#;
+        assert_eq!(
+            p.parse_stream_event(Some("message_delta"), end),
+            vec![StreamEvent::Usage(TokenUsage { input_tokens: None, output_tokens: Some(7) })]
+        );
+        assert_eq!(p.parse_stream_event(Some("ping"), r#"{"type":"ping"}"#), vec![]);
+        assert_eq!(
+            p.parse_stream_event(Some("message_stop"), r#"{"type":"message_stop"}"#),
+            vec![StreamEvent::Done]
+        );
+        let err = r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert_eq!(
+            p.parse_stream_event(Some("error"), err),
+            vec![StreamEvent::Error("Overloaded".into())]
+        );
+    }
+
+    // ============================================================
+    // Gemini
+    // ============================================================
+
+    #[test]
+    fn gemini_build_request() {
+        let mut cfg = config("gemini", "");
+        cfg.model = "models/gemini-pro".to_string();
+        cfg.max_tokens = 256;
+        let req = ProviderKind::Gemini.build_request(&cfg, "sys", "user", false);
+        assert_eq!(
+            req.url,
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-pro:generateContent"
+        );
+        assert_eq!(header(&req, "x-goog-api-key"), Some("sk-test"));
+        assert_eq!(req.body["contents"][0]["parts"][0]["text"], "user");
+        assert_eq!(req.body["systemInstruction"]["parts"][0]["text"], "sys");
+        assert_eq!(req.body["generationConfig"]["maxOutputTokens"], 256);
+
+        let req = ProviderKind::Gemini.build_request(&cfg, "sys", "user", true);
+        let stream_url = "/models/gemini-pro:streamGenerateContent?alt=sse";
+        assert!(req.url.ends_with(stream_url), "{}", req.url);
+    }
+
+    #[test]
+    fn gemini_parse_response() {
+        let resp = json!({
+            "candidates": [{
+                "content": { "role": "model", "parts": [{ "text": "a" }, { "text": "b" }] },
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": { "promptTokenCount": 6, "candidatesTokenCount": 2 }
+        });
+        let completion = ProviderKind::Gemini.parse_response(&resp).unwrap();
+        assert_eq!(completion.content, "ab");
+        assert_eq!(completion.usage, Some(usage(6, 2)));
+
+        let blocked = json!({ "promptFeedback": { "blockReason": "SAFETY" } });
+        assert!(ProviderKind::Gemini.parse_response(&blocked).is_err());
+    }
+
+    #[test]
+    fn gemini_parse_stream_event() {
+        let p = ProviderKind::Gemini;
+        let chunk = r#"{"candidates":[{"content":{"parts":[{"text":"Hi"}]}}]}"#;
+        assert_eq!(p.parse_stream_event(None, chunk), vec![StreamEvent::Delta("Hi".into())]);
+        let last = r#"{"candidates":[{"content":{"parts":[{"text":"!"}]},"finishReason":"STOP"}],
+            "usageMetadata":{"promptTokenCount":4,"candidatesTokenCount":3}}"#;
+        assert_eq!(
+            p.parse_stream_event(None, last),
+            vec![StreamEvent::Delta("!".into()), StreamEvent::Usage(usage(4, 3))]
+        );
+    }
+
+    // ============================================================
+    // Ollama
+    // ============================================================
+
+    #[test]
+    fn ollama_build_request() {
+        let mut cfg = config("ollama", "http://localhost:11434/api/");
+        cfg.api_key = String::new();
+        cfg.max_tokens = 64;
+        let req = ProviderKind::Ollama.build_request(&cfg, "sys", "user", true);
+        assert_eq!(req.url, "http://localhost:11434/api/chat");
+        assert_eq!(header(&req, "Authorization"), None);
+        assert_eq!(req.body["options"]["num_predict"], 64);
+        assert_eq!(req.body["stream"], true);
+        assert_eq!(ProviderKind::Ollama.stream_framing(), StreamFraming::Ndjson);
+    }
+
+    #[test]
+    fn ollama_parse_response() {
+        let resp = json!({
+            "message": { "role": "assistant", "content": "ok" },
+            "done": true,
+            "prompt_eval_count": 8,
+            "eval_count": 5
+        });
+        let completion = ProviderKind::Ollama.parse_response(&resp).unwrap();
+        assert_eq!(completion.content, "ok");
+        assert_eq!(completion.usage, Some(usage(8, 5)));
+
+        let err = json!({ "error": "model 'x' not found" });
+        assert!(ProviderKind::Ollama.parse_response(&err).unwrap_err().contains("not found"));
+    }
+
+    #[test]
+    fn ollama_parse_stream_event() {
+        let p = ProviderKind::Ollama;
+        let line = r#"{"message":{"role":"assistant","content":"Hi"},"done":false}"#;
+        assert_eq!(p.parse_stream_event(None, line), vec![StreamEvent::Delta("Hi".into())]);
+        let last = r#"{"message":{"role":"assistant","content":""},"done":true,
+            "prompt_eval_count":3,"eval_count":2}"#;
+        assert_eq!(
+            p.parse_stream_event(None, last),
+            vec![StreamEvent::Usage(usage(3, 2)), StreamEvent::Done]
+        );
+    }
+
+    #[test]
+    fn parse_models_per_provider() {
+        let openai = json!({ "data": [{ "id": "b" }, { "id": "a" }, { "id": "a" }] });
+        assert_eq!(ProviderKind::OpenAI.parse_models(&openai).unwrap(), vec!["a", "b"]);
+        let gemini = json!({ "models": [
+            { "name": "models/gemini-pro", "supportedGenerationMethods": ["generateContent"] },
+            { "name": "models/embedding", "supportedGenerationMethods": ["embedContent"] }
+        ] });
+        assert_eq!(ProviderKind::Gemini.parse_models(&gemini).unwrap(), vec!["gemini-pro"]);
+        let ollama = json!({ "models": [{ "name": "llama3:8b" }, { "model": "qwen2" }] });
+        let models = ProviderKind::Ollama.parse_models(&ollama).unwrap();
+        assert_eq!(models, vec!["llama3:8b", "qwen2"]);
+    }
+}
@@ -0,0 +1,587 @@
+//! AI 服务列表与凭据解析
+//!
+//! API Key 只在 Rust 端使用：列表命令返回脱敏后的 Key，前端按服务 ID 发起生成，
+//! 由 [`resolve_service`] 依次查找资源管理器本地服务列表与主程序共享服务列表。
+//! 本地服务列表与旧版配置中的 Key 经 [`secret_store`] 加密保存，读取时透明解密；
+//! 主程序共享列表由主程序维护，这里只读取不改写。
+
+use crate::fs_utils::write_atomic;
+use crate::secret_store::{self, SecretKey};
+use crate::types::{AIServiceConfig, LocalAIServices, SharedAIServices};
+use std::path::PathBuf;
+
+/// 脱敏 Key 中的占位部分，同时用于识别前端回传的脱敏值
+const MASK: &str = "****";
+
+/// 未填写 base URL 时按提供商补全（与前端 PROVIDER_BASE_URLS 保持一致）
+const PROVIDER_BASE_URLS: &[(&str, &str)] = &[
+    ("openai", "https://api.openai.com/v1"),
+    ("anthropic", "https://api.anthropic.com/v1"),
+    ("gemini", "https://generativelanguage.googleapis.com/v1beta/openai"),
+    ("xai", "https://api.x.ai/v1"),
+    ("deepseek", "https://api.deepseek.com"),
+    ("qwen", "https://dashscope.aliyuncs.com/compatible-mode/v1"),
+    ("glm", "https://open.bigmodel.cn/api/paas/v4"),
+    ("glm-code", "https://open.bigmodel.cn/api/coding/paas/v4"),
+    ("minimax", "https://api.minimaxi.com/v1"),
+    ("minimax-code", "https://api.minimaxi.com/v1"),
+    ("kimi", "https://api.moonshot.cn/v1"),
+    ("kimi-code", "https://api.kimi.com/coding/v1"),
+    ("ollama", "http://localhost:11434"),
+];
+
+/// 旧版单服务配置加密时的附加数据
+const LEGACY_KEY_CONTEXT: &str = "legacy-config";
+
+/// 未配置温度时使用的默认值
+const DEFAULT_TEMPERATURE: f32 = 0.7;
+
+// ============================================================
+// 文件路径与读写
+// ============================================================
+
+pub(crate) fn aidocplus_dir() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("无法获取用户主目录")?;
+    Ok(home.join(".aidocplus"))
+}
+
+/// 主程序共享的 AI 服务列表（~/.aidocplus/ai-services.json）
+pub fn shared_services_path() -> Result<PathBuf, String> {
+    Ok(aidocplus_dir()?.join("ai-services.json"))
+}
+
+/// 资源管理器本地 AI 服务列表（~/.aidocplus/manager-ai-services.json）
+pub fn local_services_path() -> Result<PathBuf, String> {
+    Ok(aidocplus_dir()?.join("manager-ai-services.json"))
+}
+
+/// 资源管理器旧版单服务配置（~/.aidocplus/manager-ai-config.json）
+pub fn legacy_config_path() -> Result<PathBuf, String> {
+    Ok(aidocplus_dir()?.join("manager-ai-config.json"))
+}
+
+pub fn load_shared_services() -> Result<SharedAIServices, String> {
+    let path = shared_services_path()?;
+    if !path.exists() {
+        return Ok(SharedAIServices {
+            services: vec![],
+            active_service_id: String::new(),
+            temperature: DEFAULT_TEMPERATURE,
+            max_tokens: 4096,
+        });
+    }
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("读取共享 AI 服务列表失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析共享 AI 服务列表失败: {}", e))
+}
+
+/// 读取本地服务列表文件（API Key 保持文件中的加密形式）
+fn read_local_services() -> Result<LocalAIServices, String> {
+    let path = local_services_path()?;
+    if !path.exists() {
+        return Ok(LocalAIServices {
+            services: vec![],
+            active_service_id: String::new(),
+        });
+    }
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("读取本地 AI 服务列表失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析本地 AI 服务列表失败: {}", e))
+}
+
+/// 加密 API Key 后写入本地服务列表
+fn write_local_services(data: &LocalAIServices, key: &SecretKey) -> Result<(), String> {
+    let mut data = data.clone();
+    for svc in &mut data.services {
+        svc.api_key = secret_store::seal_with(key, &svc.api_key, &local_key_context(&svc.id))?;
+    }
+    let path = local_services_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    let content = serde_json::to_string_pretty(&data)
+        .map_err(|e| format!("序列化本地 AI 服务列表失败: {}", e))?;
+    write_atomic(&path, &content).map_err(|e| format!("写入本地 AI 服务列表失败: {}", e))
+}
+
+/// 加密时的附加数据，密文与服务 ID 绑定
+fn local_key_context(service_id: &str) -> String {
+    format!("local-service:{}", service_id)
+}
+
+/// 读取本地服务列表并解密 API Key
+///
+/// 文件中仍有明文 Key（旧版本写入）时，在密钥可用的情况下立即改写为加密形式。
+pub fn load_local_services() -> Result<LocalAIServices, String> {
+    let mut data = read_local_services()?;
+    let has_plaintext = data
+        .services
+        .iter()
+        .any(|s| !s.api_key.is_empty() && !secret_store::is_encrypted(&s.api_key));
+    for svc in &mut data.services {
+        svc.api_key = secret_store::open(&svc.api_key, &local_key_context(&svc.id))?;
+    }
+    if has_plaintext {
+        if let Ok(key) = secret_store::current_key() {
+            if let Err(e) = write_local_services(&data, &key) {
+                eprintln!("[AI Services] 迁移明文 API Key 失败: {}", e);
+            }
+        }
+    }
+    Ok(data)
+}
+
+/// 保存本地服务列表
+///
+/// 前端只持有脱敏 Key，回传的脱敏值按服务 ID 还原为原 Key：
+/// 先匹配已保存的本地服务，再匹配主程序共享服务（从主程序导入时）。
+/// 只有提供商与 base URL 都未改动时才还原，修改了地址的服务必须重新填写 Key。
+pub fn save_local_services(mut data: LocalAIServices) -> Result<(), String> {
+    let key = secret_store::current_key()?;
+    let existing = load_local_services().ok();
+    let shared = load_shared_services().ok();
+    for svc in &mut data.services {
+        if !is_masked(&svc.api_key) {
+            continue;
+        }
+        let local_saved = existing
+            .as_ref()
+            .and_then(|e| e.services.iter().find(|s| s.id == svc.id))
+            .map(|s| (s.provider.as_str(), s.base_url.as_str(), s.api_key.as_str()));
+        let shared_saved = shared
+            .as_ref()
+            .and_then(|e| e.services.iter().find(|s| s.id == svc.id))
+            .map(|s| (s.provider.as_str(), s.base_url.as_str(), s.api_key.as_str()));
+        let restored = [local_saved, shared_saved]
+            .into_iter()
+            .flatten()
+            .find(|(provider, base_url, key)| {
+                same_endpoint(&svc.provider, &svc.base_url, provider, base_url)
+                    && mask_api_key(key) == svc.api_key
+            })
+            .map(|(_, _, key)| key)
+            .ok_or_else(|| format!("服务「{}」的 API Key 无法还原，请重新填写", svc.name))?;
+        svc.api_key = restored.to_string();
+    }
+    write_local_services(&data, &key)
+}
+
+/// 两组提供商与 base URL 是否指向同一端点（base URL 为空时按提供商默认地址比较）
+///
+/// 脱敏 Key 只在端点未变时还原，避免把已保存的 Key 发往前端新填写的地址。
+fn same_endpoint(
+    provider: &str,
+    base_url: &str,
+    saved_provider: &str,
+    saved_base_url: &str,
+) -> bool {
+    let normalize = |provider: &str, base_url: &str| {
+        let provider = provider.trim().to_ascii_lowercase();
+        let base = base_url.trim().trim_end_matches('/');
+        let base = if base.is_empty() {
+            provider_base_url(&provider).unwrap_or_default().trim_end_matches('/')
+        } else {
+            base
+        };
+        (base.to_string(), provider)
+    };
+    normalize(provider, base_url) == normalize(saved_provider, saved_base_url)
+}
+
+/// 读取旧版单服务配置文件（API Key 保持加密形式）
+fn read_legacy_config() -> Result<Option<AIServiceConfig>, String> {
+    let config_path = legacy_config_path()?;
+    if !config_path.exists() {
+        return Ok(None);
+    }
+    let content =
+        std::fs::read_to_string(&config_path).map_err(|e| format!("读取 AI 配置失败: {}", e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("解析 AI 配置失败: {}", e))
+}
+
+fn write_legacy_config(config: &AIServiceConfig, key: &SecretKey) -> Result<(), String> {
+    let mut config = config.clone();
+    config.api_key = secret_store::seal_with(key, &config.api_key, LEGACY_KEY_CONTEXT)?;
+    let config_path = legacy_config_path()?;
+    if let Some(parent) = config_path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    let content = serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("序列化 AI 配置失败: {}", e))?;
+    write_atomic(&config_path, &content).map_err(|e| format!("写入 AI 配置失败: {}", e))
+}
+
+/// 读取旧版单服务配置并解密 API Key，明文 Key 同样会被迁移
+pub fn load_legacy_config() -> Result<AIServiceConfig, String> {
+    let Some(mut config) = read_legacy_config()? else {
+        return Ok(AIServiceConfig {
+            provider: String::new(),
+            base_url: "https://api.openai.com/v1".to_string(),
+            api_key: String::new(),
+            model: "gpt-4o".to_string(),
+            max_tokens: 4096,
+            temperature: DEFAULT_TEMPERATURE,
+        });
+    };
+    let has_plaintext =
+        !config.api_key.is_empty() && !secret_store::is_encrypted(&config.api_key);
+    config.api_key = secret_store::open(&config.api_key, LEGACY_KEY_CONTEXT)?;
+    if has_plaintext {
+        if let Ok(key) = secret_store::current_key() {
+            if let Err(e) = write_legacy_config(&config, &key) {
+                eprintln!("[AI Services] 迁移明文 API Key 失败: {}", e);
+            }
+        }
+    }
+    Ok(config)
+}
+
+/// 保存旧版单服务配置，回传的脱敏 Key 在提供商与 base URL 未变时还原为已保存的 Key
+pub fn save_legacy_config(mut config: AIServiceConfig) -> Result<(), String> {
+    let key = secret_store::current_key()?;
+    if is_masked(&config.api_key) {
+        let old = load_legacy_config()?;
+        let same = same_endpoint(&config.provider, &config.base_url, &old.provider, &old.base_url);
+        if !same || mask_api_key(&old.api_key) != config.api_key {
+            return Err("API Key 无法还原，请重新填写".to_string());
+        }
+        config.api_key = old.api_key;
+    }
+    write_legacy_config(&config, &key)
+}
+
+// ============================================================
+// 加密口令
+// ============================================================
+
+/// 切换加密方式：`passphrase` 为 None 时改回随机密钥文件，否则由口令派生密钥
+///
+/// 先用当前密钥解密全部 Key（口令模式下需已解锁），保存新密钥文件后重新加密写回；
+/// 写回失败时回滚密钥文件，已保存的 Key 仍可用旧密钥解密。
+pub fn change_passphrase(passphrase: Option<&str>) -> Result<(), String> {
+    let local = load_local_services()?;
+    let legacy = match read_legacy_config()? {
+        Some(_) => Some(load_legacy_config()?),
+        None => None,
+    };
+
+    let (key_file, key) = secret_store::prepare_key(passphrase)?;
+    let previous = secret_store::install_key(&key_file, &key)?;
+    let written = write_local_services(&local, &key).and_then(|_| match &legacy {
+        Some(config) => write_legacy_config(config, &key),
+        None => Ok(()),
+    });
+    if let Err(e) = written {
+        secret_store::restore_key_file(previous)?;
+        // 回滚后用旧密钥重新写回，避免两个文件使用不同的密钥
+        if let Ok(old_key) = secret_store::current_key() {
+            let _ = write_local_services(&local, &old_key);
+            if let Some(config) = &legacy {
+                let _ = write_legacy_config(config, &old_key);
+            }
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+// ============================================================
+// 不含 Key 的导出 / 导入
+// ============================================================
+
+/// 导出本地服务列表，所有 API Key 置空
+pub fn export_services(output_path: &str) -> Result<u32, String> {
+    let mut data = read_local_services()?;
+    for svc in &mut data.services {
+        svc.api_key.clear();
+    }
+    let content = serde_json::to_string_pretty(&data)
+        .map_err(|e| format!("序列化 AI 服务列表失败: {}", e))?;
+    std::fs::write(output_path, content).map_err(|e| format!("写入导出文件失败: {}", e))?;
+    Ok(data.services.len() as u32)
+}
+
+/// 导入服务列表，按 ID 合并：已有服务在提供商与地址不变时保留原 API Key，否则 Key 为空待填写
+///
+/// 导入文件中的明文 Key 会被采用；加密或脱敏的 Key 无法在本机解密，一律忽略。
+pub fn import_services(input_path: &str) -> Result<u32, String> {
+    let content =
+        std::fs::read_to_string(input_path).map_err(|e| format!("读取导入文件失败: {}", e))?;
+    let imported: LocalAIServices =
+        serde_json::from_str(&content).map_err(|e| format!("解析 AI 服务列表失败: {}", e))?;
+
+    let key = secret_store::current_key()?;
+    let mut data = load_local_services()?;
+    for mut svc in imported.services {
+        if svc.id.trim().is_empty() {
+            continue;
+        }
+        let usable = !secret_store::is_encrypted(&svc.api_key) && !is_masked(&svc.api_key);
+        let existing = data.services.iter().position(|s| s.id == svc.id);
+        if !usable || svc.api_key.trim().is_empty() {
+            // 导入文件改了地址时不沿用已保存的 Key，避免 Key 被发往新的主机
+            svc.api_key = existing
+                .map(|i| &data.services[i])
+                .filter(|old| {
+                    same_endpoint(&svc.provider, &svc.base_url, &old.provider, &old.base_url)
+                })
+                .map(|old| old.api_key.clone())
+                .unwrap_or_default();
+        }
+        match existing {
+            Some(i) => data.services[i] = svc,
+            None => data.services.push(svc),
+        }
+    }
+    if data.active_service_id.is_empty() {
+        data.active_service_id = imported.active_service_id;
+    }
+    write_local_services(&data, &key)?;
+    Ok(data.services.len() as u32)
+}
+
+// ============================================================
+// 脱敏
+// ============================================================
+
+/// 脱敏 API Key：保留前 4 位与后 4 位，过短的 Key 完全隐藏；空 Key 保持为空
+pub fn mask_api_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.is_empty() {
+        return String::new();
+    }
+    if chars.len() <= 12 {
+        return MASK.to_string();
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}{}{}", head, MASK, tail)
+}
+
+/// 是否为 [`mask_api_key`] 生成的脱敏值
+pub fn is_masked(value: &str) -> bool {
+    value.contains(MASK)
+}
+
+pub fn mask_shared_services(mut data: SharedAIServices) -> SharedAIServices {
+    for svc in &mut data.services {
+        svc.api_key = mask_api_key(&svc.api_key);
+    }
+    data
+}
+
+/// 读取本地服务列表并脱敏 Key
+///
+/// 口令模式尚未解锁时不报错，已加密的 Key 显示为占位符，以便界面仍能列出服务。
+pub fn load_local_services_masked() -> Result<LocalAIServices, String> {
+    let mut data = match load_local_services() {
+        Ok(data) => data,
+        Err(e) if e.starts_with(secret_store::LOCKED_ERROR) => {
+            let mut data = read_local_services()?;
+            for svc in &mut data.services {
+                if secret_store::is_encrypted(&svc.api_key) {
+                    svc.api_key = MASK.to_string();
+                }
+            }
+            return Ok(data);
+        }
+        Err(e) => return Err(e),
+    };
+    for svc in &mut data.services {
+        svc.api_key = mask_api_key(&svc.api_key);
+    }
+    Ok(data)
+}
+
+/// 读取旧版单服务配置并脱敏 Key（未解锁时同样显示占位符）
+pub fn load_legacy_config_masked() -> Result<AIServiceConfig, String> {
+    match load_legacy_config() {
+        Ok(mut config) => {
+            config.api_key = mask_api_key(&config.api_key);
+            Ok(config)
+        }
+        Err(e) if e.starts_with(secret_store::LOCKED_ERROR) => {
+            let mut config = read_legacy_config()?.ok_or(e)?;
+            config.api_key = MASK.to_string();
+            Ok(config)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// ============================================================
+// 按服务 ID 解析凭据
+// ============================================================
+
+/// 调用方对服务默认参数的覆盖
+#[derive(Debug, Clone, Default)]
+pub struct ServiceOverrides {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+}
+
+/// 按服务 ID 解析出完整的请求配置（含 API Key）
+///
+/// 依次查找本地服务列表与共享服务列表（共享列表中已停用的服务不可用）；
+/// `service_id` 为空时使用本地默认服务，其次共享默认服务，最后回退到旧版单服务配置。
+/// 温度与 max_tokens 取服务自身设置（共享服务取列表级设置），再应用 `overrides`。
+pub fn resolve_service(
+    service_id: &str,
+    overrides: &ServiceOverrides,
+) -> Result<AIServiceConfig, String> {
+    resolve_service_with_id(service_id, overrides).map(|(_, config)| config)
+}
+
+/// 同 [`resolve_service`]，同时返回实际使用的服务 ID（`service_id` 为空时为默认服务的 ID，
+/// 回退到旧版单服务配置时为空）
+pub fn resolve_service_with_id(
+    service_id: &str,
+    overrides: &ServiceOverrides,
+) -> Result<(String, AIServiceConfig), String> {
+    let local = load_local_services()?;
+    let shared = load_shared_services()?;
+    let resolved_id = match service_id.trim() {
+        "" => default_service_id(&local, &shared),
+        id => id.to_string(),
+    };
+    let mut config = find_service(&local, &shared, &resolved_id)?;
+
+    if config.base_url.trim().is_empty() {
+        config.base_url = provider_base_url(&config.provider).unwrap_or_default().to_string();
+    }
+    if let Some(temperature) = overrides.temperature {
+        config.temperature = temperature;
+    }
+    if let Some(max_tokens) = overrides.max_tokens {
+        config.max_tokens = max_tokens;
+    }
+    let label = if service_id.trim().is_empty() { "默认" } else { service_id.trim() };
+    if config.api_key.trim().is_empty() && !config.provider.eq_ignore_ascii_case("ollama") {
+        return Err(format!("AI 服务「{}」未配置 API Key", label));
+    }
+    if config.model.trim().is_empty() {
+        return Err(format!("AI 服务「{}」未配置模型", label));
+    }
+    Ok((resolved_id, config))
+}
+
+/// 解析设置界面中尚未保存的服务配置，用于连接测试与查询模型列表
+///
+/// `draft` 为空时直接使用已保存的服务；草稿中的脱敏 Key 只有在提供商与 base URL 都与
+/// 已保存服务相同、且脱敏值一致时才还原为真实 Key，避免把某个服务的 Key 发往用户新填写的地址。
+pub fn resolve_draft(
+    service_id: &str,
+    draft: Option<AIServiceConfig>,
+) -> Result<AIServiceConfig, String> {
+    let service_id = service_id.trim();
+    let mut config = match draft {
+        None => {
+            let local = load_local_services()?;
+            let shared = load_shared_services()?;
+            find_service(&local, &shared, service_id)?
+        }
+        Some(mut draft) => {
+            if is_masked(&draft.api_key) {
+                if service_id.is_empty() {
+                    return Err("API Key 无法还原，请重新填写".to_string());
+                }
+                let local = load_local_services()?;
+                let shared = load_shared_services()?;
+                let saved = find_service(&local, &shared, service_id)?;
+                let same = same_endpoint(
+                    &draft.provider,
+                    &draft.base_url,
+                    &saved.provider,
+                    &saved.base_url,
+                );
+                if !same {
+                    return Err(format!(
+                        "服务「{}」的地址或提供商已修改，请重新填写 API Key",
+                        service_id
+                    ));
+                }
+                if mask_api_key(&saved.api_key) != draft.api_key {
+                    return Err(format!("服务「{}」的 API Key 无法还原，请重新填写", service_id));
+                }
+                draft.api_key = saved.api_key;
+            }
+            draft
+        }
+    };
+    if config.base_url.trim().is_empty() {
+        config.base_url = provider_base_url(&config.provider).unwrap_or_default().to_string();
+    }
+    Ok(config)
+}
+
+fn find_service(
+    local: &LocalAIServices,
+    shared: &SharedAIServices,
+    service_id: &str,
+) -> Result<AIServiceConfig, String> {
+    let local_config = |id: &str| {
+        local.services.iter().find(|s| s.id == id).map(|s| AIServiceConfig {
+            provider: s.provider.clone(),
+            base_url: s.base_url.clone(),
+            api_key: s.api_key.clone(),
+            model: s.model.clone(),
+            max_tokens: s.max_tokens,
+            temperature: s.temperature,
+        })
+    };
+    let shared_config = |id: &str| {
+        shared
+            .services
+            .iter()
+            .find(|s| s.id == id && s.enabled)
+            .map(|s| AIServiceConfig {
+                provider: s.provider.clone(),
+                base_url: s.base_url.clone(),
+                api_key: s.api_key.clone(),
+                model: s.model.clone(),
+                max_tokens: shared.max_tokens,
+                temperature: shared.temperature,
+            })
+    };
+
+    if !service_id.is_empty() {
+        return local_config(service_id)
+            .or_else(|| shared_config(service_id))
+            .ok_or_else(|| format!("AI 服务不存在或已停用: {}", service_id));
+    }
+
+    match default_service_id(local, shared).as_str() {
+        "" => load_legacy_config(),
+        id => find_service(local, shared, id),
+    }
+}
+
+/// 未指定服务时使用的服务：本地默认 → 本地第一个 → 共享默认（已启用）；
+/// 都没有时返回空字符串，表示使用旧版单服务配置
+fn default_service_id(local: &LocalAIServices, shared: &SharedAIServices) -> String {
+    if local.services.iter().any(|s| s.id == local.active_service_id) {
+        return local.active_service_id.clone();
+    }
+    if let Some(first) = local.services.first() {
+        return first.id.clone();
+    }
+    if shared
+        .services
+        .iter()
+        .any(|s| s.id == shared.active_service_id && s.enabled)
+    {
+        return shared.active_service_id.clone();
+    }
+    String::new()
+}
+
+/// 提供商的默认 base URL
+pub fn provider_base_url(provider: &str) -> Option<&'static str> {
+    let provider = provider.trim().to_ascii_lowercase();
+    PROVIDER_BASE_URLS
+        .iter()
+        .find(|(name, _)| *name == provider)
+        .map(|(_, url)| *url)
+}
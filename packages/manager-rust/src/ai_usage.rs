@@ -0,0 +1,596 @@
+//! AI 调用用量记录与费用估算
+//!
+//! 每次生成（成功、失败或取消）追加一行 JSON 到 `~/.aidocplus/manager-ai-usage.jsonl`，
+//! 记录提供商、模型、token 用量、耗时与结果。[`summarize`] 按日期 / 模型 / 资源类型等维度汇总，
+//! 并按价格表（`~/.aidocplus/manager-ai-prices.json`）估算费用。
+
+use crate::ai_provider::{ProviderKind, TokenUsage};
+use crate::ai_services::aidocplus_dir;
+use crate::fs_utils::write_atomic;
+use crate::types::AIServiceConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// 串行化日志追加，避免并发生成时两行交错
+static LOG_LOCK: Mutex<()> = Mutex::new(());
+
+/// 错误信息在日志中保留的最大字符数
+const MAX_ERROR_CHARS: usize = 300;
+
+// ============================================================
+// 类型定义
+// ============================================================
+
+/// 调用来源，由命令层传入
+#[derive(Debug, Clone, Default)]
+pub struct UsageContext {
+    /// 按服务 ID 生成时的服务 ID，直接传配置时为空
+    pub service_id: String,
+    /// 生成的资源类型（如 prompt-templates），未知时为空
+    pub resource_type: String,
+    /// 预算已用完时仍然发送（用户已确认）
+    pub allow_over_budget: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageOutcome {
+    Success,
+    Cancelled,
+    Error,
+}
+
+/// 用量日志中的一行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRecord {
+    /// 本地时间，RFC 3339
+    pub timestamp: String,
+    #[serde(default)]
+    pub provider: String,
+    #[serde(default)]
+    pub model: String,
+    #[serde(default, rename = "serviceId", skip_serializing_if = "String::is_empty")]
+    pub service_id: String,
+    #[serde(default, rename = "resourceType", skip_serializing_if = "String::is_empty")]
+    pub resource_type: String,
+    #[serde(default)]
+    pub stream: bool,
+    #[serde(default, rename = "inputTokens")]
+    pub input_tokens: Option<u32>,
+    #[serde(default, rename = "outputTokens")]
+    pub output_tokens: Option<u32>,
+    #[serde(default, rename = "latencyMs")]
+    pub latency_ms: u64,
+    pub outcome: UsageOutcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 单个模型的价格（每百万 token）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    /// 模型名；以 `*` 结尾时按前缀匹配（如 `claude-sonnet-4*`）
+    pub model: String,
+    #[serde(rename = "inputPerMillion")]
+    pub input_per_million: f64,
+    #[serde(rename = "outputPerMillion")]
+    pub output_per_million: f64,
+}
+
+/// 价格表
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceTable {
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    #[serde(default)]
+    pub prices: Vec<ModelPrice>,
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+/// 汇总维度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UsageGroupBy {
+    Day,
+    Month,
+    Model,
+    Provider,
+    Service,
+    ResourceType,
+}
+
+/// 汇总查询条件，日期均为本地日期 `YYYY-MM-DD`（含首尾）
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UsageQuery {
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+    #[serde(default, rename = "groupBy")]
+    pub group_by: Vec<UsageGroupBy>,
+}
+
+/// 一组汇总结果；未参与分组的维度为 None
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageGroup {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub day: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub month: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(rename = "serviceId", skip_serializing_if = "Option::is_none")]
+    pub service_id: Option<String>,
+    #[serde(rename = "resourceType", skip_serializing_if = "Option::is_none")]
+    pub resource_type: Option<String>,
+    pub requests: u32,
+    pub succeeded: u32,
+    pub cancelled: u32,
+    pub failed: u32,
+    #[serde(rename = "inputTokens")]
+    pub input_tokens: u64,
+    #[serde(rename = "outputTokens")]
+    pub output_tokens: u64,
+    /// 没有返回用量的请求数（不计入 token 与费用）
+    #[serde(rename = "missingUsage")]
+    pub missing_usage: u32,
+    #[serde(rename = "avgLatencyMs")]
+    pub avg_latency_ms: u64,
+    /// 估算费用；组内有模型不在价格表中时只统计有价格的部分
+    pub cost: f64,
+    #[serde(skip)]
+    latency_total: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageSummary {
+    pub currency: String,
+    pub groups: Vec<UsageGroup>,
+    pub total: UsageGroup,
+    /// 有用量但价格表中没有的模型
+    #[serde(rename = "unpricedModels")]
+    pub unpriced_models: Vec<String>,
+}
+
+// ============================================================
+// 文件路径
+// ============================================================
+
+/// 用量日志（~/.aidocplus/manager-ai-usage.jsonl）
+pub fn usage_log_path() -> Result<PathBuf, String> {
+    Ok(aidocplus_dir()?.join("manager-ai-usage.jsonl"))
+}
+
+/// 价格表（~/.aidocplus/manager-ai-prices.json）
+pub fn price_table_path() -> Result<PathBuf, String> {
+    Ok(aidocplus_dir()?.join("manager-ai-prices.json"))
+}
+
+// ============================================================
+// 记录
+// ============================================================
+
+impl UsageRecord {
+    /// 以当前时间创建一条记录
+    pub fn new(
+        config: &AIServiceConfig,
+        context: &UsageContext,
+        stream: bool,
+        usage: Option<&TokenUsage>,
+        latency_ms: u64,
+        outcome: UsageOutcome,
+        error: Option<&str>,
+    ) -> Self {
+        let provider = if config.provider.trim().is_empty() {
+            format!("{:?}", ProviderKind::from_config(config)).to_lowercase()
+        } else {
+            config.provider.trim().to_lowercase()
+        };
+        let error = error.map(|e| {
+            if e.chars().count() > MAX_ERROR_CHARS {
+                format!("{}…", e.chars().take(MAX_ERROR_CHARS).collect::<String>())
+            } else {
+                e.to_string()
+            }
+        });
+        Self {
+            timestamp: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            provider,
+            model: config.model.trim().to_string(),
+            service_id: context.service_id.clone(),
+            resource_type: context.resource_type.clone(),
+            stream,
+            input_tokens: usage.and_then(|u| u.input_tokens),
+            output_tokens: usage.and_then(|u| u.output_tokens),
+            latency_ms,
+            outcome,
+            error,
+        }
+    }
+
+    /// 本地日期 `YYYY-MM-DD`
+    fn day(&self) -> &str {
+        self.timestamp.get(..10).unwrap_or_default()
+    }
+}
+
+/// 追加一条用量记录；写入失败只打印日志，不影响生成结果
+pub fn record(entry: &UsageRecord) {
+    if let Err(e) = append(entry) {
+        eprintln!("[AI Usage] 写入用量记录失败: {}", e);
+    }
+}
+
+fn append(entry: &UsageRecord) -> Result<(), String> {
+    let path = usage_log_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    let _guard = LOG_LOCK.lock().map_err(|_| "用量日志状态异常".to_string())?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+    writeln!(file, "{}", line).map_err(|e| e.to_string())
+}
+
+/// 读取全部用量记录，无法解析的行（例如写到一半的最后一行）跳过
+pub fn load_records() -> Result<Vec<UsageRecord>, String> {
+    let path = usage_log_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = std::fs::File::open(&path).map_err(|e| format!("读取用量记录失败: {}", e))?;
+    Ok(std::io::BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+// ============================================================
+// 价格表
+// ============================================================
+
+impl Default for PriceTable {
+    /// 常用模型的参考价格（美元 / 百万 token），以提供商官网为准，可在设置中修改
+    fn default() -> Self {
+        let price = |model: &str, input: f64, output: f64| ModelPrice {
+            model: model.to_string(),
+            input_per_million: input,
+            output_per_million: output,
+        };
+        Self {
+            currency: default_currency(),
+            prices: vec![
+                price("gpt-4o-mini*", 0.15, 0.6),
+                price("gpt-4o*", 2.5, 10.0),
+                price("gpt-4.1-mini*", 0.4, 1.6),
+                price("gpt-4.1*", 2.0, 8.0),
+                price("claude-3-5-haiku*", 0.8, 4.0),
+                price("claude-sonnet-4*", 3.0, 15.0),
+                price("claude-3-7-sonnet*", 3.0, 15.0),
+                price("claude-3-5-sonnet*", 3.0, 15.0),
+                price("gemini-2.0-flash*", 0.1, 0.4),
+                price("deepseek-chat", 0.27, 1.1),
+                price("deepseek-reasoner", 0.55, 2.19),
+            ],
+        }
+    }
+}
+
+impl PriceTable {
+    /// 查找模型价格：精确匹配优先，其次最长的前缀匹配；模型名不区分大小写
+    pub fn find(&self, model: &str) -> Option<&ModelPrice> {
+        let model = model.trim().to_lowercase();
+        let model = model.rsplit('/').next().unwrap_or_default();
+        if let Some(exact) = self.prices.iter().find(|p| p.model.to_lowercase() == model) {
+            return Some(exact);
+        }
+        self.prices
+            .iter()
+            .filter_map(|p| {
+                let prefix = p.model.strip_suffix('*')?.to_lowercase();
+                model.starts_with(&prefix).then_some((prefix.len(), p))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, p)| p)
+    }
+
+    /// 估算一次调用的费用，模型不在价格表中时返回 None
+    pub fn cost(&self, model: &str, input_tokens: u64, output_tokens: u64) -> Option<f64> {
+        let price = self.find(model)?;
+        Some(
+            (input_tokens as f64 * price.input_per_million
+                + output_tokens as f64 * price.output_per_million)
+                / 1_000_000.0,
+        )
+    }
+}
+
+/// 读取价格表，文件不存在时使用内置参考价格
+pub fn load_price_table() -> Result<PriceTable, String> {
+    let path = price_table_path()?;
+    if !path.exists() {
+        return Ok(PriceTable::default());
+    }
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("读取价格表失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析价格表失败: {}", e))
+}
+
+pub fn save_price_table(table: &PriceTable) -> Result<(), String> {
+    for price in &table.prices {
+        if price.model.trim().is_empty() {
+            return Err("价格表中存在未填写模型名的条目".to_string());
+        }
+        if price.input_per_million < 0.0 || price.output_per_million < 0.0 {
+            return Err(format!("模型「{}」的价格不能为负数", price.model));
+        }
+    }
+    let path = price_table_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    let content =
+        serde_json::to_string_pretty(table).map_err(|e| format!("序列化价格表失败: {}", e))?;
+    write_atomic(&path, &content).map_err(|e| format!("写入价格表失败: {}", e))
+}
+
+// ============================================================
+// 汇总
+// ============================================================
+
+impl UsageGroup {
+    fn add(&mut self, record: &UsageRecord, cost: Option<f64>) {
+        self.requests += 1;
+        match record.outcome {
+            UsageOutcome::Success => self.succeeded += 1,
+            UsageOutcome::Cancelled => self.cancelled += 1,
+            UsageOutcome::Error => self.failed += 1,
+        }
+        if record.input_tokens.is_none() && record.output_tokens.is_none() {
+            self.missing_usage += 1;
+        }
+        self.input_tokens += record.input_tokens.unwrap_or(0) as u64;
+        self.output_tokens += record.output_tokens.unwrap_or(0) as u64;
+        self.latency_total += record.latency_ms;
+        self.avg_latency_ms = self.latency_total / self.requests as u64;
+        self.cost += cost.unwrap_or(0.0);
+    }
+}
+
+/// 按查询条件汇总用量记录
+pub fn summarize(query: &UsageQuery) -> Result<UsageSummary, String> {
+    let records = load_records()?;
+    let prices = load_price_table()?;
+    Ok(summarize_records(&records, &prices, query))
+}
+
+/// 汇总给定的记录（结果按分组键排序）
+pub fn summarize_records(
+    records: &[UsageRecord],
+    prices: &PriceTable,
+    query: &UsageQuery,
+) -> UsageSummary {
+    let from = query.from.as_deref().filter(|d| !d.is_empty());
+    let to = query.to.as_deref().filter(|d| !d.is_empty());
+
+    let mut groups: BTreeMap<Vec<String>, UsageGroup> = BTreeMap::new();
+    let mut total = UsageGroup::default();
+    let mut unpriced = std::collections::BTreeSet::new();
+
+    for record in records {
+        let day = record.day();
+        if from.is_some_and(|f| day < f) || to.is_some_and(|t| day > t) {
+            continue;
+        }
+        let input = record.input_tokens.unwrap_or(0) as u64;
+        let output = record.output_tokens.unwrap_or(0) as u64;
+        let cost = prices.cost(&record.model, input, output);
+        if cost.is_none() && input + output > 0 {
+            unpriced.insert(record.model.clone());
+        }
+
+        let mut group = UsageGroup::default();
+        let mut key = Vec::with_capacity(query.group_by.len());
+        for dimension in &query.group_by {
+            let value = match dimension {
+                UsageGroupBy::Day => &mut group.day,
+                UsageGroupBy::Month => &mut group.month,
+                UsageGroupBy::Model => &mut group.model,
+                UsageGroupBy::Provider => &mut group.provider,
+                UsageGroupBy::Service => &mut group.service_id,
+                UsageGroupBy::ResourceType => &mut group.resource_type,
+            };
+            let text = match dimension {
+                UsageGroupBy::Day => day.to_string(),
+                UsageGroupBy::Month => day.get(..7).unwrap_or_default().to_string(),
+                UsageGroupBy::Model => record.model.clone(),
+                UsageGroupBy::Provider => record.provider.clone(),
+                UsageGroupBy::Service => record.service_id.clone(),
+                UsageGroupBy::ResourceType => record.resource_type.clone(),
+            };
+            key.push(text.clone());
+            *value = Some(text);
+        }
+
+        groups.entry(key).or_insert(group).add(record, cost);
+        total.add(record, cost);
+    }
+
+    UsageSummary {
+        currency: prices.currency.clone(),
+        groups: groups.into_values().collect(),
+        total,
+        unpriced_models: unpriced.into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        timestamp: &str,
+        model: &str,
+        resource_type: &str,
+        tokens: Option<(u32, u32)>,
+        outcome: UsageOutcome,
+    ) -> UsageRecord {
+        UsageRecord {
+            timestamp: timestamp.to_string(),
+            provider: "openai".to_string(),
+            model: model.to_string(),
+            service_id: String::new(),
+            resource_type: resource_type.to_string(),
+            stream: false,
+            input_tokens: tokens.map(|t| t.0),
+            output_tokens: tokens.map(|t| t.1),
+            latency_ms: 100,
+            outcome,
+            error: None,
+        }
+    }
+
+    fn prices() -> PriceTable {
+        let price = |model: &str, input: f64, output: f64| ModelPrice {
+            model: model.to_string(),
+            input_per_million: input,
+            output_per_million: output,
+        };
+        PriceTable {
+            currency: "USD".to_string(),
+            prices: vec![
+                price("gpt-4o*", 2.0, 8.0),
+                price("gpt-4o-mini*", 0.1, 0.4),
+                price("gpt-4o-mini-tts", 1.0, 1.0),
+                price("deepseek-chat", 1.0, 2.0),
+            ],
+        }
+    }
+
+    fn records() -> Vec<UsageRecord> {
+        use UsageOutcome::*;
+        vec![
+            record("2026-03-01T10:00:00+08:00", "gpt-4o", "prompt-templates",
+                Some((1000, 500)), Success),
+            record("2026-03-01T11:00:00+08:00", "gpt-4o", "doc-templates",
+                Some((2000, 0)), Cancelled),
+            record("2026-03-02T09:00:00+08:00", "deepseek-chat", "prompt-templates",
+                None, Error),
+            record("2026-04-01T09:00:00+08:00", "local-llama", "prompt-templates",
+                Some((10, 10)), Success),
+        ]
+    }
+
+    fn query(from: Option<&str>, to: Option<&str>, group_by: Vec<UsageGroupBy>) -> UsageQuery {
+        UsageQuery {
+            from: from.map(str::to_string),
+            to: to.map(str::to_string),
+            group_by,
+        }
+    }
+
+    #[test]
+    fn price_find_prefers_exact_then_longest_prefix() {
+        let table = prices();
+        let model = |name: &str| table.find(name).map(|p| p.model.as_str());
+        assert_eq!(model("gpt-4o-mini-tts"), Some("gpt-4o-mini-tts"));
+        assert_eq!(model("gpt-4o-mini-2024-07-18"), Some("gpt-4o-mini*"));
+        assert_eq!(model("gpt-4o-2024-08-06"), Some("gpt-4o*"));
+        assert_eq!(model("GPT-4O"), Some("gpt-4o*"));
+        assert_eq!(model("deepseek-chat"), Some("deepseek-chat"));
+        // 没有 * 的条目只精确匹配
+        assert_eq!(model("deepseek-chat-v2"), None);
+        assert_eq!(model("gpt-4"), None);
+    }
+
+    #[test]
+    fn price_find_strips_provider_prefix() {
+        let table = prices();
+        assert_eq!(table.find("openai/gpt-4o-mini").unwrap().model, "gpt-4o-mini*");
+        let nested = table.find(" openrouter/deepseek/deepseek-chat ").unwrap();
+        assert_eq!(nested.model, "deepseek-chat");
+        let cost = table.cost("openai/gpt-4o", 1_000_000, 500_000).unwrap();
+        assert!((cost - 6.0).abs() < 1e-9);
+        assert_eq!(table.cost("unknown", 1, 1), None);
+    }
+
+    #[test]
+    fn summarize_groups_by_day() {
+        let summary = summarize_records(&records(), &prices(), &query(None, None, vec![
+            UsageGroupBy::Day,
+        ]));
+        let days: Vec<_> = summary.groups.iter().map(|g| g.day.clone().unwrap()).collect();
+        assert_eq!(days, ["2026-03-01", "2026-03-02", "2026-04-01"]);
+        let first = &summary.groups[0];
+        assert_eq!((first.requests, first.succeeded, first.cancelled), (2, 1, 1));
+        assert_eq!((first.input_tokens, first.output_tokens), (3000, 500));
+        assert!((first.cost - 0.01).abs() < 1e-9);
+        assert_eq!(first.model, None);
+        assert_eq!(summary.total.requests, 4);
+        assert_eq!(summary.currency, "USD");
+    }
+
+    #[test]
+    fn summarize_groups_by_model_and_resource_type() {
+        let summary = summarize_records(&records(), &prices(), &query(None, None, vec![
+            UsageGroupBy::Model,
+            UsageGroupBy::ResourceType,
+        ]));
+        let keys: Vec<_> = summary
+            .groups
+            .iter()
+            .map(|g| (g.model.clone().unwrap(), g.resource_type.clone().unwrap()))
+            .collect();
+        assert_eq!(keys, [
+            ("deepseek-chat".to_string(), "prompt-templates".to_string()),
+            ("gpt-4o".to_string(), "doc-templates".to_string()),
+            ("gpt-4o".to_string(), "prompt-templates".to_string()),
+            ("local-llama".to_string(), "prompt-templates".to_string()),
+        ]);
+        assert_eq!(summary.unpriced_models, ["local-llama"]);
+    }
+
+    #[test]
+    fn summarize_applies_inclusive_date_bounds() {
+        let march = query(Some("2026-03-02"), Some("2026-03-31"), vec![]);
+        let summary = summarize_records(&records(), &prices(), &march);
+        assert_eq!(summary.total.requests, 1);
+        assert_eq!(summary.groups.len(), 1);
+        let first_day = query(Some(""), Some("2026-03-01"), vec![]);
+        let summary = summarize_records(&records(), &prices(), &first_day);
+        assert_eq!(summary.total.requests, 2);
+        // 价格缺失只统计有用量的记录
+        assert!(summary.unpriced_models.is_empty());
+    }
+
+    #[test]
+    fn summarize_counts_missing_usage() {
+        let summary = summarize_records(&records(), &prices(), &query(None, None, vec![
+            UsageGroupBy::Model,
+        ]));
+        let deepseek = summary
+            .groups
+            .iter()
+            .find(|g| g.model.as_deref() == Some("deepseek-chat"))
+            .unwrap();
+        assert_eq!((deepseek.missing_usage, deepseek.failed), (1, 1));
+        assert_eq!(deepseek.input_tokens + deepseek.output_tokens, 0);
+        assert_eq!(deepseek.cost, 0.0);
+        assert_eq!(summary.total.missing_usage, 1);
+        assert_eq!(summary.total.avg_latency_ms, 100);
+    }
+}
@@ -1,6 +1,6 @@
-use crate::types::MetaConfig;
+use crate::types::{MetaConfig, SubCategoryDefinition};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// 读取 _meta.json 分类配置
 pub fn read_meta(data_dir: &str) -> Result<MetaConfig, String> {
@@ -26,3 +26,387 @@ pub fn save_meta(data_dir: &str, meta: MetaConfig) -> Result<(), String> {
         .map_err(|e| format!("序列化 _meta.json 失败: {}", e))?;
     fs::write(&meta_path, content).map_err(|e| format!("写入 _meta.json 失败: {}", e))
 }
+
+// ============================================================
+// 分类级联操作（重命名 / 删除 / 合并 / 排序）
+// ============================================================
+
+/// 校验分类 key：不能为空、不能包含路径分隔符，且不能以 `_` / `.` 开头（会被扫描跳过）
+pub fn validate_category_key(key: &str) -> Result<(), String> {
+    if key.trim().is_empty() {
+        return Err("分类 key 不能为空".to_string());
+    }
+    if key.contains('/') || key.contains('\\') || key.contains("..") {
+        return Err(format!("分类 key 不能包含路径字符: {}", key));
+    }
+    if key.starts_with('_') || key.starts_with('.') {
+        return Err(format!("分类 key 不能以 _ 或 . 开头: {}", key));
+    }
+    Ok(())
+}
+
+/// 重命名分类：更新 _meta.json、重命名分类目录，并改写目录下所有 manifest 的 majorCategory
+///
+/// 返回受影响的资源数量。任何一步失败都会回滚已完成的修改。
+pub fn rename_category(data_dir: &str, old_key: &str, new_key: &str) -> Result<u32, String> {
+    validate_category_key(old_key)?;
+    validate_category_key(new_key)?;
+    if old_key == new_key {
+        return Ok(0);
+    }
+
+    let data_path = Path::new(data_dir);
+    let mut meta = read_meta(data_dir)?;
+    if meta.categories.iter().any(|c| c.key == new_key) {
+        return Err(format!("分类 {} 已存在，请使用合并分类", new_key));
+    }
+
+    let old_dir = data_path.join(old_key);
+    let new_dir = data_path.join(new_key);
+    let in_meta = meta.categories.iter().any(|c| c.key == old_key);
+    if !in_meta && !old_dir.exists() {
+        return Err(format!("分类 {} 不存在", old_key));
+    }
+    if new_dir.exists() {
+        return Err(format!("目录已存在: {}", new_dir.display()));
+    }
+
+    // 先整体重命名目录，再改写 manifest
+    let mut count = 0u32;
+    let mut originals = Vec::new();
+    if old_dir.exists() {
+        fs::rename(&old_dir, &new_dir).map_err(|e| format!("重命名分类目录失败: {}", e))?;
+        let resource_dirs = list_resource_dirs(&new_dir);
+        originals = match rewrite_major_category(&resource_dirs, new_key) {
+            Ok(o) => o,
+            Err(e) => {
+                let _ = fs::rename(&new_dir, &old_dir);
+                return Err(e);
+            }
+        };
+        count = resource_dirs.len() as u32;
+    }
+
+    if let Some(cat) = meta.categories.iter_mut().find(|c| c.key == old_key) {
+        cat.key = new_key.to_string();
+    }
+    if let Err(e) = write_meta_atomic(data_path, &meta) {
+        restore_manifests(&originals);
+        if new_dir.exists() {
+            let _ = fs::rename(&new_dir, &old_dir);
+        }
+        return Err(e);
+    }
+
+    Ok(count)
+}
+
+/// 删除分类
+///
+/// - `reassign_to` 为 Some 时，分类下的资源整体移动到目标分类
+/// - 否则将整个分类目录移入 `_trash/`（可手动恢复）
+///
+/// 返回受影响的资源数量。
+pub fn delete_category(
+    data_dir: &str,
+    key: &str,
+    reassign_to: Option<&str>,
+) -> Result<u32, String> {
+    validate_category_key(key)?;
+    let data_path = Path::new(data_dir);
+    let mut meta = read_meta(data_dir)?;
+    let category_dir = data_path.join(key);
+
+    let count = match reassign_to {
+        Some(target) => {
+            if target == key {
+                return Err("目标分类不能与被删除的分类相同".to_string());
+            }
+            validate_category_key(target)?;
+            if !meta.categories.is_empty() && !meta.categories.iter().any(|c| c.key == target) {
+                return Err(format!("目标分类 {} 不存在", target));
+            }
+            relocate_category_resources(data_path, key, target)?.count()
+        }
+        None if category_dir.exists() => {
+            let count = list_resource_dirs(&category_dir).len() as u32;
+            crate::fs_utils::move_to_trash(data_path, &category_dir)?;
+            count
+        }
+        None => 0,
+    };
+
+    // 资源已移走的空目录直接删除，仍有残留文件时移入回收站
+    if category_dir.exists() && fs::remove_dir(&category_dir).is_err() {
+        crate::fs_utils::move_to_trash(data_path, &category_dir)?;
+    }
+
+    meta.categories.retain(|c| c.key != key);
+    write_meta_atomic(data_path, &meta)?;
+    Ok(count)
+}
+
+/// 合并分类：将 source_keys 下的全部资源移动到 target_key，并从 _meta.json 中移除源分类
+///
+/// 源分类的子分类会并入目标分类（按 key 去重）。返回移动的资源数量。
+pub fn merge_categories(
+    data_dir: &str,
+    source_keys: &[String],
+    target_key: &str,
+) -> Result<u32, String> {
+    validate_category_key(target_key)?;
+    for source in source_keys {
+        validate_category_key(source)?;
+    }
+    let data_path = Path::new(data_dir);
+    let mut meta = read_meta(data_dir)?;
+
+    // 预检查：所有源分类合并后不能出现同名资源目录
+    let target_dir = data_path.join(target_key);
+    let mut seen: std::collections::HashSet<String> = list_resource_dirs(&target_dir)
+        .iter()
+        .map(|d| dir_name(d))
+        .collect();
+    for source in source_keys.iter().filter(|s| s.as_str() != target_key) {
+        for dir in list_resource_dirs(&data_path.join(source)) {
+            let name = dir_name(&dir);
+            if !seen.insert(name.clone()) {
+                return Err(format!("合并失败：资源 {} 在多个分类中重复", name));
+            }
+        }
+    }
+
+    if !meta.categories.is_empty() && !meta.categories.iter().any(|c| c.key == target_key) {
+        return Err(format!("目标分类 {} 不存在", target_key));
+    }
+
+    // 任一源分类迁移失败时撤销之前已完成的迁移
+    let mut relocations: Vec<Relocation> = Vec::new();
+    for source in source_keys.iter().filter(|s| s.as_str() != target_key) {
+        match relocate_category_resources(data_path, source, target_key) {
+            Ok(relocation) => relocations.push(relocation),
+            Err(e) => {
+                undo_relocations(&relocations);
+                return Err(e);
+            }
+        }
+    }
+
+    // 合并子分类定义
+    let merged_subs: Vec<SubCategoryDefinition> = meta
+        .categories
+        .iter()
+        .filter(|c| c.key != target_key && source_keys.contains(&c.key))
+        .flat_map(|c| c.sub_categories.clone())
+        .collect();
+    if let Some(target) = meta.categories.iter_mut().find(|c| c.key == target_key) {
+        for sub in merged_subs {
+            if !target.sub_categories.iter().any(|s| s.key == sub.key) {
+                target.sub_categories.push(sub);
+            }
+        }
+    }
+    meta.categories
+        .retain(|c| c.key == target_key || !source_keys.contains(&c.key));
+
+    if let Err(e) = write_meta_atomic(data_path, &meta) {
+        undo_relocations(&relocations);
+        return Err(e);
+    }
+
+    // _meta.json 写入后才清理源分类目录（残留文件移入回收站，无法撤销）
+    for source in source_keys.iter().filter(|s| s.as_str() != target_key) {
+        let source_dir = data_path.join(source);
+        if source_dir.exists() && fs::remove_dir(&source_dir).is_err() {
+            crate::fs_utils::move_to_trash(data_path, &source_dir)?;
+        }
+    }
+    Ok(relocations.iter().map(Relocation::count).sum())
+}
+
+/// 按给定 key 顺序重排分类（order 依次赋值 0, 1, 2...，未列出的分类排在最后）
+pub fn reorder_categories(data_dir: &str, keys: &[String]) -> Result<(), String> {
+    let mut meta = read_meta(data_dir)?;
+    let listed = keys.len() as i32;
+    let mut next = listed;
+    meta.categories.sort_by_key(|c| c.order);
+    for cat in meta.categories.iter_mut() {
+        match keys.iter().position(|k| *k == cat.key) {
+            Some(i) => cat.order = i as i32,
+            None => {
+                cat.order = next;
+                next += 1;
+            }
+        }
+    }
+    meta.categories.sort_by_key(|c| c.order);
+    write_meta_atomic(Path::new(data_dir), &meta)
+}
+
+/// 校验子分类是否属于所给大类
+///
+/// 子分类为空时总是合法；_meta.json 中未定义该大类时不做限制（兼容没有 _meta.json 的仓库）。
+pub fn validate_sub_category(meta: &MetaConfig, major: &str, sub: &str) -> Result<(), String> {
+    if sub.is_empty() {
+        return Ok(());
+    }
+    match meta.categories.iter().find(|c| c.key == major) {
+        Some(cat) if !cat.sub_categories.iter().any(|s| s.key == sub) => Err(format!(
+            "子分类 {} 不属于分类 {}",
+            sub, major
+        )),
+        _ => Ok(()),
+    }
+}
+
+// ============================================================
+// 内部工具
+// ============================================================
+
+/// 以原子方式写入 _meta.json
+pub(crate) fn write_meta_atomic(data_path: &Path, meta: &MetaConfig) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(meta)
+        .map_err(|e| format!("序列化 _meta.json 失败: {}", e))?;
+    crate::fs_utils::write_atomic(&data_path.join("_meta.json"), &content)
+}
+
+/// 列出分类目录下所有资源目录（含 `{major}/{sub}/{id}` 嵌套结构）
+fn list_resource_dirs(category_dir: &Path) -> Vec<PathBuf> {
+    crate::resource_ops::collect_resource_dirs(category_dir)
+}
+
+fn dir_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// 批量改写 manifest 的 majorCategory
+///
+/// 先读取并解析全部 manifest，全部成功后才开始写入；写入中途失败会还原已写入的文件。
+/// 成功时返回 (manifest 路径, 原始内容) 列表，供调用方在后续步骤失败时回滚。
+fn rewrite_major_category(
+    resource_dirs: &[PathBuf],
+    new_key: &str,
+) -> Result<Vec<(PathBuf, String)>, String> {
+    let mut originals = Vec::new();
+    let mut updated = Vec::new();
+    let now = chrono::Utc::now().to_rfc3339();
+    for dir in resource_dirs {
+        let manifest_path = dir.join("manifest.json");
+        let content = fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("读取 manifest 失败 {}: {}", manifest_path.display(), e))?;
+        let mut manifest: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| format!("解析 manifest 失败 {}: {}", manifest_path.display(), e))?;
+        if let Some(obj) = manifest.as_object_mut() {
+            obj.insert("majorCategory".to_string(), serde_json::json!(new_key));
+            obj.insert("updatedAt".to_string(), serde_json::json!(now));
+        }
+        let new_content = serde_json::to_string_pretty(&manifest)
+            .map_err(|e| format!("序列化 manifest 失败: {}", e))?;
+        originals.push((manifest_path.clone(), content));
+        updated.push((manifest_path, new_content));
+    }
+
+    for (i, (path, content)) in updated.iter().enumerate() {
+        if let Err(e) = crate::fs_utils::write_atomic(path, content) {
+            restore_manifests(&originals[..i]);
+            return Err(e);
+        }
+    }
+    Ok(originals)
+}
+
+/// 回滚 manifest 到原始内容（尽力而为）
+fn restore_manifests(originals: &[(PathBuf, String)]) {
+    for (path, content) in originals {
+        let _ = crate::fs_utils::write_atomic(path, content);
+    }
+}
+
+/// 已完成的分类资源迁移，后续步骤失败时可整体撤销
+struct Relocation {
+    /// (原目录, 新目录)
+    moved: Vec<(PathBuf, PathBuf)>,
+    /// 迁移前的 manifest（路径为原目录下的路径）
+    originals: Vec<(PathBuf, String)>,
+}
+
+impl Relocation {
+    fn count(&self) -> u32 {
+        self.moved.len() as u32
+    }
+
+    /// 把目录移回原位并还原 manifest（尽力而为）
+    fn undo(&self) {
+        for (from, to) in self.moved.iter().rev() {
+            if let Some(parent) = from.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let _ = fs::rename(to, from);
+        }
+        restore_manifests(&self.originals);
+    }
+}
+
+/// 按完成顺序的逆序撤销多次迁移
+fn undo_relocations(relocations: &[Relocation]) {
+    for relocation in relocations.iter().rev() {
+        relocation.undo();
+    }
+}
+
+/// 将 from_key 分类下的所有资源目录移动到 to_key 分类，并改写 majorCategory
+///
+/// 目标分类中已存在同名资源目录时整体失败，不做任何修改。
+fn relocate_category_resources(
+    data_path: &Path,
+    from_key: &str,
+    to_key: &str,
+) -> Result<Relocation, String> {
+    let from_dir = data_path.join(from_key);
+    let to_dir = data_path.join(to_key);
+    let resource_dirs = list_resource_dirs(&from_dir);
+    if resource_dirs.is_empty() {
+        return Ok(Relocation { moved: Vec::new(), originals: Vec::new() });
+    }
+
+    // 保留子分类目录层级：{from}/{sub}/{id} → {to}/{sub}/{id}
+    let targets: Vec<PathBuf> = resource_dirs
+        .iter()
+        .map(|dir| to_dir.join(dir.strip_prefix(&from_dir).unwrap_or(dir)))
+        .collect();
+    for target in &targets {
+        if target.exists() {
+            return Err(format!("目标分类中已存在同名资源: {}", target.display()));
+        }
+    }
+
+    let originals = rewrite_major_category(&resource_dirs, to_key)?;
+
+    let mut moved: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (dir, target) in resource_dirs.iter().zip(&targets) {
+        let result = fs::create_dir_all(target.parent().unwrap_or(&to_dir))
+            .and_then(|_| fs::rename(dir, target));
+        if let Err(e) = result {
+            for (from, to) in moved.iter().rev() {
+                let _ = fs::rename(to, from);
+            }
+            restore_manifests(&originals);
+            return Err(format!("移动资源目录失败: {}", e));
+        }
+        moved.push((dir.clone(), target.clone()));
+    }
+
+    // 清理移空的子分类目录
+    if let Ok(entries) = fs::read_dir(&from_dir) {
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                let _ = fs::remove_dir(entry.path());
+            }
+        }
+    }
+
+    Ok(Relocation { moved, originals })
+}
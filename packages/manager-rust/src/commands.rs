@@ -1,21 +1,36 @@
 use crate::ai;
+use crate::ai_diagnostics;
+use crate::ai_limits;
+use crate::ai_output;
+use crate::ai_services;
+use crate::ai_usage;
 use crate::category_ops;
+use crate::http_client;
+use crate::id_service;
 use crate::import_export;
 use crate::json_file_ops;
+use crate::markdown;
+use crate::path_guard::{self, AllowedRoots};
 use crate::resource_ops;
-use crate::types::{AIServiceConfig, ContentFileEntry, LocalAIServices, MetaConfig, ResourceSummary, SharedAIServices};
-use tauri::{State, Window};
+use crate::secret_store;
+use crate::spreadsheet;
+use crate::tasks::{RequestRegistry, TaskRegistry};
+use crate::types::{AIHttpSettings, AIServiceConfig, ContentFileEntry, LocalAIServices, MetaConfig, RenameIdResult, ResourceSummary, SharedAIServices};
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter, State, Window};
+use tauri_plugin_dialog::DialogExt;
 use std::sync::Mutex;
 
 // ============================================================
 // 数据目录状态（由启动参数传入）
 // ============================================================
 
-pub struct DataDirState(pub Mutex<Option<String>>);
+/// 第二个字段记录数据目录之外允许访问的位置（bundled-resources、对话框选择的导入导出文件）
+pub struct DataDirState(pub Mutex<Option<String>>, pub AllowedRoots);
 
 impl DataDirState {
     pub fn new() -> Self {
-        Self(Mutex::new(None))
+        Self(Mutex::new(None), AllowedRoots::default())
     }
 
     pub fn set(&self, dir: String) {
@@ -24,9 +39,31 @@ impl DataDirState {
         }
     }
 
+    /// 登记启动参数传入的数据目录：设为当前目录，并作为允许的根目录供之后切换回来
+    pub fn init(&self, dir: String) {
+        self.1.add_root(std::path::Path::new(&dir));
+        self.set(dir);
+    }
+
     pub fn get(&self) -> Option<String> {
         self.0.lock().ok().and_then(|g| g.clone())
     }
+
+    /// 将前端传入的路径解析为沙箱内的绝对路径（相对路径基于当前数据目录）
+    pub fn resolve_path(&self, raw: &str) -> Result<String, String> {
+        let path = path_guard::resolve(self.get().as_deref(), &self.1, raw)?;
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    /// 同 resolve_path，但不允许是根目录本身（用于资源、内容文件等子路径）
+    pub fn resolve_child(&self, raw: &str) -> Result<String, String> {
+        let path = path_guard::resolve_child(self.get().as_deref(), &self.1, raw)?;
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    pub fn resolve_children(&self, raws: &[String]) -> Result<Vec<String>, String> {
+        raws.iter().map(|raw| self.resolve_child(raw)).collect()
+    }
 }
 
 /// 获取启动参数传入的数据目录
@@ -35,56 +72,220 @@ pub fn cmd_get_data_dir(state: State<'_, DataDirState>) -> Result<Option<String>
     Ok(state.get())
 }
 
+/// 设置当前数据目录（前端切换资源仓库时调用），之后所有路径都限制在该目录内
+///
+/// 只接受已登记的根目录（启动参数目录、bundled-resources、系统对话框选择的文件夹）及其子目录，
+/// 防止前端把沙箱根目录指向任意位置。
+#[tauri::command]
+pub fn cmd_set_data_dir(state: State<'_, DataDirState>, dir: String) -> Result<String, String> {
+    let path = path_guard::normalize(std::path::Path::new(&dir))?;
+    if !path.is_dir() {
+        return Err(format!("数据目录不存在: {}", dir));
+    }
+    // 拒绝把整个文件系统根目录或用户主目录设为数据目录
+    let home = dirs::home_dir().and_then(|h| dunce::canonicalize(h).ok());
+    if path.parent().is_none() || home.as_ref() == Some(&path) || !state.1.contains_dir(&path) {
+        return Err(path_guard::PathGuardError::OutsideAllowedRoots(dir).into());
+    }
+    let dir = path.to_string_lossy().to_string();
+    state.set(dir.clone());
+    Ok(dir)
+}
+
+/// 通过系统保存对话框选择导出目标，选中的文件登记为允许写入
+#[tauri::command]
+pub async fn cmd_choose_export_path(
+    app: AppHandle,
+    state: State<'_, DataDirState>,
+    default_name: String,
+    filter_name: String,
+    extensions: Vec<String>,
+) -> Result<Option<String>, String> {
+    let exts: Vec<&str> = extensions.iter().map(|e| e.as_str()).collect();
+    let picked = app
+        .dialog()
+        .file()
+        .add_filter(filter_name, &exts)
+        .set_file_name(default_name)
+        .blocking_save_file();
+    let Some(picked) = picked else {
+        return Ok(None);
+    };
+    let path = picked.into_path().map_err(|e| format!("无效的导出路径: {}", e))?;
+    state.1.allow_file(&path);
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// 通过系统打开对话框选择导入文件，选中的文件登记为允许读取
+#[tauri::command]
+pub async fn cmd_choose_import_file(
+    app: AppHandle,
+    state: State<'_, DataDirState>,
+    filter_name: String,
+    extensions: Vec<String>,
+) -> Result<Option<String>, String> {
+    let exts: Vec<&str> = extensions.iter().map(|e| e.as_str()).collect();
+    let picked = app
+        .dialog()
+        .file()
+        .add_filter(filter_name, &exts)
+        .blocking_pick_file();
+    let Some(picked) = picked else {
+        return Ok(None);
+    };
+    let path = picked.into_path().map_err(|e| format!("无效的导入路径: {}", e))?;
+    state.1.allow_file(&path);
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// 通过系统对话框选择文件夹，选中的文件夹整体登记为允许访问
+#[tauri::command]
+pub async fn cmd_choose_folder(
+    app: AppHandle,
+    state: State<'_, DataDirState>,
+) -> Result<Option<String>, String> {
+    let Some(picked) = app.dialog().file().blocking_pick_folder() else {
+        return Ok(None);
+    };
+    let path = picked.into_path().map_err(|e| format!("无效的文件夹路径: {}", e))?;
+    state.1.add_root(&path);
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
 // ============================================================
 // 资源 CRUD 命令
 // ============================================================
 
 #[tauri::command]
-pub fn cmd_scan_resources(data_dir: String) -> Result<Vec<ResourceSummary>, String> {
-    resource_ops::scan_resources(&data_dir)
+pub fn cmd_scan_resources(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    nested_sub_categories: Option<bool>,
+) -> Result<Vec<ResourceSummary>, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    resource_ops::scan_resources(&data_dir, nested_sub_categories.unwrap_or(false))
 }
 
 #[tauri::command]
-pub fn cmd_read_manifest(resource_path: String) -> Result<serde_json::Value, String> {
+pub fn cmd_read_manifest(
+    state: State<'_, DataDirState>,
+    resource_path: String,
+) -> Result<serde_json::Value, String> {
+    let resource_path = state.resolve_child(&resource_path)?;
     resource_ops::read_manifest(&resource_path)
 }
 
 #[tauri::command]
 pub fn cmd_save_manifest(
+    state: State<'_, DataDirState>,
     resource_path: String,
     manifest: serde_json::Value,
 ) -> Result<(), String> {
+    let resource_path = state.resolve_child(&resource_path)?;
     resource_ops::save_manifest(&resource_path, manifest)
 }
 
 #[tauri::command]
 pub fn cmd_create_resource(
+    state: State<'_, DataDirState>,
     data_dir: String,
     category: String,
     id: String,
     manifest: serde_json::Value,
     content_files: Vec<ContentFileEntry>,
 ) -> Result<String, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
     resource_ops::create_resource(&data_dir, &category, &id, manifest, content_files)
 }
 
+/// 根据名称生成仓库内唯一的资源 ID（中文按拼音音译）
+#[tauri::command]
+pub fn cmd_generate_resource_id(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    name: String,
+) -> Result<String, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    Ok(id_service::generate_id(&data_dir, &name, "res"))
+}
+
+/// 校验资源 ID 合法且未被占用
 #[tauri::command]
-pub fn cmd_delete_resource(resource_path: String) -> Result<(), String> {
+pub fn cmd_validate_resource_id(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    id: String,
+) -> Result<(), String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    id_service::ensure_available(&data_dir, &id)
+}
+
+#[tauri::command]
+pub fn cmd_delete_resource(
+    state: State<'_, DataDirState>,
+    resource_path: String,
+) -> Result<(), String> {
+    let resource_path = state.resolve_child(&resource_path)?;
     resource_ops::delete_resource(&resource_path)
 }
 
 #[tauri::command]
-pub fn cmd_batch_delete_resources(resource_paths: Vec<String>) -> Result<u32, String> {
+pub fn cmd_duplicate_resource(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    resource_path: String,
+    target_category: Option<String>,
+) -> Result<String, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    let resource_path = state.resolve_child(&resource_path)?;
+    resource_ops::duplicate_resource(&data_dir, &resource_path, target_category.as_deref())
+}
+
+#[tauri::command]
+pub fn cmd_rename_resource_id(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    resource_path: String,
+    new_id: String,
+    keep_alias: Option<bool>,
+) -> Result<RenameIdResult, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    let resource_path = state.resolve_child(&resource_path)?;
+    resource_ops::rename_resource_id(
+        &data_dir,
+        &resource_path,
+        &new_id,
+        keep_alias.unwrap_or(false),
+    )
+}
+
+#[tauri::command]
+pub fn cmd_batch_delete_resources(
+    state: State<'_, DataDirState>,
+    resource_paths: Vec<String>,
+) -> Result<u32, String> {
+    let resource_paths = state.resolve_children(&resource_paths)?;
     resource_ops::batch_delete_resources(&resource_paths)
 }
 
 #[tauri::command]
-pub fn cmd_reorder_resources(id_order_pairs: Vec<(String, i32)>) -> Result<(), String> {
+pub fn cmd_reorder_resources(
+    state: State<'_, DataDirState>,
+    id_order_pairs: Vec<(String, i32)>,
+) -> Result<(), String> {
+    let id_order_pairs = id_order_pairs
+        .into_iter()
+        .map(|(path, order)| state.resolve_child(&path).map(|p| (p, order)))
+        .collect::<Result<Vec<_>, String>>()?;
     resource_ops::reorder_resources(id_order_pairs)
 }
 
 #[tauri::command]
-pub fn cmd_reindex_all_orders(data_dir: String) -> Result<u32, String> {
+pub fn cmd_reindex_all_orders(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+) -> Result<u32, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
     resource_ops::reindex_all_orders(&data_dir)
 }
 
@@ -93,12 +294,21 @@ pub fn cmd_reindex_all_orders(data_dir: String) -> Result<u32, String> {
 // ============================================================
 
 #[tauri::command]
-pub fn cmd_read_content_file(file_path: String) -> Result<String, String> {
+pub fn cmd_read_content_file(
+    state: State<'_, DataDirState>,
+    file_path: String,
+) -> Result<String, String> {
+    let file_path = state.resolve_child(&file_path)?;
     resource_ops::read_content_file(&file_path)
 }
 
 #[tauri::command]
-pub fn cmd_save_content_file(file_path: String, content: String) -> Result<(), String> {
+pub fn cmd_save_content_file(
+    state: State<'_, DataDirState>,
+    file_path: String,
+    content: String,
+) -> Result<(), String> {
+    let file_path = state.resolve_child(&file_path)?;
     resource_ops::save_content_file(&file_path, &content)
 }
 
@@ -107,33 +317,328 @@ pub fn cmd_save_content_file(file_path: String, content: String) -> Result<(), S
 // ============================================================
 
 #[tauri::command]
-pub fn cmd_read_meta(data_dir: String) -> Result<MetaConfig, String> {
+pub fn cmd_read_meta(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+) -> Result<MetaConfig, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
     category_ops::read_meta(&data_dir)
 }
 
 #[tauri::command]
-pub fn cmd_save_meta(data_dir: String, meta: MetaConfig) -> Result<(), String> {
+pub fn cmd_save_meta(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    meta: MetaConfig,
+) -> Result<(), String> {
+    let data_dir = state.resolve_path(&data_dir)?;
     category_ops::save_meta(&data_dir, meta)
 }
 
+#[tauri::command]
+pub fn cmd_rename_category(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    old_key: String,
+    new_key: String,
+) -> Result<u32, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    category_ops::rename_category(&data_dir, &old_key, &new_key)
+}
+
+#[tauri::command]
+pub fn cmd_delete_category(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    key: String,
+    reassign_to: Option<String>,
+) -> Result<u32, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    category_ops::delete_category(&data_dir, &key, reassign_to.as_deref())
+}
+
+#[tauri::command]
+pub fn cmd_merge_categories(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    source_keys: Vec<String>,
+    target_key: String,
+) -> Result<u32, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    category_ops::merge_categories(&data_dir, &source_keys, &target_key)
+}
+
+#[tauri::command]
+pub fn cmd_reorder_categories(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    keys: Vec<String>,
+) -> Result<(), String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    category_ops::reorder_categories(&data_dir, &keys)
+}
+
 // ============================================================
 // 导入导出命令
 // ============================================================
 
+/// 导出进度事件（export-progress）的载荷
+#[derive(Clone, serde::Serialize)]
+struct ExportProgressEvent {
+    #[serde(rename = "exportId")]
+    export_id: String,
+    #[serde(flatten)]
+    progress: import_export::ExportProgress,
+}
+
+/// 导出资源包：在后台线程流式写入，通过 export-progress 事件报告进度，
+/// 可用 cmd_cancel_export(export_id) 取消
 #[tauri::command]
-pub fn cmd_export_resources(
+#[allow(clippy::too_many_arguments)]
+pub async fn cmd_export_resources(
+    window: Window,
+    state: State<'_, DataDirState>,
+    tasks: State<'_, TaskRegistry>,
     resource_paths: Vec<String>,
     output_path: String,
+    resource_type: Option<String>,
+    options: Option<import_export::ExportOptions>,
+    export_id: Option<String>,
 ) -> Result<String, String> {
-    import_export::export_resources(&resource_paths, &output_path)
+    let resource_paths = state.resolve_children(&resource_paths)?;
+    let output_path = state.resolve_path(&output_path)?;
+    let export_id = export_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let cancel = tasks.register(&export_id);
+    let registered = cancel.clone();
+    let options = options.unwrap_or_default();
+
+    let event_id = export_id.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        import_export::export_resources(
+            &resource_paths,
+            &output_path,
+            resource_type.as_deref(),
+            &options,
+            &cancel,
+            &mut |progress| {
+                let _ = window.emit(
+                    "export-progress",
+                    ExportProgressEvent {
+                        export_id: event_id.clone(),
+                        progress: progress.clone(),
+                    },
+                );
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("导出任务异常退出: {}", e));
+    tasks.finish(&export_id, &registered);
+    result?
+}
+
+/// 取消进行中的导出，导出已结束时返回 false
+#[tauri::command]
+pub fn cmd_cancel_export(tasks: State<'_, TaskRegistry>, export_id: String) -> bool {
+    tasks.cancel(&export_id)
 }
 
 #[tauri::command]
 pub fn cmd_import_resources(
+    state: State<'_, DataDirState>,
+    zip_path: String,
+    data_dir: String,
+    options: Option<import_export::ImportOptions>,
+) -> Result<import_export::ImportResult, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    let zip_path = state.resolve_path(&zip_path)?;
+    import_export::import_resources(&zip_path, &data_dir, &options.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn cmd_export_json_templates(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    template_paths: Vec<String>,
+    output_path: String,
+    resource_type: Option<String>,
+) -> Result<String, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    let output_path = state.resolve_path(&output_path)?;
+    import_export::export_json_templates(
+        &data_dir,
+        &template_paths,
+        &output_path,
+        resource_type.as_deref(),
+    )
+}
+
+#[tauri::command]
+pub fn cmd_inspect_package(
+    state: State<'_, DataDirState>,
+    zip_path: String,
+) -> Result<import_export::PackageInspection, String> {
+    let zip_path = state.resolve_path(&zip_path)?;
+    import_export::inspect_package(&zip_path)
+}
+
+#[tauri::command]
+pub fn cmd_import_json_templates(
+    state: State<'_, DataDirState>,
     zip_path: String,
     data_dir: String,
+    options: Option<import_export::ImportOptions>,
+) -> Result<import_export::ImportResult, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    let zip_path = state.resolve_path(&zip_path)?;
+    import_export::import_json_templates(&zip_path, &data_dir, &options.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn cmd_export_bundle(
+    state: State<'_, DataDirState>,
+    resource_paths: Vec<String>,
+    output_path: String,
+    format: import_export::BundleFormat,
+) -> Result<import_export::BundleExportResult, String> {
+    let resource_paths = state.resolve_children(&resource_paths)?;
+    let output_path = state.resolve_path(&output_path)?;
+    import_export::export_bundle(&resource_paths, &output_path, format)
+}
+
+#[tauri::command]
+pub fn cmd_import_bundle(
+    state: State<'_, DataDirState>,
+    bundle_path: String,
+    data_dir: String,
+    options: Option<import_export::ImportOptions>,
+) -> Result<import_export::ImportResult, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    let bundle_path = state.resolve_path(&bundle_path)?;
+    import_export::import_bundle(&bundle_path, &data_dir, &options.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn cmd_export_json_templates_bundle(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    template_paths: Vec<String>,
+    output_path: String,
+    format: import_export::BundleFormat,
+) -> Result<String, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    let output_path = state.resolve_path(&output_path)?;
+    import_export::export_json_templates_bundle(&data_dir, &template_paths, &output_path, format)
+}
+
+#[tauri::command]
+pub fn cmd_import_json_templates_bundle(
+    state: State<'_, DataDirState>,
+    bundle_path: String,
+    data_dir: String,
+    options: Option<import_export::ImportOptions>,
+) -> Result<import_export::ImportResult, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    let bundle_path = state.resolve_path(&bundle_path)?;
+    import_export::import_json_templates_bundle(
+        &bundle_path,
+        &data_dir,
+        &options.unwrap_or_default(),
+    )
+}
+
+#[tauri::command]
+pub fn cmd_import_markdown(
+    state: State<'_, DataDirState>,
+    folder: String,
+    data_dir: String,
+    options: Option<import_export::ImportOptions>,
+) -> Result<import_export::ImportResult, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    let folder = state.resolve_path(&folder)?;
+    markdown::import_markdown(&folder, &data_dir, &options.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn cmd_import_json_templates_markdown(
+    state: State<'_, DataDirState>,
+    folder: String,
+    data_dir: String,
+    options: Option<import_export::ImportOptions>,
 ) -> Result<import_export::ImportResult, String> {
-    import_export::import_resources(&zip_path, &data_dir)
+    let data_dir = state.resolve_path(&data_dir)?;
+    let folder = state.resolve_path(&folder)?;
+    markdown::import_json_templates_markdown(&folder, &data_dir, &options.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn cmd_export_json_templates_markdown(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    template_paths: Vec<String>,
+    output_dir: String,
+) -> Result<u32, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    let output_dir = state.resolve_path(&output_dir)?;
+    markdown::export_json_templates_markdown(&data_dir, &template_paths, &output_dir)
+}
+
+// ============================================================
+// 表格导出与批量编辑命令
+// ============================================================
+
+#[tauri::command]
+pub fn cmd_export_sheet(
+    state: State<'_, DataDirState>,
+    resource_paths: Vec<String>,
+    output_path: String,
+    options: Option<spreadsheet::SheetExportOptions>,
+) -> Result<String, String> {
+    let resource_paths = state.resolve_children(&resource_paths)?;
+    let output_path = state.resolve_path(&output_path)?;
+    spreadsheet::export_sheet(&resource_paths, &output_path, &options.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn cmd_import_sheet(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    sheet_path: String,
+    options: Option<spreadsheet::SheetImportOptions>,
+) -> Result<spreadsheet::SheetImportResult, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    let sheet_path = state.resolve_path(&sheet_path)?;
+    spreadsheet::import_sheet(&data_dir, &sheet_path, &options.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn cmd_export_json_templates_sheet(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    template_paths: Vec<String>,
+    output_path: String,
+    options: Option<spreadsheet::SheetExportOptions>,
+) -> Result<String, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    let output_path = state.resolve_path(&output_path)?;
+    spreadsheet::export_json_templates_sheet(
+        &data_dir,
+        &template_paths,
+        &output_path,
+        &options.unwrap_or_default(),
+    )
+}
+
+#[tauri::command]
+pub fn cmd_import_json_templates_sheet(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    sheet_path: String,
+    options: Option<spreadsheet::SheetImportOptions>,
+) -> Result<spreadsheet::SheetImportResult, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    let sheet_path = state.resolve_path(&sheet_path)?;
+    spreadsheet::import_json_templates_sheet(&data_dir, &sheet_path, &options.unwrap_or_default())
 }
 
 // ============================================================
@@ -142,42 +647,243 @@ pub fn cmd_import_resources(
 
 #[tauri::command]
 pub fn cmd_batch_set_enabled(
+    state: State<'_, DataDirState>,
     resource_paths: Vec<String>,
     enabled: bool,
 ) -> Result<u32, String> {
+    let resource_paths = state.resolve_children(&resource_paths)?;
     resource_ops::batch_set_enabled(&resource_paths, enabled)
 }
 
 #[tauri::command]
 pub fn cmd_batch_move_category(
+    state: State<'_, DataDirState>,
     resource_paths: Vec<String>,
     new_category: String,
+    new_sub_category: Option<String>,
+    nested_sub_categories: Option<bool>,
 ) -> Result<u32, String> {
-    resource_ops::batch_move_category(&resource_paths, &new_category)
+    let resource_paths = state.resolve_children(&resource_paths)?;
+    resource_ops::batch_move_category(
+        &resource_paths,
+        &new_category,
+        new_sub_category.as_deref(),
+        nested_sub_categories.unwrap_or(false),
+    )
 }
 
 // ============================================================
 // AI 生成命令
 // ============================================================
 
+/// 非流式生成；传入 request_id 时可用 cmd_ai_cancel 取消。
+/// resource_type 仅用于用量记录；预算已用完时返回 `[BUDGET]` 开头的错误，
+/// 用户确认后以 allow_over_budget 重新调用
+///
+/// 已弃用，请改用 cmd_ai_generate_with_service。保留给自带完整配置的外部调用方（如脚本、
+/// 插件）：只使用调用方传入的 Key，从不还原已保存的 Key，脱敏 Key 直接拒绝。
 #[tauri::command]
 pub async fn cmd_ai_generate(
+    requests: State<'_, RequestRegistry>,
     config: AIServiceConfig,
     system_prompt: String,
     user_prompt: String,
+    request_id: Option<String>,
+    resource_type: Option<String>,
+    allow_over_budget: Option<bool>,
 ) -> Result<String, String> {
-    ai::ai_generate(&config, &system_prompt, &user_prompt).await
+    reject_masked_key(&config)?;
+    let context = ai_usage::UsageContext {
+        service_id: String::new(),
+        resource_type: resource_type.unwrap_or_default(),
+        allow_over_budget: allow_over_budget.unwrap_or(false),
+    };
+    run_ai_generate(&requests, &config, &context, &system_prompt, &user_prompt, request_id).await
 }
 
+/// 流式生成：增量与最终事件（done / cancelled / error）都发送到该请求自己的 on_event 通道，
+/// 可用 cmd_ai_cancel(request_id) 中止；取消时返回已收到的部分内容
+///
+/// 已弃用，请改用 cmd_ai_generate_stream_with_service；Key 的处理同 cmd_ai_generate。
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn cmd_ai_generate_stream(
-    window: Window,
+    requests: State<'_, RequestRegistry>,
     config: AIServiceConfig,
     system_prompt: String,
     user_prompt: String,
+    request_id: Option<String>,
+    resource_type: Option<String>,
+    allow_over_budget: Option<bool>,
+    on_event: Channel<ai::AIStreamEvent>,
 ) -> Result<String, String> {
-    ai::ai_generate_stream(window, &config, &system_prompt, &user_prompt, "ai-generate-stream")
-        .await
+    reject_masked_key(&config)?;
+    let context = ai_usage::UsageContext {
+        service_id: String::new(),
+        resource_type: resource_type.unwrap_or_default(),
+        allow_over_budget: allow_over_budget.unwrap_or(false),
+    };
+    run_ai_generate_stream(
+        &requests,
+        &config,
+        &context,
+        &system_prompt,
+        &user_prompt,
+        request_id,
+        on_event,
+    )
+    .await
+}
+
+/// 直接传入配置的命令只接受调用方自己的明文 Key
+fn reject_masked_key(config: &AIServiceConfig) -> Result<(), String> {
+    if ai_services::is_masked(&config.api_key) {
+        return Err("请按服务 ID 调用生成命令，或传入完整的 API Key".to_string());
+    }
+    Ok(())
+}
+
+/// 按服务 ID 生成（非流式），API Key 在后端解析，不经过前端；
+/// service_id 为空时使用默认服务，temperature / max_tokens 覆盖服务设置
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn cmd_ai_generate_with_service(
+    requests: State<'_, RequestRegistry>,
+    service_id: Option<String>,
+    system_prompt: String,
+    user_prompt: String,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    request_id: Option<String>,
+    resource_type: Option<String>,
+    allow_over_budget: Option<bool>,
+) -> Result<String, String> {
+    let overrides = ai_services::ServiceOverrides { temperature, max_tokens };
+    let (service_id, config) =
+        ai_services::resolve_service_with_id(service_id.as_deref().unwrap_or(""), &overrides)?;
+    let context = ai_usage::UsageContext {
+        service_id,
+        resource_type: resource_type.unwrap_or_default(),
+        allow_over_budget: allow_over_budget.unwrap_or(false),
+    };
+    run_ai_generate(&requests, &config, &context, &system_prompt, &user_prompt, request_id).await
+}
+
+/// 按服务 ID 流式生成，事件与 cmd_ai_generate_stream 相同
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn cmd_ai_generate_stream_with_service(
+    requests: State<'_, RequestRegistry>,
+    service_id: Option<String>,
+    system_prompt: String,
+    user_prompt: String,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    request_id: Option<String>,
+    resource_type: Option<String>,
+    allow_over_budget: Option<bool>,
+    on_event: Channel<ai::AIStreamEvent>,
+) -> Result<String, String> {
+    let overrides = ai_services::ServiceOverrides { temperature, max_tokens };
+    let (service_id, config) =
+        ai_services::resolve_service_with_id(service_id.as_deref().unwrap_or(""), &overrides)?;
+    let context = ai_usage::UsageContext {
+        service_id,
+        resource_type: resource_type.unwrap_or_default(),
+        allow_over_budget: allow_over_budget.unwrap_or(false),
+    };
+    run_ai_generate_stream(
+        &requests,
+        &config,
+        &context,
+        &system_prompt,
+        &user_prompt,
+        request_id,
+        on_event,
+    )
+    .await
+}
+
+async fn run_ai_generate(
+    requests: &RequestRegistry,
+    config: &AIServiceConfig,
+    context: &ai_usage::UsageContext,
+    system_prompt: &str,
+    user_prompt: &str,
+    request_id: Option<String>,
+) -> Result<String, String> {
+    let request_id = request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let cancel = requests.register(&request_id);
+    let result =
+        ai::ai_complete_tracked(config, context, system_prompt, user_prompt, &cancel).await;
+    requests.finish(&request_id, &cancel);
+    result.map(|completion| completion.content)
+}
+
+async fn run_ai_generate_stream(
+    requests: &RequestRegistry,
+    config: &AIServiceConfig,
+    context: &ai_usage::UsageContext,
+    system_prompt: &str,
+    user_prompt: &str,
+    request_id: Option<String>,
+    on_event: Channel<ai::AIStreamEvent>,
+) -> Result<String, String> {
+    let request_id = request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let cancel = requests.register(&request_id);
+
+    let delta_channel = on_event.clone();
+    let end = ai::ai_generate_stream(
+        config,
+        context,
+        system_prompt,
+        user_prompt,
+        &cancel,
+        &mut |delta| {
+            let _ = delta_channel.send(ai::AIStreamEvent::Delta {
+                content: delta.to_string(),
+            });
+        },
+    )
+    .await;
+    requests.finish(&request_id, &cancel);
+
+    let _ = on_event.send(end.clone());
+    match end {
+        ai::AIStreamEvent::Error { error, .. } => Err(error),
+        ai::AIStreamEvent::Done { content, .. }
+        | ai::AIStreamEvent::Cancelled { content, .. }
+        | ai::AIStreamEvent::Delta { content } => Ok(content),
+    }
+}
+
+/// 取消进行中的 AI 请求，请求已结束时返回 false
+#[tauri::command]
+pub fn cmd_ai_cancel(requests: State<'_, RequestRegistry>, request_id: String) -> bool {
+    requests.cancel(&request_id)
+}
+
+/// 测试 AI 服务连接：发送最小请求并把失败原因归类为可操作的提示
+///
+/// 传入 config 时测试设置界面中尚未保存的配置（脱敏 Key 按 service_id 还原），
+/// 否则测试已保存的服务。
+#[tauri::command]
+pub async fn cmd_ai_test_service(
+    service_id: Option<String>,
+    config: Option<AIServiceConfig>,
+) -> Result<ai_diagnostics::AIServiceTestResult, String> {
+    let config = ai_services::resolve_draft(service_id.as_deref().unwrap_or(""), config)?;
+    Ok(ai_diagnostics::test_service(&config).await)
+}
+
+/// 查询服务可用的模型列表（用于设置界面的模型下拉框）
+#[tauri::command]
+pub async fn cmd_ai_list_models(
+    service_id: Option<String>,
+    config: Option<AIServiceConfig>,
+) -> Result<Vec<String>, String> {
+    let config = ai_services::resolve_draft(service_id.as_deref().unwrap_or(""), config)?;
+    ai_diagnostics::list_models(&config).await
 }
 
 // ============================================================
@@ -185,15 +891,28 @@ pub async fn cmd_ai_generate_stream(
 // ============================================================
 
 #[tauri::command]
-pub fn cmd_run_build_script(repo_dir: String) -> Result<String, String> {
-    let build_script = std::path::Path::new(&repo_dir).join("scripts").join("build.sh");
+pub fn cmd_run_build_script(
+    state: State<'_, DataDirState>,
+    repo_dir: String,
+) -> Result<String, String> {
+    // 仓库根目录只能是当前数据目录的直接上级或其所在的 git 仓库根目录
+    let repo_path = path_guard::normalize(std::path::Path::new(&repo_dir))?;
+    let data_dir = state
+        .get()
+        .and_then(|d| path_guard::normalize(std::path::Path::new(&d)).ok())
+        .ok_or_else(|| path_guard::PathGuardError::NoDataDir(repo_dir.clone()))?;
+    let git_root = data_dir.ancestors().find(|p| p.join(".git").exists());
+    if data_dir.parent() != Some(repo_path.as_path()) && git_root != Some(repo_path.as_path()) {
+        return Err(path_guard::PathGuardError::OutsideAllowedRoots(repo_dir).into());
+    }
+    let build_script = repo_path.join("scripts").join("build.sh");
     if !build_script.exists() {
         return Err("build.sh 不存在".to_string());
     }
 
     let output = std::process::Command::new("bash")
         .arg(&build_script)
-        .current_dir(&repo_dir)
+        .current_dir(&repo_path)
         .output()
         .map_err(|e| format!("执行 build.sh 失败: {}", e))?;
 
@@ -211,94 +930,161 @@ pub fn cmd_run_build_script(repo_dir: String) -> Result<String, String> {
 // AI 配置持久化
 // ============================================================
 
+/// 旧版单服务配置，API Key 脱敏后返回
 #[tauri::command]
 pub fn cmd_load_ai_config() -> Result<AIServiceConfig, String> {
-    let config_path = get_ai_config_path()?;
-    if !config_path.exists() {
-        return Ok(AIServiceConfig {
-            base_url: "https://api.openai.com/v1".to_string(),
-            api_key: String::new(),
-            model: "gpt-4o".to_string(),
-            max_tokens: 4096,
-            temperature: 0.7,
-        });
-    }
-    let content =
-        std::fs::read_to_string(&config_path).map_err(|e| format!("读取 AI 配置失败: {}", e))?;
-    serde_json::from_str(&content).map_err(|e| format!("解析 AI 配置失败: {}", e))
+    ai_services::load_legacy_config_masked()
 }
 
 #[tauri::command]
 pub fn cmd_save_ai_config(config: AIServiceConfig) -> Result<(), String> {
-    let config_path = get_ai_config_path()?;
-    if let Some(parent) = config_path.parent() {
-        std::fs::create_dir_all(parent).ok();
-    }
-    let content = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("序列化 AI 配置失败: {}", e))?;
-    std::fs::write(&config_path, content).map_err(|e| format!("写入 AI 配置失败: {}", e))
-}
-
-fn get_ai_config_path() -> Result<std::path::PathBuf, String> {
-    let home = dirs::home_dir().ok_or("无法获取用户主目录")?;
-    Ok(home
-        .join(".aidocplus")
-        .join("manager-ai-config.json"))
+    ai_services::save_legacy_config(config)
 }
 
 // ============================================================
 // 读取主程序共享的 AI 服务列表
 // ============================================================
 
+/// API Key 脱敏后返回，生成时由 cmd_ai_generate_with_service 在后端解析
 #[tauri::command]
 pub fn cmd_load_shared_ai_services() -> Result<SharedAIServices, String> {
-    let home = dirs::home_dir().ok_or("无法获取用户主目录")?;
-    let path = home.join(".aidocplus").join("ai-services.json");
-    if !path.exists() {
-        return Ok(SharedAIServices {
-            services: vec![],
-            active_service_id: String::new(),
-            temperature: 0.7,
-            max_tokens: 4096,
-        });
-    }
-    let content =
-        std::fs::read_to_string(&path).map_err(|e| format!("读取共享 AI 服务列表失败: {}", e))?;
-    serde_json::from_str(&content).map_err(|e| format!("解析共享 AI 服务列表失败: {}", e))
+    ai_services::load_shared_services().map(ai_services::mask_shared_services)
 }
 
 // ============================================================
 // 资源管理器本地 AI 服务列表
 // ============================================================
 
-fn get_local_ai_services_path() -> Result<std::path::PathBuf, String> {
-    let home = dirs::home_dir().ok_or("无法获取用户主目录")?;
-    Ok(home.join(".aidocplus").join("manager-ai-services.json"))
-}
-
+/// API Key 脱敏后返回
 #[tauri::command]
 pub fn cmd_load_local_ai_services() -> Result<LocalAIServices, String> {
-    let path = get_local_ai_services_path()?;
-    if !path.exists() {
-        return Ok(LocalAIServices {
-            services: vec![],
-            active_service_id: String::new(),
-        });
-    }
-    let content =
-        std::fs::read_to_string(&path).map_err(|e| format!("读取本地 AI 服务列表失败: {}", e))?;
-    serde_json::from_str(&content).map_err(|e| format!("解析本地 AI 服务列表失败: {}", e))
+    ai_services::load_local_services_masked()
 }
 
+/// 保存本地服务列表，未修改的（脱敏）Key 自动还原
 #[tauri::command]
 pub fn cmd_save_local_ai_services(data: LocalAIServices) -> Result<(), String> {
-    let path = get_local_ai_services_path()?;
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent).ok();
-    }
-    let content = serde_json::to_string_pretty(&data)
-        .map_err(|e| format!("序列化本地 AI 服务列表失败: {}", e))?;
-    std::fs::write(&path, content).map_err(|e| format!("写入本地 AI 服务列表失败: {}", e))
+    ai_services::save_local_services(data)
+}
+
+/// 导出本地服务列表（不含 API Key），返回服务数量
+#[tauri::command]
+pub fn cmd_export_ai_services(
+    state: State<'_, DataDirState>,
+    output_path: String,
+) -> Result<u32, String> {
+    let output_path = state.resolve_path(&output_path)?;
+    ai_services::export_services(&output_path)
+}
+
+/// 按 ID 合并导入服务列表，已有服务保留原 API Key，返回合并后的服务数量
+#[tauri::command]
+pub fn cmd_import_ai_services(
+    state: State<'_, DataDirState>,
+    input_path: String,
+) -> Result<u32, String> {
+    let input_path = state.resolve_path(&input_path)?;
+    ai_services::import_services(&input_path)
+}
+
+// ============================================================
+// AI 用量统计
+// ============================================================
+
+/// 按日期 / 模型 / 资源类型等维度汇总用量并估算费用
+#[tauri::command]
+pub fn cmd_ai_usage_summary(
+    query: Option<ai_usage::UsageQuery>,
+) -> Result<ai_usage::UsageSummary, String> {
+    ai_usage::summarize(&query.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn cmd_load_ai_price_table() -> Result<ai_usage::PriceTable, String> {
+    ai_usage::load_price_table()
+}
+
+#[tauri::command]
+pub fn cmd_save_ai_price_table(table: ai_usage::PriceTable) -> Result<(), String> {
+    ai_usage::save_price_table(&table)
+}
+
+// ============================================================
+// AI 预算与限速
+// ============================================================
+
+#[tauri::command]
+pub fn cmd_load_ai_limits() -> Result<ai_limits::AILimits, String> {
+    ai_limits::load_limits()
+}
+
+#[tauri::command]
+pub fn cmd_save_ai_limits(limits: ai_limits::AILimits) -> Result<(), String> {
+    ai_limits::save_limits(&limits)
+}
+
+/// 服务今日与本月已用的 token 与估算费用
+#[tauri::command]
+pub fn cmd_ai_budget_usage(service_id: String) -> Result<ai_limits::BudgetUsage, String> {
+    ai_limits::budget_usage(&service_id)
+}
+
+// ============================================================
+// AI 输出解析
+// ============================================================
+
+/// 解析 AI 生成的资源输出；传入 data_dir 时避开仓库内已占用的 ID
+#[tauri::command]
+pub fn cmd_ai_parse_output(
+    state: State<'_, DataDirState>,
+    text: String,
+    spec: ai_output::AIOutputSpec,
+    data_dir: Option<String>,
+) -> Result<ai_output::AIParseResult, String> {
+    let data_dir = data_dir.map(|d| state.resolve_path(&d)).transpose()?;
+    ai_output::parse_ai_output(&text, &spec, data_dir.as_deref())
+}
+
+// ============================================================
+// AI 网络设置
+// ============================================================
+
+#[tauri::command]
+pub fn cmd_load_ai_network_settings() -> Result<AIHttpSettings, String> {
+    http_client::load_settings()
+}
+
+/// 保存超时、重试、代理与 CA 证书设置，之后的 AI 请求立即使用新设置
+#[tauri::command]
+pub fn cmd_save_ai_network_settings(settings: AIHttpSettings) -> Result<(), String> {
+    http_client::save_settings(settings)
+}
+
+// ============================================================
+// API Key 加密
+// ============================================================
+
+#[tauri::command]
+pub fn cmd_ai_key_status() -> Result<secret_store::SecretStatus, String> {
+    secret_store::status()
+}
+
+/// 口令模式下解锁 API Key（本次运行期间有效）
+#[tauri::command]
+pub fn cmd_ai_unlock_keys(passphrase: String) -> Result<(), String> {
+    secret_store::unlock(&passphrase)
+}
+
+#[tauri::command]
+pub fn cmd_ai_lock_keys() {
+    secret_store::lock()
+}
+
+/// 设置加密口令；passphrase 为空时改回随机密钥文件。已保存的 Key 会用新密钥重新加密
+#[tauri::command]
+pub fn cmd_ai_set_passphrase(passphrase: Option<String>) -> Result<(), String> {
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+    ai_services::change_passphrase(passphrase.as_deref())
 }
 
 // ============================================================
@@ -306,26 +1092,37 @@ pub fn cmd_save_local_ai_services(data: LocalAIServices) -> Result<(), String> {
 // ============================================================
 
 #[tauri::command]
-pub fn cmd_scan_json_resources(data_dir: String) -> Result<Vec<ResourceSummary>, String> {
+pub fn cmd_scan_json_resources(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+) -> Result<Vec<ResourceSummary>, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
     json_file_ops::scan_json_resources(&data_dir)
 }
 
 #[tauri::command]
-pub fn cmd_read_json_categories(data_dir: String) -> Result<Vec<crate::types::CategoryDefinition>, String> {
+pub fn cmd_read_json_categories(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+) -> Result<Vec<crate::types::CategoryDefinition>, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
     json_file_ops::read_json_categories(&data_dir)
 }
 
 #[tauri::command]
 pub fn cmd_read_json_template(
+    state: State<'_, DataDirState>,
     data_dir: String,
     category_key: String,
     template_id: String,
 ) -> Result<json_file_ops::JsonResourceDetail, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
     json_file_ops::read_json_template(&data_dir, &category_key, &template_id)
 }
 
 #[tauri::command]
 pub fn cmd_save_json_template(
+    state: State<'_, DataDirState>,
     data_dir: String,
     category_key: String,
     template_id: String,
@@ -339,12 +1136,15 @@ pub fn cmd_save_json_template(
     plugin_data: Option<serde_json::Value>,
     include_content: Option<bool>,
     include_ai_content: Option<bool>,
+    tags: Option<Vec<String>>,
 ) -> Result<(), String> {
-    json_file_ops::save_json_template(&data_dir, &category_key, &template_id, &name, &description, &content, variables, author_notes, ai_generated_content, enabled_plugins, plugin_data, include_content, include_ai_content)
+    let data_dir = state.resolve_path(&data_dir)?;
+    json_file_ops::save_json_template(&data_dir, &category_key, &template_id, &name, &description, &content, variables, author_notes, ai_generated_content, enabled_plugins, plugin_data, include_content, include_ai_content, tags)
 }
 
 #[tauri::command]
 pub fn cmd_create_json_template(
+    state: State<'_, DataDirState>,
     data_dir: String,
     category_key: String,
     id: String,
@@ -359,47 +1159,135 @@ pub fn cmd_create_json_template(
     include_content: Option<bool>,
     include_ai_content: Option<bool>,
 ) -> Result<String, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
     json_file_ops::create_json_template(&data_dir, &category_key, &id, &name, &description, &content, variables, author_notes, ai_generated_content, enabled_plugins, plugin_data, include_content, include_ai_content)
 }
 
 #[tauri::command]
 pub fn cmd_delete_json_template(
+    state: State<'_, DataDirState>,
     data_dir: String,
     category_key: String,
     template_id: String,
 ) -> Result<(), String> {
+    let data_dir = state.resolve_path(&data_dir)?;
     json_file_ops::delete_json_template(&data_dir, &category_key, &template_id)
 }
 
+#[tauri::command]
+pub fn cmd_duplicate_json_template(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    category_key: String,
+    template_id: String,
+    target_category: Option<String>,
+) -> Result<String, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    json_file_ops::duplicate_json_template(
+        &data_dir,
+        &category_key,
+        &template_id,
+        target_category.as_deref(),
+    )
+}
+
+#[tauri::command]
+pub fn cmd_rename_json_template_id(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    category_key: String,
+    template_id: String,
+    new_id: String,
+    keep_alias: Option<bool>,
+) -> Result<RenameIdResult, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    json_file_ops::rename_json_template_id(
+        &data_dir,
+        &category_key,
+        &template_id,
+        &new_id,
+        keep_alias.unwrap_or(false),
+    )
+}
+
 #[tauri::command]
 pub fn cmd_batch_delete_json_templates(
+    state: State<'_, DataDirState>,
     data_dir: String,
     paths: Vec<String>,
 ) -> Result<u32, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
     json_file_ops::batch_delete_json_templates(&data_dir, &paths)
 }
 
 #[tauri::command]
 pub fn cmd_move_json_template(
+    state: State<'_, DataDirState>,
     data_dir: String,
     from_category: String,
     template_id: String,
     to_category: String,
 ) -> Result<(), String> {
+    let data_dir = state.resolve_path(&data_dir)?;
     json_file_ops::move_json_template(&data_dir, &from_category, &template_id, &to_category)
 }
 
 #[tauri::command]
 pub fn cmd_save_json_category(
+    state: State<'_, DataDirState>,
     data_dir: String,
     category_key: String,
     name: String,
     icon: String,
     order: i32,
 ) -> Result<(), String> {
+    let data_dir = state.resolve_path(&data_dir)?;
     json_file_ops::save_json_category(&data_dir, &category_key, &name, &icon, order)
 }
 
+#[tauri::command]
+pub fn cmd_rename_json_category(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    old_key: String,
+    new_key: String,
+) -> Result<u32, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    json_file_ops::rename_json_category(&data_dir, &old_key, &new_key)
+}
+
+#[tauri::command]
+pub fn cmd_delete_json_category(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    category_key: String,
+    reassign_to: Option<String>,
+) -> Result<u32, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    json_file_ops::delete_json_category(&data_dir, &category_key, reassign_to.as_deref())
+}
+
+#[tauri::command]
+pub fn cmd_merge_json_categories(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    source_keys: Vec<String>,
+    target_key: String,
+) -> Result<u32, String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    json_file_ops::merge_json_categories(&data_dir, &source_keys, &target_key)
+}
+
+#[tauri::command]
+pub fn cmd_reorder_json_categories(
+    state: State<'_, DataDirState>,
+    data_dir: String,
+    keys: Vec<String>,
+) -> Result<(), String> {
+    let data_dir = state.resolve_path(&data_dir)?;
+    json_file_ops::reorder_json_categories(&data_dir, &keys)
+}
+
 /// 返回所有需要注册的 Tauri 命令的 invoke_handler
 /// 在各 app 的 main.rs 中调用：
 /// ```rust
@@ -408,11 +1296,19 @@ pub fn cmd_save_json_category(
 pub fn get_command_handlers() -> impl Fn(tauri::ipc::Invoke) -> bool {
     tauri::generate_handler![
         cmd_get_data_dir,
+        cmd_set_data_dir,
+        cmd_choose_export_path,
+        cmd_choose_import_file,
+        cmd_choose_folder,
         cmd_scan_resources,
         cmd_read_manifest,
         cmd_save_manifest,
         cmd_create_resource,
+        cmd_generate_resource_id,
+        cmd_validate_resource_id,
         cmd_delete_resource,
+        cmd_duplicate_resource,
+        cmd_rename_resource_id,
         cmd_batch_delete_resources,
         cmd_reorder_resources,
         cmd_reindex_all_orders,
@@ -420,18 +1316,57 @@ pub fn get_command_handlers() -> impl Fn(tauri::ipc::Invoke) -> bool {
         cmd_save_content_file,
         cmd_read_meta,
         cmd_save_meta,
+        cmd_rename_category,
+        cmd_delete_category,
+        cmd_merge_categories,
+        cmd_reorder_categories,
         cmd_export_resources,
+        cmd_cancel_export,
         cmd_import_resources,
+        cmd_export_json_templates,
+        cmd_import_json_templates,
+        cmd_inspect_package,
+        cmd_export_bundle,
+        cmd_import_bundle,
+        cmd_export_json_templates_bundle,
+        cmd_import_json_templates_bundle,
+        cmd_import_markdown,
+        cmd_import_json_templates_markdown,
+        cmd_export_json_templates_markdown,
+        cmd_export_sheet,
+        cmd_import_sheet,
+        cmd_export_json_templates_sheet,
+        cmd_import_json_templates_sheet,
         cmd_batch_set_enabled,
         cmd_batch_move_category,
         cmd_ai_generate,
         cmd_ai_generate_stream,
+        cmd_ai_generate_with_service,
+        cmd_ai_generate_stream_with_service,
+        cmd_ai_cancel,
+        cmd_ai_test_service,
+        cmd_ai_list_models,
         cmd_run_build_script,
         cmd_load_ai_config,
         cmd_save_ai_config,
         cmd_load_shared_ai_services,
         cmd_load_local_ai_services,
         cmd_save_local_ai_services,
+        cmd_export_ai_services,
+        cmd_import_ai_services,
+        cmd_load_ai_network_settings,
+        cmd_save_ai_network_settings,
+        cmd_ai_usage_summary,
+        cmd_load_ai_price_table,
+        cmd_save_ai_price_table,
+        cmd_load_ai_limits,
+        cmd_save_ai_limits,
+        cmd_ai_budget_usage,
+        cmd_ai_parse_output,
+        cmd_ai_key_status,
+        cmd_ai_unlock_keys,
+        cmd_ai_lock_keys,
+        cmd_ai_set_passphrase,
         // JSON 文件模式命令
         cmd_scan_json_resources,
         cmd_read_json_categories,
@@ -439,8 +1374,14 @@ pub fn get_command_handlers() -> impl Fn(tauri::ipc::Invoke) -> bool {
         cmd_save_json_template,
         cmd_create_json_template,
         cmd_delete_json_template,
+        cmd_duplicate_json_template,
+        cmd_rename_json_template_id,
         cmd_batch_delete_json_templates,
         cmd_move_json_template,
         cmd_save_json_category,
+        cmd_rename_json_category,
+        cmd_delete_json_category,
+        cmd_merge_json_categories,
+        cmd_reorder_json_categories,
     ]
 }
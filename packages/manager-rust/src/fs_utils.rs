@@ -0,0 +1,69 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 原子写入文件：先写入同目录下的临时文件，再 rename 覆盖目标
+///
+/// rename 在同一文件系统内是原子的，可避免写到一半时崩溃留下残缺的 JSON。
+pub fn write_atomic(path: &Path, content: &str) -> Result<(), String> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("无效的文件路径: {}", path.display()))?
+        .to_string_lossy()
+        .to_string();
+    let tmp_path = path.with_file_name(format!(".{}.tmp", file_name));
+
+    fs::write(&tmp_path, content)
+        .map_err(|e| format!("写入临时文件失败 {}: {}", tmp_path.display(), e))?;
+    fs::rename(&tmp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        format!("写入文件失败 {}: {}", path.display(), e)
+    })
+}
+
+/// 将文件或目录移入 data_dir/_trash/，返回回收站中的新路径
+///
+/// 以 `_` 开头的目录会被扫描跳过，因此回收站内容不会出现在资源列表中。
+pub fn move_to_trash(data_dir: &Path, target: &Path) -> Result<PathBuf, String> {
+    let trash_dir = data_dir.join("_trash");
+    fs::create_dir_all(&trash_dir).map_err(|e| format!("创建回收站目录失败: {}", e))?;
+
+    let name = target
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let stamp = chrono::Utc::now().format("%Y%m%d%H%M%S");
+    let mut trash_path = trash_dir.join(format!("{}-{}", name, stamp));
+    let mut n = 1;
+    while trash_path.exists() {
+        trash_path = trash_dir.join(format!("{}-{}-{}", name, stamp, n));
+        n += 1;
+    }
+
+    fs::rename(target, &trash_path).map_err(|e| format!("移入回收站失败: {}", e))?;
+    Ok(trash_path)
+}
+
+/// 递归复制目录（包含所有子目录与文件），目标目录必须不存在
+pub fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<u64, String> {
+    if dst.exists() {
+        return Err(format!("目标目录已存在: {}", dst.display()));
+    }
+    let mut count = 0u64;
+    for entry in walkdir::WalkDir::new(src) {
+        let entry = entry.map_err(|e| format!("遍历目录失败: {}", e))?;
+        let rel = entry
+            .path()
+            .strip_prefix(src)
+            .map_err(|e| format!("计算相对路径失败: {}", e))?;
+        let target = dst.join(rel);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target).map_err(|e| format!("创建目录失败: {}", e))?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &target)
+                .map_err(|e| format!("复制 {} 失败: {}", entry.path().display(), e))?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
@@ -0,0 +1,220 @@
+//! AI 请求共享的 HTTP 客户端
+//!
+//! 客户端按网络设置（超时、代理、额外 CA 证书）构建一次后在所有 AI 请求间复用，
+//! 保存设置时重新构建。[`send_with_retry`] 在连接失败、超时、429 与 5xx 时按指数退避
+//! 加随机抖动重试，并优先遵循服务端的 `Retry-After`。
+
+use crate::ai_services::aidocplus_dir;
+use crate::fs_utils::write_atomic;
+use crate::types::AIHttpSettings;
+use rand::Rng;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 已构建的客户端及其对应的设置
+static SHARED: Mutex<Option<(AIHttpSettings, reqwest::Client)>> = Mutex::new(None);
+
+// ============================================================
+// 设置读写
+// ============================================================
+
+/// 网络设置文件（~/.aidocplus/manager-network.json）
+pub fn settings_path() -> Result<PathBuf, String> {
+    Ok(aidocplus_dir()?.join("manager-network.json"))
+}
+
+pub fn load_settings() -> Result<AIHttpSettings, String> {
+    let path = settings_path()?;
+    if !path.exists() {
+        return Ok(AIHttpSettings::default());
+    }
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("读取网络设置失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析网络设置失败: {}", e))
+}
+
+/// 保存网络设置并替换共享客户端；设置无效（代理地址或证书错误）时不写入
+pub fn save_settings(settings: AIHttpSettings) -> Result<(), String> {
+    let client = build_client(&settings)?;
+    let path = settings_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    let content = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("序列化网络设置失败: {}", e))?;
+    write_atomic(&path, &content).map_err(|e| format!("写入网络设置失败: {}", e))?;
+    if let Ok(mut shared) = SHARED.lock() {
+        *shared = Some((settings, client));
+    }
+    Ok(())
+}
+
+// ============================================================
+// 客户端
+// ============================================================
+
+/// 按设置构建客户端
+pub fn build_client(settings: &AIHttpSettings) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder();
+    if settings.connect_timeout_secs > 0 {
+        builder = builder.connect_timeout(Duration::from_secs(settings.connect_timeout_secs));
+    }
+    if settings.read_timeout_secs > 0 {
+        builder = builder.read_timeout(Duration::from_secs(settings.read_timeout_secs));
+    }
+
+    let proxy_url = settings.proxy_url.trim();
+    if !proxy_url.is_empty() {
+        let mut proxy = reqwest::Proxy::all(proxy_url)
+            .map_err(|e| format!("代理地址无效「{}」: {}", proxy_url, e))?;
+        if !settings.no_proxy.trim().is_empty() {
+            proxy = proxy.no_proxy(reqwest::NoProxy::from_string(&settings.no_proxy));
+        }
+        builder = builder.proxy(proxy);
+    } else if !settings.use_system_proxy {
+        builder = builder.no_proxy();
+    }
+
+    let ca_path = settings.ca_cert_path.trim();
+    if !ca_path.is_empty() {
+        let pem = std::fs::read(ca_path)
+            .map_err(|e| format!("读取 CA 证书失败「{}」: {}", ca_path, e))?;
+        let certs = reqwest::Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("解析 CA 证书失败「{}」: {}", ca_path, e))?;
+        if certs.is_empty() {
+            return Err(format!("CA 证书文件中没有 PEM 格式的证书: {}", ca_path));
+        }
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    builder
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))
+}
+
+/// 共享客户端及当前设置，首次使用时按设置文件构建
+///
+/// reqwest::Client 内部是引用计数的，克隆后仍共享同一个连接池。
+pub fn shared_client() -> Result<(AIHttpSettings, reqwest::Client), String> {
+    let mut shared = SHARED.lock().map_err(|_| "HTTP 客户端状态异常".to_string())?;
+    if let Some((settings, client)) = shared.as_ref() {
+        return Ok((settings.clone(), client.clone()));
+    }
+    let settings = load_settings()?;
+    let client = build_client(&settings)?;
+    *shared = Some((settings.clone(), client.clone()));
+    Ok((settings, client))
+}
+
+// ============================================================
+// 重试
+// ============================================================
+
+/// 发送请求，对可重试的失败按设置重试
+///
+/// `make_request` 每次重试都会被调用以重新构建请求。重试等待期间调用方可以直接丢弃
+/// 返回的 future 来取消。最后一次仍失败时返回该次的响应（由调用方报告状态码与响应体）。
+pub async fn send_with_retry(
+    settings: &AIHttpSettings,
+    make_request: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<reqwest::Response, String> {
+    let mut attempt = 0;
+    loop {
+        let can_retry = attempt < settings.max_retries;
+        let delay = match make_request().send().await {
+            Ok(response) if can_retry && is_retryable_status(response.status()) => {
+                let max_delay = Duration::from_millis(settings.retry_max_delay_ms);
+                match retry_after(response.headers()) {
+                    // 服务端要求的等待过长，直接返回让用户决定
+                    Some(delay) if delay > max_delay => return Ok(response),
+                    Some(delay) => delay,
+                    None => backoff_delay(settings, attempt),
+                }
+            }
+            Ok(response) => return Ok(response),
+            Err(e) if can_retry && (e.is_connect() || e.is_timeout()) => {
+                eprintln!("[AI] 请求失败: {}", e);
+                backoff_delay(settings, attempt)
+            }
+            Err(e) => return Err(format!("AI 请求失败: {}", e)),
+        };
+        attempt += 1;
+        eprintln!(
+            "[AI] {} ms 后进行第 {}/{} 次重试",
+            delay.as_millis(),
+            attempt,
+            settings.max_retries
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// 429、5xx 中的临时性错误，以及 Anthropic 的 529（服务过载）
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 408 | 429 | 500 | 502 | 503 | 504 | 529)
+}
+
+/// 解析 `retry-after-ms`（OpenAI 扩展）或 `Retry-After`（秒数或 HTTP 日期）
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.parse::<f64>().ok()) {
+        if ms.is_finite() && ms >= 0.0 {
+            return Some(Duration::from_millis(ms as u64));
+        }
+    }
+    let value = header("retry-after")?;
+    if let Ok(secs) = value.parse::<f64>() {
+        // 超出 Duration 范围的值（如 1e20）视为无限等待，由调用方按「等待过长」处理
+        return (secs >= 0.0).then(|| Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// 第 `attempt` 次重试前的等待：初始间隔按 2 的幂增长并封顶，再在后一半范围内随机抖动，
+/// 避免多个请求同时重试
+fn backoff_delay(settings: &AIHttpSettings, attempt: u32) -> Duration {
+    let base = settings.retry_base_delay_ms.max(1);
+    let max = settings.retry_max_delay_ms.max(base);
+    let ceiling = base.saturating_mul(1u64 << attempt.min(20)).min(max);
+    let jittered = rand::thread_rng().gen_range(ceiling / 2..=ceiling);
+    Duration::from_millis(jittered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(name: &'static str, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn retry_after_parses_seconds_and_milliseconds() {
+        assert_eq!(retry_after(&headers("retry-after", "2")), Some(Duration::from_secs(2)));
+        assert_eq!(retry_after(&headers("retry-after", "0.5")), Some(Duration::from_millis(500)));
+        let ms = retry_after(&headers("retry-after-ms", "150"));
+        assert_eq!(ms, Some(Duration::from_millis(150)));
+        assert_eq!(retry_after(&headers("retry-after", "-1")), None);
+        assert_eq!(retry_after(&headers("retry-after", "NaN")), None);
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn retry_after_overflow_does_not_panic() {
+        assert_eq!(retry_after(&headers("retry-after", "1e20")), Some(Duration::MAX));
+        assert_eq!(retry_after(&headers("retry-after", "inf")), Some(Duration::MAX));
+        let ms = retry_after(&headers("retry-after-ms", "1e30"));
+        assert_eq!(ms, Some(Duration::from_millis(u64::MAX)));
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+// ============================================================
+// 资源 ID 生成与校验
+// ============================================================
+
+/// ID 最大长度（避免 Windows 路径过长）
+pub const MAX_ID_LEN: usize = 64;
+
+/// Windows 保留设备名（不区分大小写，带扩展名同样保留）
+const WINDOWS_RESERVED: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// 校验资源 ID 是否可安全用作目录名 / 文件名
+///
+/// 只允许 ASCII 字母、数字、`-`、`_`、`.`，不能以 `_` / `.` 开头（会被扫描跳过），
+/// 不能以 `.` 结尾，也不能是 Windows 保留名。
+pub fn validate_id(id: &str) -> Result<(), String> {
+    if id.is_empty() {
+        return Err("资源 ID 不能为空".to_string());
+    }
+    if id.len() > MAX_ID_LEN {
+        return Err(format!("资源 ID 过长（最多 {} 个字符）: {}", MAX_ID_LEN, id));
+    }
+    if let Some(c) = id
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+    {
+        return Err(format!("资源 ID 包含非法字符 '{}': {}", c, id));
+    }
+    if id.starts_with('_') || id.starts_with('.') || id.ends_with('.') || id.contains("..") {
+        return Err(format!("资源 ID 不能以 _ 或 . 开头、以 . 结尾或包含 ..: {}", id));
+    }
+    let stem = id.split('.').next().unwrap_or(id).to_ascii_lowercase();
+    if WINDOWS_RESERVED.contains(&stem.as_str()) {
+        return Err(format!("资源 ID 是 Windows 保留名: {}", id));
+    }
+    Ok(())
+}
+
+/// 将任意字符串整理为合法 ID：转小写，非法字符替换为 `-`，合并连续分隔符并去掉首尾分隔符
+///
+/// 结果可能为空（输入不含任何可用字符），调用方需自行兜底。
+pub fn sanitize_id(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            out.push(c.to_ascii_lowercase());
+        } else if !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    let trimmed = out.trim_matches(|c| c == '-' || c == '_');
+    let mut id: String = trimmed.chars().take(MAX_ID_LEN).collect();
+    while id.ends_with('-') || id.ends_with('_') {
+        id.pop();
+    }
+    if validate_id(&id).is_err() && !id.is_empty() {
+        // 仅剩保留名等情况：加后缀规避
+        id = format!("{}-res", id);
+    }
+    id
+}
+
+/// 由名称生成可读的 slug：中文按拼音音译（如「学术论文摘要」→ `xue-shu-lun-wen-zhai-yao`）
+pub fn slugify_name(name: &str) -> String {
+    sanitize_id(&deunicode::deunicode(name))
+}
+
+/// 在 existing 中不存在时直接返回 base，否则依次尝试 `{base}-2`、`{base}-3`...
+pub fn make_unique(base: &str, existing: &HashSet<String>) -> String {
+    if !existing.contains(base) {
+        return base.to_string();
+    }
+    let mut n = 2;
+    loop {
+        let suffix = format!("-{}", n);
+        let stem: String = base.chars().take(MAX_ID_LEN - suffix.len()).collect();
+        let candidate = format!("{}{}", stem, suffix);
+        if !existing.contains(&candidate) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+/// 收集仓库内已占用的全部 ID（目录模式资源 + JSON 文件模式模板）
+pub fn existing_ids(data_dir: &str) -> HashSet<String> {
+    let mut ids = crate::resource_ops::collect_all_resource_ids(Path::new(data_dir));
+    ids.extend(crate::json_file_ops::collect_all_template_ids(data_dir));
+    ids
+}
+
+/// 校验 ID 合法且在整个仓库内未被占用
+pub fn ensure_available(data_dir: &str, id: &str) -> Result<(), String> {
+    validate_id(id)?;
+    if existing_ids(data_dir).contains(id) {
+        return Err(format!("资源 ID {} 已存在", id));
+    }
+    Ok(())
+}
+
+/// 根据名称生成仓库内唯一的 ID；名称无法音译出任何字符时使用 `{fallback}-{短 uuid}`
+pub fn generate_id(data_dir: &str, name: &str, fallback: &str) -> String {
+    let mut base = slugify_name(name);
+    if base.is_empty() {
+        let short = uuid::Uuid::new_v4().simple().to_string();
+        let prefix = sanitize_id(fallback);
+        let prefix = if prefix.is_empty() { "res".to_string() } else { prefix };
+        base = format!("{}-{}", prefix, &short[..8]);
+    }
+    make_unique(&base, &existing_ids(data_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_id_accepts_safe_ids() {
+        for id in ["abc", "a-b_c.v2", "A1", "con-txt", "x".repeat(MAX_ID_LEN).as_str()] {
+            assert!(validate_id(id).is_ok(), "{}", id);
+        }
+    }
+
+    #[test]
+    fn validate_id_rejects_unsafe_ids() {
+        let too_long = "x".repeat(MAX_ID_LEN + 1);
+        let cases = [
+            "", "..", "a..b", "../x", "a/b", "a\\b", ".hidden", "_trash", "trailing.", "中文",
+            "a b", "con", "CON", "con.txt", "Lpt9.md", "nul.", too_long.as_str(),
+        ];
+        for id in cases {
+            assert!(validate_id(id).is_err(), "{:?}", id);
+        }
+    }
+
+    #[test]
+    fn sanitize_id_produces_valid_ids() {
+        let cases = [
+            ("Hello World", "hello-world"),
+            ("  --a__b--  ", "a__b"),
+            ("_leading", "leading"),
+            (".hidden", "hidden"),
+            ("trailing.", "trailing"),
+            ("..", ""),
+            ("../../etc/passwd", "etc-passwd"),
+            ("a...b", "a-b"),
+            ("CON", "con-res"),
+            ("con.txt", "con-txt"),
+            ("lpt1", "lpt1-res"),
+            ("！？", ""),
+            ("", ""),
+        ];
+        for (raw, expected) in cases {
+            let id = sanitize_id(raw);
+            assert_eq!(id, expected, "{:?}", raw);
+            assert!(id.is_empty() || validate_id(&id).is_ok(), "{:?} -> {:?}", raw, id);
+        }
+    }
+
+    #[test]
+    fn sanitize_id_truncates_to_max_len() {
+        let id = sanitize_id(&format!("{}-{}", "a".repeat(MAX_ID_LEN - 1), "b".repeat(10)));
+        assert_eq!(id, "a".repeat(MAX_ID_LEN - 1));
+        assert!(validate_id(&id).is_ok());
+        assert_eq!(sanitize_id(&"z".repeat(200)).len(), MAX_ID_LEN);
+    }
+
+    #[test]
+    fn slugify_name_transliterates_chinese() {
+        assert_eq!(slugify_name("学术论文摘要"), "xue-shu-lun-wen-zhai-yao");
+        assert_eq!(slugify_name("周报 Weekly"), "zhou-bao-weekly");
+        assert_eq!(slugify_name("🙂"), "slight-smile");
+        assert_eq!(slugify_name("？！——"), "");
+        assert_eq!(slugify_name(""), "");
+    }
+
+    #[test]
+    fn make_unique_appends_suffix_within_max_len() {
+        let mut existing: HashSet<String> = ["a", "a-2"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(make_unique("b", &existing), "b");
+        assert_eq!(make_unique("a", &existing), "a-3");
+
+        let long = "x".repeat(MAX_ID_LEN);
+        existing.insert(long.clone());
+        let unique = make_unique(&long, &existing);
+        assert_eq!(unique, format!("{}-2", "x".repeat(MAX_ID_LEN - 2)));
+        assert!(validate_id(&unique).is_ok());
+
+        existing.insert(unique);
+        for n in 3..=9 {
+            existing.insert(format!("{}-{}", "x".repeat(MAX_ID_LEN - 2), n));
+        }
+        // 两位数后缀时截掉更多字符
+        let unique = make_unique(&long, &existing);
+        assert_eq!(unique, format!("{}-10", "x".repeat(MAX_ID_LEN - 3)));
+        assert_eq!(unique.len(), MAX_ID_LEN);
+    }
+}
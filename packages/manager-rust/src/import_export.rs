@@ -1,15 +1,108 @@
+use crate::json_file_ops::{CategoryJsonFile, JsonTemplateEntry};
+use crate::package::{
+    FileDigest, PackageFile, PackageManifest, PackageResource, PACKAGE_MANIFEST_FILE,
+};
+use crate::types::{CategoryDefinition, GenericManifest, MetaConfig, SubCategoryDefinition};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
-/// 导出资源为 ZIP 文件
-pub fn export_resources(resource_paths: &[String], output_path: &str) -> Result<String, String> {
-    let file =
-        fs::File::create(output_path).map_err(|e| format!("创建 ZIP 文件失败: {}", e))?;
-    let mut zip = zip::ZipWriter::new(file);
-    let options = zip::write::FileOptions::default()
-        .compression_method(zip::CompressionMethod::Deflated);
+// ============================================================
+// 导出（流式写入 + 进度）
+// ============================================================
+
+/// 导出选项
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ExportOptions {
+    /// 压缩级别 0-9，0 表示只存储不压缩；为空时使用默认级别
+    #[serde(default, rename = "compressionLevel")]
+    pub compression_level: Option<i32>,
+    /// 是否包含内容文件；为 false 时每个资源只导出 manifest.json
+    #[serde(default = "default_true", rename = "includeContentFiles")]
+    pub include_content_files: bool,
+    /// 跳过资源目录内以 `.` 或 `_` 开头的文件与目录
+    #[serde(default = "default_true", rename = "excludeHidden")]
+    pub exclude_hidden: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            compression_level: None,
+            include_content_files: true,
+            exclude_hidden: true,
+        }
+    }
+}
+
+impl ExportOptions {
+    fn file_options(&self) -> Result<zip::write::FileOptions, String> {
+        let options = zip::write::FileOptions::default();
+        Ok(match self.compression_level {
+            Some(0) => options.compression_method(zip::CompressionMethod::Stored),
+            Some(level @ 1..=9) => options
+                .compression_method(zip::CompressionMethod::Deflated)
+                .compression_level(Some(level)),
+            Some(level) => return Err(format!("压缩级别必须在 0-9 之间: {}", level)),
+            None => options.compression_method(zip::CompressionMethod::Deflated),
+        })
+    }
+}
+
+/// 导出进度（每写完一个文件、大文件每写入 1 MiB 回调一次）
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ExportProgress {
+    #[serde(rename = "filesDone")]
+    pub files_done: u64,
+    #[serde(rename = "filesTotal")]
+    pub files_total: u64,
+    #[serde(rename = "bytesDone")]
+    pub bytes_done: u64,
+    #[serde(rename = "bytesTotal")]
+    pub bytes_total: u64,
+    /// 正在写入的包内路径
+    pub current: String,
+}
 
+/// 导出被取消时返回的错误
+pub const EXPORT_CANCELLED: &str = "导出已取消";
+
+/// 大文件写入过程中两次进度回调之间的字节数
+const PROGRESS_STEP_BYTES: u64 = 1024 * 1024;
+
+/// 待写入资源包的文件
+struct ExportItem {
+    path: PathBuf,
+    name: String,
+    size: u64,
+}
+
+/// 导出资源为 .aidocpack 资源包（ZIP 格式）
+///
+/// 每个资源目录作为一个顶层目录写入，根目录附带 aidocpack.json 描述包内容及每个文件的 SHA-256。
+/// resource_type 为空时取第一个资源 manifest 中的 resourceType。
+///
+/// 文件按块流式写入，不整体读入内存；每写完一个文件调用 on_progress。cancel 置位后尽快中止，
+/// 删除写了一半的输出文件并返回 [`EXPORT_CANCELLED`]。
+pub fn export_resources(
+    resource_paths: &[String],
+    output_path: &str,
+    resource_type: Option<&str>,
+    options: &ExportOptions,
+    cancel: &AtomicBool,
+    on_progress: &mut dyn FnMut(&ExportProgress),
+) -> Result<String, String> {
+    let file_options = options.file_options()?;
+    let mut package = PackageManifest::new(resource_type.unwrap_or(""), "directory");
+
+    // 先收集全部文件，得到总数与总大小
+    let mut items = Vec::new();
     for resource_path in resource_paths {
         let path = Path::new(resource_path);
         if !path.exists() || !path.is_dir() {
@@ -22,129 +115,1696 @@ pub fn export_resources(resource_paths: &[String], output_path: &str) -> Result<
             .to_string_lossy()
             .to_string();
 
-        // 递归添加目录中的所有文件
-        add_dir_to_zip(&mut zip, path, &resource_name, options)
-            .map_err(|e| format!("添加文件到 ZIP 失败: {}", e))?;
+        let manifest = fs::read_to_string(path.join("manifest.json"))
+            .ok()
+            .and_then(|c| serde_json::from_str::<GenericManifest>(&c).ok());
+        if let Some(manifest) = &manifest {
+            if package.resource_type.is_empty() {
+                package.resource_type = manifest.resource_type.clone();
+            }
+        }
+        package.resources.push(PackageResource {
+            id: manifest.as_ref().map(|m| m.id.clone()).unwrap_or_else(|| resource_name.clone()),
+            name: manifest.as_ref().map(|m| m.name.clone()).unwrap_or_default(),
+            version: manifest.as_ref().map(|m| m.version.clone()).unwrap_or_default(),
+            major_category: manifest.map(|m| m.major_category).unwrap_or_default(),
+            path: resource_name.clone(),
+        });
+
+        collect_export_items(path, &resource_name, options, &mut items)?;
     }
 
-    zip.finish()
-        .map_err(|e| format!("完成 ZIP 写入失败: {}", e))?;
+    let mut progress = ExportProgress {
+        files_total: items.len() as u64,
+        bytes_total: items.iter().map(|i| i.size).sum(),
+        ..Default::default()
+    };
+    on_progress(&progress);
+
+    let file =
+        fs::File::create(output_path).map_err(|e| format!("创建 ZIP 文件失败: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let result = (|| -> Result<(), String> {
+        for item in &items {
+            progress.current = item.name.clone();
+            let digest = write_file_to_zip(
+                &mut zip,
+                item,
+                file_options,
+                cancel,
+                &mut progress,
+                on_progress,
+            )?;
+            package.files.push(digest);
+            progress.files_done += 1;
+            on_progress(&progress);
+        }
+        write_package_manifest(&mut zip, &package, file_options)?;
+        zip.finish()
+            .map_err(|e| format!("完成 ZIP 写入失败: {}", e))?;
+        Ok(())
+    })();
 
+    if let Err(e) = result {
+        drop(zip);
+        let _ = fs::remove_file(output_path);
+        return Err(e);
+    }
     Ok(output_path.to_string())
 }
 
-fn add_dir_to_zip(
+/// 收集一个资源目录下需要导出的文件（按文件名排序，不跟随符号链接）
+fn collect_export_items(
+    resource_dir: &Path,
+    resource_name: &str,
+    options: &ExportOptions,
+    items: &mut Vec<ExportItem>,
+) -> Result<(), String> {
+    let walker = walkdir::WalkDir::new(resource_dir)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            !(options.exclude_hidden && (name.starts_with('.') || name.starts_with('_')))
+        });
+    for entry in walker {
+        let entry = entry.map_err(|e| format!("遍历目录失败: {}", e))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel = entry
+            .path()
+            .strip_prefix(resource_dir)
+            .map_err(|e| format!("计算相对路径失败: {}", e))?;
+        if !options.include_content_files && rel != Path::new("manifest.json") {
+            continue;
+        }
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        items.push(ExportItem {
+            path: entry.path().to_path_buf(),
+            name: format!("{}/{}", resource_name, zip_entry_name(rel)),
+            size,
+        });
+    }
+    Ok(())
+}
+
+/// 分块把文件写入 ZIP，同时计算校验值、累计进度并检查取消标志
+fn write_file_to_zip(
     zip: &mut zip::ZipWriter<fs::File>,
-    dir: &Path,
-    prefix: &str,
+    item: &ExportItem,
+    options: zip::write::FileOptions,
+    cancel: &AtomicBool,
+    progress: &mut ExportProgress,
+    on_progress: &mut dyn FnMut(&ExportProgress),
+) -> Result<PackageFile, String> {
+    let mut file = fs::File::open(&item.path).map_err(|e| format!("打开文件失败: {}", e))?;
+    zip.start_file(&item.name, options)
+        .map_err(|e| format!("创建 ZIP 条目失败: {}", e))?;
+
+    let mut digest = FileDigest::default();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut since_report = 0u64;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(EXPORT_CANCELLED.to_string());
+        }
+        let n = file
+            .read(&mut buffer)
+            .map_err(|e| format!("读取文件失败: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        zip.write_all(&buffer[..n])
+            .map_err(|e| format!("写入 ZIP 失败: {}", e))?;
+        digest.update(&buffer[..n]);
+        progress.bytes_done += n as u64;
+        since_report += n as u64;
+        if since_report >= PROGRESS_STEP_BYTES {
+            since_report = 0;
+            on_progress(progress);
+        }
+    }
+    Ok(digest.finish(&item.name))
+}
+
+/// 在资源包根目录写入 aidocpack.json
+fn write_package_manifest(
+    zip: &mut zip::ZipWriter<fs::File>,
+    package: &PackageManifest,
     options: zip::write::FileOptions,
 ) -> Result<(), String> {
-    let entries = fs::read_dir(dir).map_err(|e| format!("读取目录失败: {}", e))?;
-
-    for entry in entries.flatten() {
-        let path = entry.path();
-        let name = format!(
-            "{}/{}",
-            prefix,
-            path.file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-        );
+    let content = serde_json::to_string_pretty(package)
+        .map_err(|e| format!("序列化资源包描述失败: {}", e))?;
+    zip.start_file(PACKAGE_MANIFEST_FILE, options)
+        .map_err(|e| format!("创建 ZIP 条目失败: {}", e))?;
+    zip.write_all(content.as_bytes())
+        .map_err(|e| format!("写入 ZIP 失败: {}", e))
+}
 
-        if path.is_dir() {
-            add_dir_to_zip(zip, &path, &name, options)?;
-        } else {
-            let mut file =
-                fs::File::open(&path).map_err(|e| format!("打开文件失败: {}", e))?;
-            let mut buffer = Vec::new();
-            file.read_to_end(&mut buffer)
-                .map_err(|e| format!("读取文件失败: {}", e))?;
+// ============================================================
+// 导入（防 zip-slip / zip 炸弹）
+// ============================================================
+
+/// 单个压缩包最多包含的条目数
+pub const MAX_IMPORT_ENTRIES: usize = 10_000;
+/// 解压后总大小上限（512 MiB）
+pub const MAX_IMPORT_TOTAL_SIZE: u64 = 512 * 1024 * 1024;
+/// 单个文件解压后大小上限（64 MiB）
+pub const MAX_IMPORT_ENTRY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// unix mode 中的文件类型位：符号链接
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
 
-            zip.start_file(&name, options)
-                .map_err(|e| format!("创建 ZIP 条目失败: {}", e))?;
-            zip.write_all(&buffer)
-                .map_err(|e| format!("写入 ZIP 失败: {}", e))?;
+/// 压缩包内一个条目的安全相对路径
+struct ZipEntryPlan {
+    index: usize,
+    rel_path: PathBuf,
+    is_dir: bool,
+}
+
+/// 校验 ZIP 条目名：只允许普通路径分量，拒绝绝对路径、盘符、`..`、`.` 与反斜杠
+fn safe_entry_path(name: &str) -> Option<PathBuf> {
+    if name.is_empty() || name.starts_with('/') || name.contains('\\') || name.contains('\0') {
+        return None;
+    }
+    let mut path = PathBuf::new();
+    for part in name.split('/').filter(|p| !p.is_empty()) {
+        if part == ".." || part == "." || part.contains(':') {
+            return None;
         }
+        path.push(part);
     }
+    if path.as_os_str().is_empty() {
+        return None;
+    }
+    Some(path)
+}
 
-    Ok(())
+/// 相对路径转为 ZIP 内的 `/` 分隔名称（与资源包描述中的 path 对应）
+fn zip_entry_name(rel_path: &Path) -> String {
+    rel_path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 读取压缩包条目内容，实际读取量超过 limit 即失败（不信任头部声明的大小）
+fn read_entry_limited(reader: impl Read, limit: u64) -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::new();
+    reader
+        .take(limit + 1)
+        .read_to_end(&mut buffer)
+        .map_err(|e| format!("读取 ZIP 内容失败: {}", e))?;
+    if buffer.len() as u64 > limit {
+        return Err(format!("文件解压后超过大小上限 {} 字节", limit));
+    }
+    Ok(buffer)
 }
 
-/// 从 ZIP 文件导入资源
-pub fn import_resources(zip_path: &str, data_dir: &str) -> Result<ImportResult, String> {
+/// 校验资源包内的 manifest.json：必须能解析，且 id 是合法 ID
+fn validate_import_manifest(content: &[u8]) -> Result<GenericManifest, String> {
+    let manifest: GenericManifest = serde_json::from_slice(content)
+        .map_err(|e| format!("manifest.json 解析失败: {}", e))?;
+    crate::id_service::validate_id(&manifest.id)
+        .map_err(|e| format!("manifest.json 中的 id 无效: {}", e))?;
+    Ok(manifest)
+}
+
+/// 打开待导入的 ZIP 并按顶层目录分组，同时完成路径、类型与声明大小的安全检查
+///
+/// 含不安全路径（`..`、绝对路径）或符号链接的顶层目录整体拒绝，记入返回的失败列表；
+/// 条目数或总解压大小超限时直接返回错误。
+fn open_import_archive(zip_path: &str) -> Result<ImportArchive, String> {
     let file = fs::File::open(zip_path).map_err(|e| format!("打开 ZIP 文件失败: {}", e))?;
     let mut archive =
         zip::ZipArchive::new(file).map_err(|e| format!("解析 ZIP 文件失败: {}", e))?;
 
-    let mut imported = Vec::new();
-    let mut skipped = Vec::new();
+    if archive.len() > MAX_IMPORT_ENTRIES {
+        return Err(format!(
+            "ZIP 条目过多（{} 个，上限 {} 个）",
+            archive.len(),
+            MAX_IMPORT_ENTRIES
+        ));
+    }
 
-    // 收集所有顶层目录名
-    let mut top_dirs: std::collections::HashSet<String> = std::collections::HashSet::new();
+    let mut groups: BTreeMap<String, Vec<ZipEntryPlan>> = BTreeMap::new();
+    let mut rejected: BTreeMap<String, String> = BTreeMap::new();
+    let mut declared_total = 0u64;
+    let mut package_index = None;
     for i in 0..archive.len() {
-        if let Ok(file) = archive.by_index(i) {
-            if let Some(first_component) = file.name().split('/').next() {
-                if !first_component.is_empty() {
-                    top_dirs.insert(first_component.to_string());
+        let entry = archive
+            .by_index(i)
+            .map_err(|e| format!("读取 ZIP 条目失败: {}", e))?;
+        let name = entry.name().to_string();
+
+        let Some(rel_path) = safe_entry_path(&name).filter(|_| entry.enclosed_name().is_some())
+        else {
+            let top = name
+                .split(['/', '\\'])
+                .find(|p| !p.is_empty() && *p != "..")
+                .unwrap_or(&name)
+                .to_string();
+            rejected
+                .entry(top)
+                .or_insert_with(|| format!("包含不安全的路径: {}", name));
+            continue;
+        };
+        let top = rel_path
+            .components()
+            .next()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        if entry.unix_mode().is_some_and(|m| m & S_IFMT == S_IFLNK) {
+            rejected
+                .entry(top)
+                .or_insert_with(|| format!("包含符号链接: {}", name));
+            continue;
+        }
+
+        if entry.size() > MAX_IMPORT_ENTRY_SIZE {
+            rejected.entry(top).or_insert_with(|| {
+                format!("文件 {} 超过大小上限 {} 字节", name, MAX_IMPORT_ENTRY_SIZE)
+            });
+            continue;
+        }
+        declared_total = declared_total.saturating_add(entry.size());
+        if declared_total > MAX_IMPORT_TOTAL_SIZE {
+            return Err(format!(
+                "ZIP 解压后总大小超过上限 {} 字节",
+                MAX_IMPORT_TOTAL_SIZE
+            ));
+        }
+
+        // 根目录下的散落文件不属于任何资源（资源包描述文件除外）
+        if rel_path.components().count() == 1 && !entry.is_dir() {
+            if rel_path == Path::new(PACKAGE_MANIFEST_FILE) {
+                package_index = Some(i);
+            }
+            continue;
+        }
+        groups.entry(top).or_default().push(ZipEntryPlan {
+            index: i,
+            rel_path,
+            is_dir: entry.is_dir(),
+        });
+    }
+
+    // 资源包：逐个文件比对 SHA-256，损坏、缺失或多出文件的资源整体拒绝
+    let package = match package_index {
+        Some(index) => {
+            let entry = archive
+                .by_index(index)
+                .map_err(|e| format!("读取 ZIP 条目失败: {}", e))?;
+            let manifest = PackageManifest::parse(&read_entry_limited(entry, MAX_IMPORT_ENTRY_SIZE)?)?;
+            let mut actual = BTreeMap::new();
+            let mut hashed_total = 0u64;
+            for plan in groups.values().flatten().filter(|p| !p.is_dir) {
+                let entry = archive
+                    .by_index(plan.index)
+                    .map_err(|e| format!("读取 ZIP 条目失败: {}", e))?;
+                let (hash, size) =
+                    crate::package::sha256_reader(entry, MAX_IMPORT_ENTRY_SIZE)?;
+                hashed_total += size;
+                if hashed_total > MAX_IMPORT_TOTAL_SIZE {
+                    return Err(format!(
+                        "ZIP 解压后总大小超过上限 {} 字节",
+                        MAX_IMPORT_TOTAL_SIZE
+                    ));
+                }
+                actual.insert(zip_entry_name(&plan.rel_path), (hash, size));
+            }
+            for problem in manifest.verify(&actual) {
+                let top = problem.path.split('/').next().unwrap_or_default().to_string();
+                rejected.entry(top).or_insert_with(|| {
+                    format!("完整性校验失败 {}: {}", problem.path, problem.error)
+                });
+            }
+            Some(manifest)
+        }
+        None => None,
+    };
+
+    let mut failed = Vec::new();
+    for (top, reason) in rejected {
+        groups.remove(&top);
+        failed.push(ImportFailure {
+            id: top,
+            error: reason,
+        });
+    }
+
+    Ok(ImportArchive {
+        archive,
+        groups,
+        failed,
+        package,
+        read_total: 0,
+    })
+}
+
+/// 通过安全检查的压缩包
+pub(crate) struct ImportArchive {
+    archive: zip::ZipArchive<fs::File>,
+    groups: BTreeMap<String, Vec<ZipEntryPlan>>,
+    failed: Vec<ImportFailure>,
+    /// 资源包描述（普通 ZIP 为 None）
+    package: Option<PackageManifest>,
+    /// 已实际解压的字节数（用于总大小上限）
+    read_total: u64,
+}
+
+impl ImportArchive {
+    /// 读取一个条目的内容，同时累计总解压大小
+    fn read(&mut self, index: usize) -> Result<Vec<u8>, String> {
+        let entry = self
+            .archive
+            .by_index(index)
+            .map_err(|e| format!("读取 ZIP 条目失败: {}", e))?;
+        let buffer = read_entry_limited(entry, MAX_IMPORT_ENTRY_SIZE)?;
+        self.read_total += buffer.len() as u64;
+        if self.read_total > MAX_IMPORT_TOTAL_SIZE {
+            return Err(format!(
+                "ZIP 解压后总大小超过上限 {} 字节",
+                MAX_IMPORT_TOTAL_SIZE
+            ));
+        }
+        Ok(buffer)
+    }
+
+    /// 读取资源目录下的直接文件（`{top}/{filename}`），返回 文件名 → 内容
+    fn read_top_files(&mut self, top: &str) -> Result<BTreeMap<String, Vec<u8>>, String> {
+        let files: Vec<(usize, String)> = self
+            .groups
+            .get(top)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter(|e| !e.is_dir && e.rel_path.components().count() == 2)
+                    .map(|e| {
+                        let name = e.rel_path.file_name().unwrap_or_default();
+                        (e.index, name.to_string_lossy().to_string())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut out = BTreeMap::new();
+        for (index, name) in files {
+            out.insert(name, self.read(index)?);
+        }
+        Ok(out)
+    }
+
+    /// 读取 JSON 文件模式导出包（`_json_templates/{category}.json`）中的全部分类
+    fn read_json_package(&mut self) -> Result<Vec<CategoryJsonFile>, String> {
+        let mut categories = Vec::new();
+        for (name, content) in self.read_top_files(JSON_PACKAGE_DIR)? {
+            if !name.ends_with(".json") {
+                continue;
+            }
+            match serde_json::from_slice::<CategoryJsonFile>(&content) {
+                Ok(cat_file) => categories.push(cat_file),
+                Err(e) => self.failed.push(ImportFailure {
+                    id: format!("{}/{}", JSON_PACKAGE_DIR, name),
+                    error: format!("解析分类 JSON 失败: {}", e),
+                }),
+            }
+        }
+        Ok(categories)
+    }
+}
+
+/// 待导入的单个资源
+pub(crate) struct ImportCandidate {
+    /// 来源描述（压缩包内顶层目录名，或 `_json_templates/{category}.json#{id}`）
+    pub(crate) source: String,
+    pub(crate) manifest: GenericManifest,
+    pub(crate) kind: CandidateKind,
+}
+
+pub(crate) enum CandidateKind {
+    /// 目录模式资源包中的一个顶层目录
+    Directory(String),
+    /// JSON 文件模式导出包中的一个模板
+    Template(Box<JsonTemplateEntry>),
+    /// JSON / JSONL 清单中的一条记录：文件名 → 内容（含 manifest.json）
+    Files(BTreeMap<String, Vec<u8>>),
+}
+
+/// 冲突判定：返回（操作, 原因），Err 表示该项无法按所选策略处理
+///
+/// `replaceable` 表示冲突的现有资源与导入目标处于同一数据模式，可被覆盖；
+/// `newer` 返回导入版本是否更新，None 表示无法比较。
+fn decide_action(
+    strategy: ConflictStrategy,
+    duplicate_in_package: bool,
+    conflict: bool,
+    replaceable: bool,
+    newer: impl FnOnce() -> Option<bool>,
+) -> Result<(ImportAction, &'static str), String> {
+    if duplicate_in_package {
+        return Ok(match strategy {
+            ConflictStrategy::KeepBoth => (ImportAction::KeepBoth, "压缩包内存在重复的 ID"),
+            _ => (ImportAction::Skip, "压缩包内存在重复的 ID"),
+        });
+    }
+    if !conflict {
+        return Ok((ImportAction::Create, ""));
+    }
+    match strategy {
+        ConflictStrategy::Skip => Ok((ImportAction::Skip, "ID 已存在")),
+        ConflictStrategy::KeepBoth => Ok((ImportAction::KeepBoth, "ID 已存在，以新 ID 保留两者")),
+        _ if !replaceable => {
+            Err("ID 与另一种数据模式的资源冲突，只能跳过或保留两者".to_string())
+        }
+        ConflictStrategy::Overwrite => Ok((ImportAction::Overwrite, "ID 已存在，覆盖现有资源")),
+        ConflictStrategy::MergeNewer => Ok(match newer() {
+            Some(true) => (ImportAction::Overwrite, "导入的版本更新，覆盖现有资源"),
+            Some(false) => (ImportAction::Skip, "现有资源不比导入的版本旧"),
+            None => (ImportAction::Skip, "缺少 updatedAt，无法比较新旧，已跳过"),
+        }),
+    }
+}
+
+/// 从 ZIP 文件导入资源（目录模式）
+///
+/// 每个顶层目录视为一个资源，必须包含合法的 manifest.json。含不安全路径（`..`、绝对路径）或
+/// 符号链接的资源整体拒绝；条目数、单文件与总解压大小超限时整个导入中止。资源先解压到
+/// `_import_tmp/` 暂存目录，全部写入成功后再移动到目标位置。
+///
+/// 资源按 manifest 的 majorCategory 放入 `{category}/{id}`，_meta.json 中缺失的分类会自动创建；
+/// ID 与仓库内任意资源（含 JSON 文件模式模板）冲突时按 options 中的策略处理。
+/// `dry_run` 为 true 时只返回计划的操作（actions），不修改任何文件。
+///
+/// 也可导入 JSON 文件模式的导出包：每个模板转换为 manifest.json + 内容文件
+/// （文件名由 `options.content_file` 指定，默认 content.md）。
+pub fn import_resources(
+    zip_path: &str,
+    data_dir: &str,
+    options: &ImportOptions,
+) -> Result<ImportResult, String> {
+    let mut package = open_import_archive(zip_path)?;
+
+    // 第二遍：收集待导入的资源（目录资源读取 manifest，JSON 导出包中的模板转换为 manifest）
+    let mut candidates = Vec::new();
+    let tops: Vec<String> = package.groups.keys().cloned().collect();
+    for top in tops {
+        if top == JSON_PACKAGE_DIR {
+            for cat_file in package.read_json_package()? {
+                for tmpl in cat_file.templates {
+                    let value = template_to_manifest(&tmpl, &cat_file.key);
+                    let source = format!("{}/{}.json#{}", JSON_PACKAGE_DIR, cat_file.key, tmpl.id);
+                    match serde_json::from_value::<GenericManifest>(value)
+                        .map_err(|e| e.to_string())
+                        .and_then(|m| crate::id_service::validate_id(&m.id).map(|_| m))
+                    {
+                        Ok(manifest) => candidates.push(ImportCandidate {
+                            source,
+                            manifest,
+                            kind: CandidateKind::Template(Box::new(tmpl)),
+                        }),
+                        Err(e) => package.failed.push(ImportFailure { id: source, error: e }),
+                    }
                 }
             }
+            continue;
         }
+
+        let manifest_rel = Path::new(&top).join("manifest.json");
+        let manifest_index = package.groups[&top]
+            .iter()
+            .find(|e| e.rel_path == manifest_rel)
+            .map(|e| e.index);
+        let Some(manifest_index) = manifest_index else {
+            package.failed.push(ImportFailure {
+                id: top,
+                error: "缺少 manifest.json".to_string(),
+            });
+            continue;
+        };
+        let manifest_bytes = package.read(manifest_index)?;
+        match validate_import_manifest(&manifest_bytes) {
+            Ok(manifest) => candidates.push(ImportCandidate {
+                source: top.clone(),
+                manifest,
+                kind: CandidateKind::Directory(top),
+            }),
+            Err(e) => package.failed.push(ImportFailure { id: top, error: e }),
+        }
+    }
+
+    let failed = std::mem::take(&mut package.failed);
+    apply_resource_import(data_dir, candidates, failed, options, Some(&mut package))
+}
+
+/// 按冲突策略规划并写入目录模式数据目录（ZIP 与 JSON / JSONL 导入共用）
+///
+/// `archive` 仅在候选项来自 ZIP 顶层目录时需要，用于按需解压条目。
+pub(crate) fn apply_resource_import(
+    data_dir: &str,
+    candidates: Vec<ImportCandidate>,
+    mut failed: Vec<ImportFailure>,
+    options: &ImportOptions,
+    mut archive: Option<&mut ImportArchive>,
+) -> Result<ImportResult, String> {
+    if let Some(name) = options.content_file.as_deref() {
+        validate_content_file_name(name)?;
     }
+    let mut imported = Vec::new();
+    let mut skipped = Vec::new();
 
-    for top_dir in &top_dirs {
-        // 检查目标目录是否已存在
-        // 导入时放入 data_dir 的根目录下（用户后续可移动分类）
-        let target_dir = Path::new(data_dir).join("imported").join(top_dir);
-        if target_dir.exists() {
-            skipped.push(top_dir.clone());
+    // 决定目标分类与冲突处理方式（不写盘）
+    let data_path = Path::new(data_dir);
+    let existing = index_existing_resources(data_path);
+    let mut taken: HashSet<String> = existing.keys().cloned().collect();
+    taken.extend(crate::json_file_ops::collect_all_template_ids(data_dir));
+    let mut meta = crate::category_ops::read_meta(data_dir)?;
+    let mut meta_changed = false;
+    let mut created_categories = Vec::new();
+    let mut planned_ids: HashSet<String> = HashSet::new();
+    let mut actions = Vec::new();
+    let mut plans: Vec<(ImportCandidate, usize)> = Vec::new();
+
+    for candidate in candidates {
+        let manifest = &candidate.manifest;
+        // 按 manifest 的 majorCategory 放置，未指定时放入 imported 分类
+        let category = if manifest.major_category.is_empty() {
+            DEFAULT_IMPORT_CATEGORY.to_string()
+        } else {
+            manifest.major_category.clone()
+        };
+        if let Err(e) = crate::category_ops::validate_category_key(&category) {
+            failed.push(ImportFailure {
+                id: manifest.id.clone(),
+                error: e,
+            });
             continue;
         }
 
-        fs::create_dir_all(&target_dir).map_err(|e| format!("创建目录失败: {}", e))?;
-        imported.push(top_dir.clone());
+        let id = manifest.id.clone();
+        let strategy = options.strategy_for(&id, &candidate.source);
+        let existing_dir = existing.get(&id);
+        let existing_updated_at = existing_dir.and_then(|d| read_updated_at(d));
+        let decision = decide_action(
+            strategy,
+            planned_ids.contains(&id),
+            taken.contains(&id),
+            existing_dir.is_some(),
+            || Some(is_newer(&manifest.updated_at, existing_updated_at.as_deref())),
+        );
+        let (action, reason) = match decision {
+            Ok(d) => d,
+            Err(e) => {
+                failed.push(ImportFailure { id, error: e });
+                continue;
+            }
+        };
+
+        let target_id = if action == ImportAction::KeepBoth {
+            let occupied: HashSet<String> = taken.union(&planned_ids).cloned().collect();
+            crate::id_service::make_unique(&id, &occupied)
+        } else {
+            id.clone()
+        };
+        let target_dir = data_path.join(&category).join(&target_id);
+        if matches!(action, ImportAction::Create | ImportAction::KeepBoth) && target_dir.exists() {
+            failed.push(ImportFailure {
+                id,
+                error: format!("目标目录已存在: {}", target_dir.display()),
+            });
+            continue;
+        }
+        if action != ImportAction::Skip {
+            planned_ids.insert(target_id.clone());
+            meta_changed |= ensure_category(
+                &mut meta,
+                &category,
+                &manifest.sub_category,
+                &mut created_categories,
+            );
+        }
+
+        actions.push(ImportPlanItem {
+            source: candidate.source.clone(),
+            id,
+            target_id,
+            category,
+            sub_category: manifest.sub_category.clone(),
+            action,
+            reason: reason.to_string(),
+            existing_path: existing_dir.map(|d| d.to_string_lossy().to_string()),
+            existing_updated_at,
+            incoming_updated_at: manifest.updated_at.clone(),
+            target_path: target_dir.to_string_lossy().to_string(),
+        });
+        plans.push((candidate, actions.len() - 1));
     }
 
-    // 解压文件
-    for i in 0..archive.len() {
-        let mut file = archive
-            .by_index(i)
-            .map_err(|e| format!("读取 ZIP 条目失败: {}", e))?;
+    if options.dry_run {
+        for item in &actions {
+            if item.action == ImportAction::Skip {
+                skipped.push(item.id.clone());
+            } else {
+                imported.push(item.target_id.clone());
+            }
+        }
+        return Ok(ImportResult {
+            imported,
+            skipped,
+            failed,
+            actions,
+            created_categories,
+            dry_run: true,
+        });
+    }
+
+    // 补全分类后按计划写入暂存目录、移动
+    if meta_changed {
+        crate::category_ops::write_meta_atomic(data_path, &meta)?;
+    }
+
+    let staging_root = data_path
+        .join("_import_tmp")
+        .join(uuid::Uuid::new_v4().to_string());
+    let content_file = options.content_file.as_deref().unwrap_or("content.md");
+
+    let result = (|| -> Result<(), String> {
+        for (candidate, action_index) in &plans {
+            let item = &actions[*action_index];
+            if item.action == ImportAction::Skip {
+                skipped.push(item.id.clone());
+                continue;
+            }
+
+            // 写入暂存目录
+            let staged_dir = match &candidate.kind {
+                CandidateKind::Directory(top) => {
+                    let package = archive
+                        .as_deref_mut()
+                        .ok_or_else(|| "缺少资源包".to_string())?;
+                    let entries: Vec<(usize, PathBuf, bool)> = package.groups[top]
+                        .iter()
+                        .map(|e| (e.index, e.rel_path.clone(), e.is_dir))
+                        .collect();
+                    for (index, rel_path, is_dir) in entries {
+                        let target_path = staging_root.join("zip").join(&rel_path);
+                        if is_dir {
+                            fs::create_dir_all(&target_path)
+                                .map_err(|e| format!("创建目录失败: {}", e))?;
+                            continue;
+                        }
+                        if let Some(parent) = target_path.parent() {
+                            fs::create_dir_all(parent)
+                                .map_err(|e| format!("创建目录失败: {}", e))?;
+                        }
+                        let buffer = package.read(index)?;
+                        fs::write(&target_path, buffer)
+                            .map_err(|e| format!("写入文件失败: {}", e))?;
+                    }
+                    staging_root.join("zip").join(top)
+                }
+                CandidateKind::Template(tmpl) => {
+                    let dir = staging_root.join("json").join(&item.target_id);
+                    fs::create_dir_all(&dir).map_err(|e| format!("创建目录失败: {}", e))?;
+                    let manifest = template_to_manifest(tmpl, &item.category);
+                    let manifest_content = serde_json::to_string_pretty(&manifest)
+                        .map_err(|e| format!("序列化 manifest 失败: {}", e))?;
+                    fs::write(dir.join("manifest.json"), manifest_content)
+                        .map_err(|e| format!("写入 manifest 失败: {}", e))?;
+                    fs::write(dir.join(content_file), template_content_file(tmpl, content_file))
+                        .map_err(|e| format!("写入 {} 失败: {}", content_file, e))?;
+                    dir
+                }
+                CandidateKind::Files(files) => {
+                    let dir = staging_root.join("bundle").join(&item.target_id);
+                    fs::create_dir_all(&dir).map_err(|e| format!("创建目录失败: {}", e))?;
+                    for (name, content) in files {
+                        fs::write(dir.join(name), content)
+                            .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+                    }
+                    dir
+                }
+            };
+            update_staged_manifest(&staged_dir, &item.target_id, &item.category)?;
+
+            // 覆盖时先把现有资源移入回收站，移动失败再还原
+            let target_dir = PathBuf::from(&item.target_path);
+            let trashed = match (&item.action, &item.existing_path) {
+                (ImportAction::Overwrite, Some(existing_path)) => Some((
+                    PathBuf::from(existing_path),
+                    crate::fs_utils::move_to_trash(data_path, Path::new(existing_path))?,
+                )),
+                _ => None,
+            };
+            if let Some(parent) = target_dir.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+            }
+            if let Err(e) = fs::rename(&staged_dir, &target_dir) {
+                if let Some((original, trash_path)) = trashed {
+                    let _ = fs::rename(trash_path, original);
+                }
+                return Err(format!("移动导入资源失败: {}", e));
+            }
+            imported.push(item.target_id.clone());
+        }
+        Ok(())
+    })();
+
+    let _ = fs::remove_dir_all(&staging_root);
+    if let Ok(mut rest) = fs::read_dir(data_path.join("_import_tmp")) {
+        if rest.next().is_none() {
+            let _ = fs::remove_dir(data_path.join("_import_tmp"));
+        }
+    }
+    result?;
+
+    Ok(ImportResult {
+        imported,
+        skipped,
+        failed,
+        actions,
+        created_categories,
+        dry_run: false,
+    })
+}
+
+// ============================================================
+// JSON 文件模式导入导出
+// ============================================================
+
+/// JSON 文件模式导出包中存放分类文件的目录（以 `_` 开头，不会与资源 ID 冲突）
+const JSON_PACKAGE_DIR: &str = "_json_templates";
+
+/// 导出 JSON 文件模式的模板为 ZIP 文件
+///
+/// template_paths 为 `category_key::template_id` 形式；按分类打包为
+/// `_json_templates/{category}.json`，保留分类的名称、图标与排序，只包含选中的模板；
+/// 根目录同样附带 aidocpack.json。
+pub fn export_json_templates(
+    data_dir: &str,
+    template_paths: &[String],
+    output_path: &str,
+    resource_type: Option<&str>,
+) -> Result<String, String> {
+    let selected = group_template_paths(template_paths)?;
+
+    let file =
+        fs::File::create(output_path).map_err(|e| format!("创建 ZIP 文件失败: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let mut package = PackageManifest::new(resource_type.unwrap_or(""), "json-file");
+
+    for (cat_key, ids) in &selected {
+        crate::category_ops::validate_category_key(cat_key)?;
+        let json_path = Path::new(data_dir).join(format!("{}.json", cat_key));
+        let mut cat_file = crate::json_file_ops::read_category_file(&json_path)?;
+        cat_file.templates.retain(|t| ids.contains(&t.id));
+        if cat_file.templates.is_empty() {
+            continue;
+        }
+        let content = serde_json::to_string_pretty(&cat_file)
+            .map_err(|e| format!("序列化失败: {}", e))?;
+        let name = format!("{}/{}.json", JSON_PACKAGE_DIR, cat_key);
+        zip.start_file(&name, options)
+            .map_err(|e| format!("创建 ZIP 条目失败: {}", e))?;
+        zip.write_all(content.as_bytes())
+            .map_err(|e| format!("写入 ZIP 失败: {}", e))?;
+        package.add_file(&name, content.as_bytes());
+        package.resources.extend(cat_file.templates.iter().map(|t| PackageResource {
+            id: t.id.clone(),
+            name: t.name.clone(),
+            version: String::new(),
+            major_category: cat_file.key.clone(),
+            path: name.clone(),
+        }));
+    }
+
+    write_package_manifest(&mut zip, &package, options)?;
+    zip.finish()
+        .map_err(|e| format!("完成 ZIP 写入失败: {}", e))?;
+
+    Ok(output_path.to_string())
+}
+
+/// 按分类归并 `category_key::template_id` 形式的模板路径
+pub(crate) fn group_template_paths(
+    template_paths: &[String],
+) -> Result<BTreeMap<String, Vec<String>>, String> {
+    let mut selected: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for path in template_paths {
+        let (cat_key, tmpl_id) = path
+            .split_once("::")
+            .ok_or_else(|| format!("无效的模板路径: {}", path))?;
+        selected
+            .entry(cat_key.to_string())
+            .or_default()
+            .push(tmpl_id.to_string());
+    }
+    Ok(selected)
+}
 
-        let name = file.name().to_string();
-        let first_component = name.split('/').next().unwrap_or("");
+/// 从 ZIP 文件导入模板（JSON 文件模式）
+///
+/// 支持两种包：JSON 文件模式导出包（`_json_templates/*.json`），以及目录模式资源包——
+/// 后者读取 manifest.json 与内容文件（content.json 或 content.md），内联为模板条目。
+/// 模板写入 `{category}.json`，缺失的分类文件按包内分类信息创建；冲突策略与 dry-run 同目录模式。
+/// JSON 文件模式的模板没有 updatedAt，`mergeNewer` 策略遇到冲突时一律跳过。
+pub fn import_json_templates(
+    zip_path: &str,
+    data_dir: &str,
+    options: &ImportOptions,
+) -> Result<ImportResult, String> {
+    let mut package = open_import_archive(zip_path)?;
 
-        if !imported.contains(&first_component.to_string()) {
+    // 收集待导入的模板：(来源, 分类信息, 模板)
+    let mut candidates: Vec<(String, CategoryJsonFile, JsonTemplateEntry)> = Vec::new();
+    let tops: Vec<String> = package.groups.keys().cloned().collect();
+    for top in tops {
+        if top == JSON_PACKAGE_DIR {
+            for mut cat_file in package.read_json_package()? {
+                for tmpl in std::mem::take(&mut cat_file.templates) {
+                    let source = format!("{}/{}.json#{}", JSON_PACKAGE_DIR, cat_file.key, tmpl.id);
+                    candidates.push((source, cat_file.clone(), tmpl));
+                }
+            }
             continue;
         }
 
-        let target_path = Path::new(data_dir).join("imported").join(&name);
+        let files = package.read_top_files(&top)?;
+        match directory_to_template(&files) {
+            Ok((category, tmpl)) => candidates.push((top, new_category_file(&category), tmpl)),
+            Err(e) => package.failed.push(ImportFailure { id: top, error: e }),
+        }
+    }
+
+    let failed = std::mem::take(&mut package.failed);
+    apply_template_import(data_dir, candidates, failed, options)
+}
+
+/// 按冲突策略把模板写入分类文件（ZIP 与 JSON / JSONL 导入共用）
+///
+/// candidates 为（来源, 分类信息, 模板）；分类文件不存在时按分类信息创建。
+pub(crate) fn apply_template_import(
+    data_dir: &str,
+    candidates: Vec<(String, CategoryJsonFile, JsonTemplateEntry)>,
+    mut failed: Vec<ImportFailure>,
+    options: &ImportOptions,
+) -> Result<ImportResult, String> {
+    // 现有模板：ID → 所在分类
+    let data_path = Path::new(data_dir);
+    let mut files: BTreeMap<String, CategoryJsonFile> = BTreeMap::new();
+    if let Ok(entries) = fs::read_dir(data_path) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            if let Ok(cat_file) = crate::json_file_ops::read_category_file(&path) {
+                files.insert(cat_file.key.clone(), cat_file);
+            }
+        }
+    }
+    let existing: HashMap<String, String> = files
+        .values()
+        .flat_map(|f| f.templates.iter().map(|t| (t.id.clone(), f.key.clone())))
+        .collect();
+    let mut taken: HashSet<String> = existing.keys().cloned().collect();
+    taken.extend(crate::resource_ops::collect_all_resource_ids(data_path));
+
+    let mut imported = Vec::new();
+    let mut skipped = Vec::new();
+    let mut created_categories = Vec::new();
+    let mut planned_ids: HashSet<String> = HashSet::new();
+    let mut actions = Vec::new();
+    let mut changed: HashSet<String> = HashSet::new();
+
+    for (source, cat_info, mut tmpl) in candidates {
+        let id = tmpl.id.clone();
+        let category = if cat_info.key.is_empty() {
+            DEFAULT_IMPORT_CATEGORY.to_string()
+        } else {
+            cat_info.key.clone()
+        };
+        if let Err(e) = crate::id_service::validate_id(&id)
+            .and_then(|_| crate::category_ops::validate_category_key(&category))
+        {
+            failed.push(ImportFailure { id: source, error: e });
+            continue;
+        }
 
-        if file.is_dir() {
-            fs::create_dir_all(&target_path).ok();
+        let strategy = options.strategy_for(&id, &source);
+        let existing_category = existing.get(&id).cloned();
+        let decision = decide_action(
+            strategy,
+            planned_ids.contains(&id),
+            taken.contains(&id),
+            existing_category.is_some(),
+            || None,
+        );
+        let (action, reason) = match decision {
+            Ok(d) => d,
+            Err(e) => {
+                failed.push(ImportFailure { id, error: e });
+                continue;
+            }
+        };
+        let target_id = if action == ImportAction::KeepBoth {
+            let occupied: HashSet<String> = taken.union(&planned_ids).cloned().collect();
+            crate::id_service::make_unique(&id, &occupied)
         } else {
-            if let Some(parent) = target_path.parent() {
-                fs::create_dir_all(parent).ok();
+            id.clone()
+        };
+
+        actions.push(ImportPlanItem {
+            source,
+            id: id.clone(),
+            target_id: target_id.clone(),
+            category: category.clone(),
+            sub_category: String::new(),
+            action,
+            reason: reason.to_string(),
+            existing_path: existing_category.as_ref().map(|c| format!("{}::{}", c, id)),
+            existing_updated_at: None,
+            incoming_updated_at: String::new(),
+            target_path: format!("{}::{}", category, target_id),
+        });
+        if action == ImportAction::Skip {
+            skipped.push(id);
+            continue;
+        }
+        planned_ids.insert(target_id.clone());
+        imported.push(target_id.clone());
+
+        // 在内存中修改分类文件，最后统一写入
+        let mut keep_order = None;
+        if action == ImportAction::Overwrite {
+            if let Some(old_file) = existing_category.as_ref().and_then(|c| files.get_mut(c)) {
+                keep_order = old_file.templates.iter().find(|t| t.id == id).map(|t| t.order);
+                old_file.templates.retain(|t| t.id != id);
+                changed.insert(old_file.key.clone());
+            }
+        }
+        let target_file = files.entry(category.clone()).or_insert_with(|| {
+            created_categories.push(category.clone());
+            CategoryJsonFile {
+                key: category.clone(),
+                templates: Vec::new(),
+                ..cat_info
+            }
+        });
+        tmpl.id = target_id;
+        tmpl.order = match keep_order {
+            Some(order) if existing_category.as_deref() == Some(category.as_str()) => order,
+            _ => target_file.templates.iter().map(|t| t.order).max().unwrap_or(-1) + 1,
+        };
+        target_file.templates.push(tmpl);
+        target_file.templates.sort_by_key(|t| t.order);
+        changed.insert(category);
+    }
+
+    if !options.dry_run {
+        for key in &changed {
+            if let Some(cat_file) = files.get(key) {
+                let json_path = data_path.join(format!("{}.json", key));
+                crate::json_file_ops::write_category_file_atomic(&json_path, cat_file)?;
             }
-            let mut buffer = Vec::new();
-            file.read_to_end(&mut buffer)
-                .map_err(|e| format!("读取 ZIP 内容失败: {}", e))?;
-            fs::write(&target_path, buffer)
-                .map_err(|e| format!("写入文件失败: {}", e))?;
         }
     }
 
     Ok(ImportResult {
         imported,
         skipped,
-        failed: Vec::new(),
+        failed,
+        actions,
+        created_categories,
+        dry_run: options.dry_run,
+    })
+}
+
+/// JSON 模板 → 目录模式 manifest（字段对应关系与前端 JSON 模式适配保持一致）
+pub(crate) fn template_to_manifest(tmpl: &JsonTemplateEntry, category: &str) -> serde_json::Value {
+    let mut manifest = serde_json::json!({
+        "id": tmpl.id,
+        "name": tmpl.name,
+        "description": tmpl.description,
+        "majorCategory": category,
+        "subCategory": "",
+        "tags": tmpl.tags,
+        "order": tmpl.order,
+        "enabled": true,
+        "source": "custom",
+        "variables": tmpl.variables,
+        "enabledPlugins": tmpl.enabled_plugins,
+        "includeContent": tmpl.include_content,
+        "includeAiContent": tmpl.include_ai_content,
+    });
+    if !tmpl.aliases.is_empty() {
+        if let Some(obj) = manifest.as_object_mut() {
+            obj.insert("aliases".to_string(), serde_json::json!(tmpl.aliases));
+        }
+    }
+    manifest
+}
+
+/// JSON 模板 → 目录模式内容文件：content.json 时组装文档模板结构，否则直接写正文
+pub(crate) fn template_content_file(tmpl: &JsonTemplateEntry, content_file: &str) -> String {
+    if content_file.ends_with(".json") {
+        let value = serde_json::json!({
+            "authorNotes": tmpl.author_notes,
+            "content": tmpl.content,
+            "aiGeneratedContent": tmpl.ai_generated_content,
+            "pluginData": tmpl.plugin_data,
+        });
+        serde_json::to_string_pretty(&value).unwrap_or_default()
+    } else {
+        tmpl.content.clone()
+    }
+}
+
+/// 目录模式资源（manifest.json + 内容文件）→ JSON 模板，返回（分类, 模板）
+///
+/// 内容优先取 content.json（文档模板结构），其次 content.md，再次任意一个 .md 文件。
+fn directory_to_template(
+    files: &BTreeMap<String, Vec<u8>>,
+) -> Result<(String, JsonTemplateEntry), String> {
+    let manifest_bytes = files
+        .get("manifest.json")
+        .ok_or_else(|| "缺少 manifest.json".to_string())?;
+    let manifest = validate_import_manifest(manifest_bytes)?;
+    let raw: serde_json::Value = serde_json::from_slice(manifest_bytes)
+        .map_err(|e| format!("manifest.json 解析失败: {}", e))?;
+    let str_list = |key: &str| -> Vec<String> {
+        raw.get(key)
+            .and_then(|v| v.as_array())
+            .map(|a| a.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+            .unwrap_or_default()
+    };
+    let flag = |key: &str| raw.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+
+    let mut tmpl = JsonTemplateEntry {
+        id: manifest.id.clone(),
+        name: manifest.name.clone(),
+        description: manifest.description.clone(),
+        content: String::new(),
+        variables: str_list("variables"),
+        order: manifest.order,
+        author_notes: String::new(),
+        tags: manifest.tags.clone(),
+        ai_generated_content: String::new(),
+        enabled_plugins: str_list("enabledPlugins"),
+        plugin_data: raw.get("pluginData").filter(|v| !v.is_null()).cloned(),
+        include_content: flag("includeContent"),
+        include_ai_content: flag("includeAiContent"),
+        aliases: str_list("aliases"),
+    };
+
+    let text = |bytes: &Vec<u8>| String::from_utf8_lossy(bytes).to_string();
+    if let Some(bytes) = files.get("content.json") {
+        let doc: serde_json::Value = serde_json::from_slice(bytes)
+            .map_err(|e| format!("content.json 解析失败: {}", e))?;
+        let field = |key: &str| doc.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+        tmpl.content = field("content");
+        tmpl.author_notes = field("authorNotes");
+        tmpl.ai_generated_content = field("aiGeneratedContent");
+        if let Some(plugin_data) = doc.get("pluginData").filter(|v| !v.is_null()) {
+            tmpl.plugin_data = Some(plugin_data.clone());
+        }
+    } else if let Some(bytes) = files
+        .get("content.md")
+        .or_else(|| files.iter().find(|(name, _)| name.ends_with(".md")).map(|(_, b)| b))
+    {
+        tmpl.content = text(bytes);
+    }
+
+    Ok((manifest.major_category, tmpl))
+}
+
+/// 目录模式资源导入到 JSON 文件模式时使用的分类信息（名称同分类键，排在最后）
+fn new_category_file(category: &str) -> CategoryJsonFile {
+    CategoryJsonFile {
+        key: category.to_string(),
+        name: category.to_string(),
+        icon: "📋".to_string(),
+        order: 999,
+        templates: Vec::new(),
+    }
+}
+
+// ============================================================
+// JSON / JSONL 清单导入导出
+// ============================================================
+//
+// 每条记录是资源的 manifest 对象，额外带一个 `contentFiles` 字段（文件名 → 文本内容），
+// 便于用脚本或其他工具处理。JSON 清单为格式化的数组，JSONL 每行一条记录。
+// 只内联资源目录根下的 UTF-8 文本文件，跳过的二进制文件与子目录会在结果中逐一列出。
+
+/// 记录中存放内联内容文件的字段名
+const BUNDLE_CONTENT_FIELD: &str = "contentFiles";
+
+/// 清单格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BundleFormat {
+    /// 单个 JSON 数组
+    Json,
+    /// 每行一条 JSON 记录
+    Jsonl,
+}
+
+/// JSON / JSONL 清单导出结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BundleExportResult {
+    /// 写入的清单文件路径
+    pub path: String,
+    /// 未能内联而被跳过的文件与子目录（`{资源目录名}/{文件名}`，子目录以 `/` 结尾）
+    pub skipped: Vec<String>,
+}
+
+/// 导出资源为 JSON / JSONL 清单（目录模式）
+///
+/// 清单只能容纳根目录下的文本文件，二进制文件与嵌套目录记入 `skipped`，由前端提示清单不完整。
+pub fn export_bundle(
+    resource_paths: &[String],
+    output_path: &str,
+    format: BundleFormat,
+) -> Result<BundleExportResult, String> {
+    let mut records = Vec::new();
+    let mut skipped_files = Vec::new();
+    for resource_path in resource_paths {
+        let dir = Path::new(resource_path);
+        let manifest_content = fs::read_to_string(dir.join("manifest.json"))
+            .map_err(|e| format!("读取 {} 的 manifest.json 失败: {}", resource_path, e))?;
+        let manifest: serde_json::Value = serde_json::from_str(&manifest_content)
+            .map_err(|e| format!("{} 的 manifest.json 解析失败: {}", resource_path, e))?;
+        let dir_name = dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+        let mut content_files = BTreeMap::new();
+        let entries = fs::read_dir(dir).map_err(|e| format!("读取资源目录失败: {}", e))?;
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == "manifest.json" || name.starts_with('.') || name.starts_with('_') {
+                continue;
+            }
+            let file_type = entry.file_type().ok();
+            if file_type.is_some_and(|t| t.is_dir()) {
+                skipped_files.push(format!("{}/{}/", dir_name, name));
+                continue;
+            }
+            if !file_type.is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            match fs::read_to_string(entry.path()) {
+                Ok(text) => {
+                    content_files.insert(name, text);
+                }
+                Err(_) => skipped_files.push(format!("{}/{}", dir_name, name)),
+            }
+        }
+        records.push(bundle_record(manifest, content_files));
+    }
+    let path = write_bundle(&records, output_path, format)?;
+    Ok(BundleExportResult { path, skipped: skipped_files })
+}
+
+/// 导出 JSON 文件模式的模板为 JSON / JSONL 清单
+///
+/// 模板转换为目录模式 manifest，正文、作者备注、AI 生成内容与插件数据放在 content.json 中，
+/// 与导出 ZIP 时的对应关系一致。
+pub fn export_json_templates_bundle(
+    data_dir: &str,
+    template_paths: &[String],
+    output_path: &str,
+    format: BundleFormat,
+) -> Result<String, String> {
+    let selected = group_template_paths(template_paths)?;
+    let mut records = Vec::new();
+    for (cat_key, ids) in &selected {
+        crate::category_ops::validate_category_key(cat_key)?;
+        let json_path = Path::new(data_dir).join(format!("{}.json", cat_key));
+        let cat_file = crate::json_file_ops::read_category_file(&json_path)?;
+        for tmpl in cat_file.templates.iter().filter(|t| ids.contains(&t.id)) {
+            let mut content_files = BTreeMap::new();
+            content_files.insert(
+                "content.json".to_string(),
+                template_content_file(tmpl, "content.json"),
+            );
+            records.push(bundle_record(template_to_manifest(tmpl, cat_key), content_files));
+        }
+    }
+    write_bundle(&records, output_path, format)
+}
+
+/// 从 JSON / JSONL 清单导入资源（目录模式）
+///
+/// 格式按文件内容自动识别。冲突策略、dry-run 与分类补全同 ZIP 导入；
+/// 设置了 `contentFile` 且记录中没有该文件时，从 content.json / content.md 转换生成。
+pub fn import_bundle(
+    bundle_path: &str,
+    data_dir: &str,
+    options: &ImportOptions,
+) -> Result<ImportResult, String> {
+    if let Some(name) = options.content_file.as_deref() {
+        validate_content_file_name(name)?;
+    }
+    let (records, mut failed) = read_bundle(bundle_path)?;
+    let mut candidates = Vec::new();
+    for (source, mut files) in records {
+        let converted = (|| -> Result<GenericManifest, String> {
+            let manifest = validate_import_manifest(&files["manifest.json"])?;
+            if let Some(content_file) = options.content_file.as_deref() {
+                let convertible =
+                    files.contains_key("content.json") || files.contains_key("content.md");
+                if !files.contains_key(content_file) && convertible {
+                    let (_, tmpl) = directory_to_template(&files)?;
+                    files.remove("content.json");
+                    files.remove("content.md");
+                    files.insert(
+                        content_file.to_string(),
+                        template_content_file(&tmpl, content_file).into_bytes(),
+                    );
+                }
+            }
+            Ok(manifest)
+        })();
+        match converted {
+            Ok(manifest) => candidates.push(ImportCandidate {
+                source,
+                manifest,
+                kind: CandidateKind::Files(files),
+            }),
+            Err(e) => failed.push(ImportFailure { id: source, error: e }),
+        }
+    }
+    apply_resource_import(data_dir, candidates, failed, options, None)
+}
+
+/// 从 JSON / JSONL 清单导入模板（JSON 文件模式）
+pub fn import_json_templates_bundle(
+    bundle_path: &str,
+    data_dir: &str,
+    options: &ImportOptions,
+) -> Result<ImportResult, String> {
+    let (records, mut failed) = read_bundle(bundle_path)?;
+    let mut candidates = Vec::new();
+    for (source, files) in records {
+        match directory_to_template(&files) {
+            Ok((category, tmpl)) => {
+                candidates.push((source, new_category_file(&category), tmpl))
+            }
+            Err(e) => failed.push(ImportFailure { id: source, error: e }),
+        }
+    }
+    apply_template_import(data_dir, candidates, failed, options)
+}
+
+/// 组装一条清单记录：manifest 对象 + contentFiles
+fn bundle_record(
+    mut manifest: serde_json::Value,
+    content_files: BTreeMap<String, String>,
+) -> serde_json::Value {
+    if let Some(obj) = manifest.as_object_mut() {
+        obj.insert(BUNDLE_CONTENT_FIELD.to_string(), serde_json::json!(content_files));
+    }
+    manifest
+}
+
+/// 写出清单文件，失败时删除不完整的输出
+fn write_bundle(
+    records: &[serde_json::Value],
+    output_path: &str,
+    format: BundleFormat,
+) -> Result<String, String> {
+    let content = match format {
+        BundleFormat::Json => serde_json::to_string_pretty(records)
+            .map_err(|e| format!("序列化失败: {}", e))?,
+        BundleFormat::Jsonl => {
+            let mut lines = String::new();
+            for record in records {
+                let line =
+                    serde_json::to_string(record).map_err(|e| format!("序列化失败: {}", e))?;
+                lines.push_str(&line);
+                lines.push('\n');
+            }
+            lines
+        }
+    };
+    if let Err(e) = fs::write(output_path, content) {
+        let _ = fs::remove_file(output_path);
+        return Err(format!("写入清单文件失败: {}", e));
+    }
+    Ok(output_path.to_string())
+}
+
+/// 清单中的一条记录：（来源, 文件名 → 内容）
+type BundleRecord = (String, BTreeMap<String, Vec<u8>>);
+
+/// 读取清单文件，返回记录列表与逐条解析失败的记录
+///
+/// 首个非空白字符为 `[` 时按 JSON 数组解析，否则按 JSONL 逐行解析；
+/// 来源记为 `#序号`（JSONL 为行号）。
+fn read_bundle(bundle_path: &str) -> Result<(Vec<BundleRecord>, Vec<ImportFailure>), String> {
+    let size = fs::metadata(bundle_path)
+        .map_err(|e| format!("读取清单文件失败: {}", e))?
+        .len();
+    if size > MAX_IMPORT_TOTAL_SIZE {
+        return Err(format!("清单文件超过大小上限 {} 字节", MAX_IMPORT_TOTAL_SIZE));
+    }
+    let content =
+        fs::read_to_string(bundle_path).map_err(|e| format!("读取清单文件失败: {}", e))?;
+
+    let mut raw: Vec<(String, Result<serde_json::Value, String>)> = Vec::new();
+    if content.trim_start().starts_with('[') {
+        let values: Vec<serde_json::Value> =
+            serde_json::from_str(&content).map_err(|e| format!("清单文件解析失败: {}", e))?;
+        for (i, value) in values.into_iter().enumerate() {
+            raw.push((format!("#{}", i + 1), Ok(value)));
+        }
+    } else {
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parsed = serde_json::from_str(line).map_err(|e| format!("解析失败: {}", e));
+            raw.push((format!("#{}", i + 1), parsed));
+        }
+    }
+    if raw.len() > MAX_IMPORT_ENTRIES {
+        return Err(format!("清单记录数超过上限 {}", MAX_IMPORT_ENTRIES));
+    }
+
+    let mut records = Vec::new();
+    let mut failed = Vec::new();
+    for (source, parsed) in raw {
+        match parsed.and_then(split_bundle_record) {
+            Ok(files) => records.push((source, files)),
+            Err(e) => failed.push(ImportFailure { id: source, error: e }),
+        }
+    }
+    Ok((records, failed))
+}
+
+/// 校验内容文件名：只能是单个普通文件名（不含路径分隔符、`..`），且不能是 manifest.json 或隐藏文件
+pub(crate) fn validate_content_file_name(name: &str) -> Result<(), String> {
+    let single = safe_entry_path(name).is_some() && !name.contains('/');
+    if !single || name == "manifest.json" || name.starts_with('.') {
+        return Err(format!("非法的内容文件名: {}", name));
+    }
+    Ok(())
+}
+
+/// 拆分一条记录为 manifest.json 与内容文件；内容文件名必须是单个安全的文件名
+fn split_bundle_record(
+    mut value: serde_json::Value,
+) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let obj = value
+        .as_object_mut()
+        .ok_or_else(|| "记录不是 JSON 对象".to_string())?;
+    let content_files = obj.remove(BUNDLE_CONTENT_FIELD);
+
+    let mut files = BTreeMap::new();
+    if let Some(content_files) = content_files.filter(|v| !v.is_null()) {
+        let content_files = content_files
+            .as_object()
+            .ok_or_else(|| format!("{} 必须是对象", BUNDLE_CONTENT_FIELD))?;
+        for (name, text) in content_files {
+            validate_content_file_name(name)?;
+            let text = text
+                .as_str()
+                .ok_or_else(|| format!("内容文件 {} 必须是字符串", name))?;
+            files.insert(name.clone(), text.as_bytes().to_vec());
+        }
+    }
+    let manifest =
+        serde_json::to_vec_pretty(&value).map_err(|e| format!("序列化 manifest 失败: {}", e))?;
+    files.insert("manifest.json".to_string(), manifest);
+    Ok(files)
+}
+
+// ============================================================
+// 资源包预览
+// ============================================================
+
+/// 资源包预览结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PackageInspection {
+    /// 资源包描述；普通 ZIP（没有 aidocpack.json）为 null，此时未做完整性校验
+    pub package: Option<PackageManifest>,
+    pub resources: Vec<PackageResource>,
+    /// 安全检查与完整性校验发现的问题（对应资源导入时会被拒绝）
+    pub problems: Vec<ImportFailure>,
+    /// 有资源包描述且全部文件校验通过
+    pub verified: bool,
+}
+
+/// 查看资源包内容（不导入）：返回包描述、资源列表与校验问题
+pub fn inspect_package(zip_path: &str) -> Result<PackageInspection, String> {
+    let mut archive = open_import_archive(zip_path)?;
+
+    let resources = match &archive.package {
+        Some(package) => package.resources.clone(),
+        None => {
+            // 普通 ZIP：从各资源的 manifest.json / JSON 导出包中读取
+            let mut resources = Vec::new();
+            let tops: Vec<String> = archive.groups.keys().cloned().collect();
+            for top in tops {
+                if top == JSON_PACKAGE_DIR {
+                    for cat_file in archive.read_json_package()? {
+                        let path = format!("{}/{}.json", JSON_PACKAGE_DIR, cat_file.key);
+                        resources.extend(cat_file.templates.iter().map(|t| PackageResource {
+                            id: t.id.clone(),
+                            name: t.name.clone(),
+                            version: String::new(),
+                            major_category: cat_file.key.clone(),
+                            path: path.clone(),
+                        }));
+                    }
+                    continue;
+                }
+                let files = archive.read_top_files(&top)?;
+                match files.get("manifest.json").map(|b| validate_import_manifest(b)) {
+                    Some(Ok(manifest)) => resources.push(PackageResource {
+                        id: manifest.id,
+                        name: manifest.name,
+                        version: manifest.version,
+                        major_category: manifest.major_category,
+                        path: top,
+                    }),
+                    Some(Err(e)) => archive.failed.push(ImportFailure { id: top, error: e }),
+                    None => archive.failed.push(ImportFailure {
+                        id: top,
+                        error: "缺少 manifest.json".to_string(),
+                    }),
+                }
+            }
+            resources
+        }
+    };
+
+    Ok(PackageInspection {
+        verified: archive.package.is_some() && archive.failed.is_empty(),
+        package: archive.package,
+        resources,
+        problems: archive.failed,
     })
 }
 
+/// 未指定 majorCategory 的资源导入到的分类
+pub(crate) const DEFAULT_IMPORT_CATEGORY: &str = "imported";
+
+/// 建立 ID → 资源目录的索引（manifest 中的 id 与目录名都计入）
+pub(crate) fn index_existing_resources(data_path: &Path) -> HashMap<String, PathBuf> {
+    let mut index = HashMap::new();
+    for dir in crate::resource_ops::collect_all_resource_dirs(data_path) {
+        let dir_name = dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if let Some(id) = fs::read_to_string(dir.join("manifest.json"))
+            .ok()
+            .and_then(|c| serde_json::from_str::<GenericManifest>(&c).ok())
+            .map(|m| m.id)
+        {
+            index.entry(id).or_insert_with(|| dir.clone());
+        }
+        index.entry(dir_name).or_insert(dir);
+    }
+    index
+}
+
+fn read_updated_at(resource_dir: &Path) -> Option<String> {
+    let content = fs::read_to_string(resource_dir.join("manifest.json")).ok()?;
+    let manifest: serde_json::Value = serde_json::from_str(&content).ok()?;
+    manifest
+        .get("updatedAt")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+}
+
+/// 比较 updatedAt：两者都是 RFC 3339 时按时间比较，否则按字符串比较；导入方缺失时视为不更新
+fn is_newer(incoming: &str, existing: Option<&str>) -> bool {
+    if incoming.is_empty() {
+        return false;
+    }
+    let Some(existing) = existing else {
+        return true;
+    };
+    match (
+        chrono::DateTime::parse_from_rfc3339(incoming),
+        chrono::DateTime::parse_from_rfc3339(existing),
+    ) {
+        (Ok(a), Ok(b)) => a > b,
+        _ => incoming > existing,
+    }
+}
+
+/// 确保 _meta.json 中存在该分类（及子分类），缺失时追加到末尾；返回是否有修改
+fn ensure_category(
+    meta: &mut MetaConfig,
+    category: &str,
+    sub_category: &str,
+    created: &mut Vec<String>,
+) -> bool {
+    let mut changed = false;
+    if !meta.categories.iter().any(|c| c.key == category) {
+        let order = meta.categories.iter().map(|c| c.order).max().unwrap_or(0) + 1;
+        meta.categories.push(CategoryDefinition {
+            key: category.to_string(),
+            name: category.to_string(),
+            icon: None,
+            order,
+            sub_categories: Vec::new(),
+        });
+        created.push(category.to_string());
+        changed = true;
+    }
+    if !sub_category.is_empty() {
+        if let Some(cat) = meta.categories.iter_mut().find(|c| c.key == category) {
+            if !cat.sub_categories.iter().any(|s| s.key == sub_category) {
+                let order = cat.sub_categories.iter().map(|s| s.order).max().unwrap_or(0) + 1;
+                cat.sub_categories.push(SubCategoryDefinition {
+                    key: sub_category.to_string(),
+                    name: sub_category.to_string(),
+                    icon: None,
+                    order,
+                });
+                created.push(format!("{}/{}", category, sub_category));
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// 改写暂存目录中 manifest 的 id 与 majorCategory（保留两者时使用新 ID）
+fn update_staged_manifest(staged_dir: &Path, id: &str, category: &str) -> Result<(), String> {
+    let manifest_path = staged_dir.join("manifest.json");
+    let content =
+        fs::read_to_string(&manifest_path).map_err(|e| format!("读取 manifest 失败: {}", e))?;
+    let mut manifest: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| format!("解析 manifest 失败: {}", e))?;
+    let obj = manifest
+        .as_object_mut()
+        .ok_or_else(|| "manifest.json 不是 JSON 对象".to_string())?;
+    if obj.get("id").and_then(|v| v.as_str()) == Some(id)
+        && obj.get("majorCategory").and_then(|v| v.as_str()) == Some(category)
+    {
+        return Ok(());
+    }
+    obj.insert("id".to_string(), serde_json::json!(id));
+    obj.insert("majorCategory".to_string(), serde_json::json!(category));
+    let content = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("序列化 manifest 失败: {}", e))?;
+    fs::write(&manifest_path, content).map_err(|e| format!("写入 manifest 失败: {}", e))
+}
+
+/// ID 冲突时的处理策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictStrategy {
+    /// 跳过导入的资源，保留现有资源
+    #[default]
+    Skip,
+    /// 现有资源移入回收站，用导入的资源替换
+    Overwrite,
+    /// 导入的资源使用新 ID，两者都保留
+    KeepBoth,
+    /// 比较 updatedAt，导入的版本更新时覆盖，否则跳过
+    MergeNewer,
+}
+
+/// 导入选项：全局策略 + 按 ID（或压缩包内顶层目录名）单独指定的策略
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct ImportOptions {
+    #[serde(default)]
+    pub strategy: ConflictStrategy,
+    #[serde(default, rename = "perItem")]
+    pub per_item: HashMap<String, ConflictStrategy>,
+    /// 只返回计划的操作，不写入任何文件
+    #[serde(default, rename = "dryRun")]
+    pub dry_run: bool,
+    /// 跨模式导入（JSON 模板 → 目录模式）时写入的内容文件名，默认 content.md
+    #[serde(default, rename = "contentFile")]
+    pub content_file: Option<String>,
+}
+
+impl ImportOptions {
+    /// 某一项实际使用的策略：先按 ID、再按来源查找单独指定的策略，否则使用全局策略
+    fn strategy_for(&self, id: &str, source: &str) -> ConflictStrategy {
+        self.per_item
+            .get(id)
+            .or_else(|| self.per_item.get(source))
+            .copied()
+            .unwrap_or(self.strategy)
+    }
+}
+
+/// 单个资源最终执行的操作
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportAction {
+    Create,
+    Skip,
+    Overwrite,
+    KeepBoth,
+}
+
+/// 导入计划中的一项（dry-run 时供前端预览）
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ImportPlanItem {
+    /// 压缩包内的顶层目录名
+    pub source: String,
+    pub id: String,
+    #[serde(rename = "targetId")]
+    pub target_id: String,
+    pub category: String,
+    #[serde(rename = "subCategory")]
+    pub sub_category: String,
+    pub action: ImportAction,
+    pub reason: String,
+    #[serde(rename = "existingPath")]
+    pub existing_path: Option<String>,
+    #[serde(rename = "existingUpdatedAt")]
+    pub existing_updated_at: Option<String>,
+    #[serde(rename = "incomingUpdatedAt")]
+    pub incoming_updated_at: String,
+    #[serde(rename = "targetPath")]
+    pub target_path: String,
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct ImportResult {
     pub imported: Vec<String>,
     pub skipped: Vec<String>,
     pub failed: Vec<ImportFailure>,
+    pub actions: Vec<ImportPlanItem>,
+    /// 导入时新建的分类（子分类记为 `major/sub`）
+    #[serde(rename = "createdCategories")]
+    pub created_categories: Vec<String>,
+    #[serde(rename = "dryRun")]
+    pub dry_run: bool,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -152,3 +1812,126 @@ pub struct ImportFailure {
     pub id: String,
     pub error: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zip::write::FileOptions;
+
+    /// 待写入测试压缩包的条目
+    enum Entry<'a> {
+        File(&'a str, &'a [u8]),
+        Symlink(&'a str, &'a str),
+    }
+
+    /// 在临时目录中写出压缩包，返回其路径
+    fn write_zip(entries: &[Entry]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("import-test-{}.zip", uuid::Uuid::new_v4()));
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        // 不压缩：超大条目的测试不必花时间在 deflate 上
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for entry in entries {
+            match entry {
+                Entry::File(name, content) => {
+                    zip.start_file(*name, options).unwrap();
+                    zip.write_all(content).unwrap();
+                }
+                Entry::Symlink(name, target) => zip.add_symlink(*name, *target, options).unwrap(),
+            }
+        }
+        zip.finish().unwrap();
+        path
+    }
+
+    /// 打开压缩包，返回 (通过检查的顶层目录, 被拒绝的顶层目录)
+    fn open(path: &Path) -> Result<(Vec<String>, Vec<String>), String> {
+        let result = open_import_archive(path.to_str().unwrap());
+        let _ = fs::remove_file(path);
+        let archive = result?;
+        let failed = archive.failed.iter().map(|f| f.id.clone()).collect();
+        Ok((archive.groups.keys().cloned().collect(), failed))
+    }
+
+    const MANIFEST: &[u8] = br#"{"id": "ok"}"#;
+
+    #[test]
+    fn safe_entry_path_rejects_escaping_names() {
+        for name in [
+            "", "/", "../x", "a/../../x", "a/./b", "/etc/passwd", "C:/Windows/x", "C:x",
+            "a\\b", "..\\x", "a/b\0c",
+        ] {
+            assert!(safe_entry_path(name).is_none(), "{:?}", name);
+        }
+        assert_eq!(safe_entry_path("a//b/"), Some(PathBuf::from("a/b")));
+        assert_eq!(safe_entry_path("res/content.md"), Some(PathBuf::from("res/content.md")));
+    }
+
+    #[test]
+    fn open_import_archive_rejects_unsafe_paths() {
+        let zip = write_zip(&[
+            Entry::File("ok/manifest.json", MANIFEST),
+            Entry::File("evil/../../outside.txt", b"x"),
+            Entry::File("/abs/manifest.json", MANIFEST),
+            Entry::File("C:/drive/manifest.json", MANIFEST),
+        ]);
+        let (groups, failed) = open(&zip).unwrap();
+        assert_eq!(groups, ["ok"]);
+        assert_eq!(failed.len(), 3, "{:?}", failed);
+        assert!(failed.contains(&"evil".to_string()));
+        assert!(failed.contains(&"abs".to_string()));
+    }
+
+    #[test]
+    fn open_import_archive_rejects_symlinks() {
+        let zip = write_zip(&[
+            Entry::File("ok/manifest.json", MANIFEST),
+            Entry::File("linked/manifest.json", MANIFEST),
+            Entry::Symlink("linked/content.md", "/etc/passwd"),
+        ]);
+        let (groups, failed) = open(&zip).unwrap();
+        assert_eq!(groups, ["ok"]);
+        assert_eq!(failed, ["linked"]);
+    }
+
+    #[test]
+    fn open_import_archive_enforces_entry_limits() {
+        let names: Vec<String> =
+            (0..=MAX_IMPORT_ENTRIES).map(|i| format!("r/{}.txt", i)).collect();
+        let entries: Vec<Entry> = names.iter().map(|n| Entry::File(n, b"")).collect();
+        let err = open(&write_zip(&entries)).unwrap_err();
+        assert!(err.contains("条目过多"), "{}", err);
+
+        let big = vec![0u8; MAX_IMPORT_ENTRY_SIZE as usize + 1];
+        let zip = write_zip(&[
+            Entry::File("ok/manifest.json", MANIFEST),
+            Entry::File("big/manifest.json", MANIFEST),
+            Entry::File("big/content.md", &big),
+        ]);
+        let (groups, failed) = open(&zip).unwrap();
+        assert_eq!(groups, ["ok"]);
+        assert_eq!(failed, ["big"]);
+    }
+
+    #[test]
+    fn read_entry_limited_checks_actual_size() {
+        assert_eq!(read_entry_limited(&b"0123456789"[..], 10).unwrap().len(), 10);
+        assert!(read_entry_limited(&b"0123456789X"[..], 10).is_err());
+    }
+
+    #[test]
+    fn open_import_archive_rejects_package_hash_mismatch() {
+        let mut package = PackageManifest::new("prompt-templates", "directory");
+        package.add_file("good/manifest.json", MANIFEST);
+        package.add_file("bad/manifest.json", MANIFEST);
+        let package = serde_json::to_vec(&package).unwrap();
+        let zip = write_zip(&[
+            Entry::File(PACKAGE_MANIFEST_FILE, &package),
+            Entry::File("good/manifest.json", MANIFEST),
+            Entry::File("bad/manifest.json", br#"{"id": "ok2"}"#),
+            Entry::File("extra/manifest.json", MANIFEST),
+        ]);
+        let (groups, failed) = open(&zip).unwrap();
+        assert_eq!(groups, ["good"]);
+        assert_eq!(failed, ["bad", "extra"]);
+    }
+}
@@ -2,7 +2,7 @@ use crate::types::{CategoryDefinition, ResourceSummary};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 // ============================================================
 // JSON 文件模式的数据结构
@@ -49,6 +49,9 @@ pub struct JsonTemplateEntry {
     pub include_content: bool,
     #[serde(default, rename = "includeAiContent")]
     pub include_ai_content: bool,
+    /// 历史 ID（重命名时保留，供下游按旧 ID 解析）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
 }
 
 /// 管理器前端需要的完整模板数据（含 manifest + content）
@@ -79,6 +82,9 @@ pub struct JsonResourceDetail {
     pub include_content: bool,
     #[serde(default, rename = "includeAiContent")]
     pub include_ai_content: bool,
+    /// 历史 ID（重命名时保留，供下游按旧 ID 解析）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
 }
 
 // ============================================================
@@ -86,13 +92,19 @@ pub struct JsonResourceDetail {
 // ============================================================
 
 /// 读取单个分类 JSON 文件
-fn read_category_file(path: &Path) -> Result<CategoryJsonFile, String> {
+pub(crate) fn read_category_file(path: &Path) -> Result<CategoryJsonFile, String> {
     let content = fs::read_to_string(path)
         .map_err(|e| format!("读取文件失败 {}: {}", path.display(), e))?;
     serde_json::from_str(&content)
         .map_err(|e| format!("解析 JSON 失败 {}: {}", path.display(), e))
 }
 
+/// 分类 key 对应的 JSON 文件路径（先校验 key，防止借 `..` 等逃出数据目录）
+fn category_file_path(data_dir: &str, category_key: &str) -> Result<PathBuf, String> {
+    crate::category_ops::validate_category_key(category_key)?;
+    Ok(Path::new(data_dir).join(format!("{}.json", category_key)))
+}
+
 /// 写入分类 JSON 文件
 fn write_category_file(path: &Path, data: &CategoryJsonFile) -> Result<(), String> {
     let content = serde_json::to_string_pretty(data)
@@ -182,7 +194,7 @@ pub fn read_json_categories(data_dir: &str) -> Result<Vec<CategoryDefinition>, S
 
 /// 读取单个模板的完整数据
 pub fn read_json_template(data_dir: &str, category_key: &str, template_id: &str) -> Result<JsonResourceDetail, String> {
-    let json_path = Path::new(data_dir).join(format!("{}.json", category_key));
+    let json_path = category_file_path(data_dir, category_key)?;
     let cat_file = read_category_file(&json_path)?;
 
     let tmpl = cat_file.templates.iter()
@@ -205,6 +217,7 @@ pub fn read_json_template(data_dir: &str, category_key: &str, template_id: &str)
         plugin_data: tmpl.plugin_data.clone(),
         include_content: tmpl.include_content,
         include_ai_content: tmpl.include_ai_content,
+        aliases: tmpl.aliases.clone(),
     })
 }
 
@@ -227,8 +240,9 @@ pub fn save_json_template(
     plugin_data: Option<serde_json::Value>,
     include_content: Option<bool>,
     include_ai_content: Option<bool>,
+    tags: Option<Vec<String>>,
 ) -> Result<(), String> {
-    let json_path = Path::new(data_dir).join(format!("{}.json", category_key));
+    let json_path = category_file_path(data_dir, category_key)?;
     let mut cat_file = read_category_file(&json_path)?;
 
     let tmpl = cat_file.templates.iter_mut()
@@ -257,6 +271,9 @@ pub fn save_json_template(
     if let Some(iac) = include_ai_content {
         tmpl.include_ai_content = iac;
     }
+    if let Some(tags) = tags {
+        tmpl.tags = tags;
+    }
 
     write_category_file(&json_path, &cat_file)
 }
@@ -277,7 +294,10 @@ pub fn create_json_template(
     include_content: Option<bool>,
     include_ai_content: Option<bool>,
 ) -> Result<String, String> {
-    let json_path = Path::new(data_dir).join(format!("{}.json", category_key));
+    crate::category_ops::validate_category_key(category_key)?;
+    // 检查 ID 是否合法且在所有分类中唯一
+    crate::id_service::ensure_available(data_dir, id)?;
+    let json_path = category_file_path(data_dir, category_key)?;
 
     let mut cat_file = if json_path.exists() {
         read_category_file(&json_path)?
@@ -291,11 +311,6 @@ pub fn create_json_template(
         }
     };
 
-    // 检查 ID 是否已存在
-    if cat_file.templates.iter().any(|t| t.id == id) {
-        return Err(format!("模板 ID {} 已存在", id));
-    }
-
     // 计算 order
     let max_order = cat_file.templates.iter().map(|t| t.order).max().unwrap_or(-1);
 
@@ -313,6 +328,7 @@ pub fn create_json_template(
         plugin_data,
         include_content: include_content.unwrap_or(false),
         include_ai_content: include_ai_content.unwrap_or(false),
+        aliases: Vec::new(),
     });
 
     write_category_file(&json_path, &cat_file)?;
@@ -321,7 +337,7 @@ pub fn create_json_template(
 
 /// 删除模板
 pub fn delete_json_template(data_dir: &str, category_key: &str, template_id: &str) -> Result<(), String> {
-    let json_path = Path::new(data_dir).join(format!("{}.json", category_key));
+    let json_path = category_file_path(data_dir, category_key)?;
     let mut cat_file = read_category_file(&json_path)?;
 
     let before = cat_file.templates.len();
@@ -344,8 +360,12 @@ pub fn batch_delete_json_templates(data_dir: &str, paths: &[String]) -> Result<u
         }
     }
 
+    // 先校验全部分类 key，避免删到一半才失败
+    for cat_key in grouped.keys() {
+        crate::category_ops::validate_category_key(cat_key)?;
+    }
     for (cat_key, tmpl_ids) in &grouped {
-        let json_path = Path::new(data_dir).join(format!("{}.json", cat_key));
+        let json_path = category_file_path(data_dir, cat_key)?;
         if let Ok(mut cat_file) = read_category_file(&json_path) {
             let before = cat_file.templates.len();
             cat_file.templates.retain(|t| !tmpl_ids.contains(&t.id));
@@ -379,7 +399,7 @@ pub fn reorder_json_templates_in_category(
     category_key: &str,
     id_order_pairs: &[(String, i32)],
 ) -> Result<(), String> {
-    let json_path = Path::new(data_dir).join(format!("{}.json", category_key));
+    let json_path = category_file_path(data_dir, category_key)?;
     let mut cat_file = read_category_file(&json_path)?;
 
     for (id, new_order) in id_order_pairs {
@@ -399,7 +419,7 @@ pub fn move_json_template(
     to_category: &str,
 ) -> Result<(), String> {
     // 从源分类读取并移除
-    let from_path = Path::new(data_dir).join(format!("{}.json", from_category));
+    let from_path = category_file_path(data_dir, from_category)?;
     let mut from_file = read_category_file(&from_path)?;
 
     let tmpl_idx = from_file.templates.iter().position(|t| t.id == template_id)
@@ -410,7 +430,7 @@ pub fn move_json_template(
     write_category_file(&from_path, &from_file)?;
 
     // 添加到目标分类
-    let to_path = Path::new(data_dir).join(format!("{}.json", to_category));
+    let to_path = category_file_path(data_dir, to_category)?;
     let mut to_file = if to_path.exists() {
         read_category_file(&to_path)?
     } else {
@@ -439,7 +459,7 @@ pub fn save_json_category(
     icon: &str,
     order: i32,
 ) -> Result<(), String> {
-    let json_path = Path::new(data_dir).join(format!("{}.json", category_key));
+    let json_path = category_file_path(data_dir, category_key)?;
     let mut cat_file = if json_path.exists() {
         read_category_file(&json_path)?
     } else {
@@ -458,3 +478,298 @@ pub fn save_json_category(
 
     write_category_file(&json_path, &cat_file)
 }
+
+// ============================================================
+// 分类级联操作（重命名 / 删除 / 合并 / 排序）
+// ============================================================
+
+/// 以原子方式写入分类 JSON 文件（用于多文件联动修改）
+pub(crate) fn write_category_file_atomic(path: &Path, data: &CategoryJsonFile) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(data)
+        .map_err(|e| format!("序列化失败: {}", e))?;
+    crate::fs_utils::write_atomic(path, &content)
+}
+
+/// 重命名分类：`{old}.json` → `{new}.json`，并同步修正文件内的 key
+///
+/// 返回该分类下的模板数量。
+pub fn rename_json_category(data_dir: &str, old_key: &str, new_key: &str) -> Result<u32, String> {
+    crate::category_ops::validate_category_key(new_key)?;
+    if old_key == new_key {
+        return Ok(0);
+    }
+
+    let old_path = category_file_path(data_dir, old_key)?;
+    let new_path = category_file_path(data_dir, new_key)?;
+    if new_path.exists() {
+        return Err(format!("分类 {} 已存在，请使用合并分类", new_key));
+    }
+
+    let mut cat_file = read_category_file(&old_path)?;
+    cat_file.key = new_key.to_string();
+    write_category_file_atomic(&new_path, &cat_file)?;
+
+    if let Err(e) = fs::remove_file(&old_path) {
+        let _ = fs::remove_file(&new_path);
+        return Err(format!("删除旧分类文件失败: {}", e));
+    }
+    Ok(cat_file.templates.len() as u32)
+}
+
+/// 删除分类
+///
+/// - `reassign_to` 为 Some 时，模板追加到目标分类末尾
+/// - 否则将 `{key}.json` 移入 `_trash/`
+///
+/// 返回受影响的模板数量。
+pub fn delete_json_category(
+    data_dir: &str,
+    category_key: &str,
+    reassign_to: Option<&str>,
+) -> Result<u32, String> {
+    let data_path = Path::new(data_dir);
+    let json_path = category_file_path(data_dir, category_key)?;
+    let cat_file = read_category_file(&json_path)?;
+    let count = cat_file.templates.len() as u32;
+
+    match reassign_to {
+        Some(target) => {
+            if target == category_key {
+                return Err("目标分类不能与被删除的分类相同".to_string());
+            }
+            merge_json_categories(data_dir, &[category_key.to_string()], target)
+        }
+        None => {
+            crate::fs_utils::move_to_trash(data_path, &json_path)?;
+            Ok(count)
+        }
+    }
+}
+
+/// 合并分类：将源分类的全部模板追加到目标分类（order 顺延），然后删除源分类文件
+///
+/// 目标分类不存在时以目标 key 新建。模板 ID 冲突时整体失败，不做任何修改。
+pub fn merge_json_categories(
+    data_dir: &str,
+    source_keys: &[String],
+    target_key: &str,
+) -> Result<u32, String> {
+    let target_path = category_file_path(data_dir, target_key)?;
+    let mut target_file = if target_path.exists() {
+        read_category_file(&target_path)?
+    } else {
+        CategoryJsonFile {
+            key: target_key.to_string(),
+            name: target_key.to_string(),
+            icon: "📋".to_string(),
+            order: 999,
+            templates: Vec::new(),
+        }
+    };
+
+    let mut source_paths = Vec::new();
+    let mut next_order = target_file.templates.iter().map(|t| t.order).max().unwrap_or(-1) + 1;
+    let mut count = 0u32;
+    for source in source_keys.iter().filter(|s| s.as_str() != target_key) {
+        let source_path = category_file_path(data_dir, source)?;
+        let mut source_file = read_category_file(&source_path)?;
+        source_file.templates.sort_by_key(|t| t.order);
+        for mut tmpl in source_file.templates {
+            if target_file.templates.iter().any(|t| t.id == tmpl.id) {
+                return Err(format!("合并失败：模板 ID {} 重复", tmpl.id));
+            }
+            tmpl.order = next_order;
+            next_order += 1;
+            target_file.templates.push(tmpl);
+            count += 1;
+        }
+        source_paths.push(source_path);
+    }
+
+    write_category_file_atomic(&target_path, &target_file)?;
+    for source_path in &source_paths {
+        fs::remove_file(source_path)
+            .map_err(|e| format!("删除源分类文件失败 {}: {}", source_path.display(), e))?;
+    }
+    Ok(count)
+}
+
+/// 按给定 key 顺序重排分类（写入各文件的 order 字段）
+pub fn reorder_json_categories(data_dir: &str, keys: &[String]) -> Result<(), String> {
+    for key in keys {
+        crate::category_ops::validate_category_key(key)?;
+    }
+    for (i, key) in keys.iter().enumerate() {
+        let json_path = category_file_path(data_dir, key)?;
+        let mut cat_file = read_category_file(&json_path)?;
+        if cat_file.order != i as i32 {
+            cat_file.order = i as i32;
+            write_category_file_atomic(&json_path, &cat_file)?;
+        }
+    }
+    Ok(())
+}
+
+// ============================================================
+// 复制模板
+// ============================================================
+
+/// 收集所有分类 JSON 文件中的模板 ID
+pub(crate) fn collect_all_template_ids(data_dir: &str) -> std::collections::HashSet<String> {
+    let mut ids = std::collections::HashSet::new();
+    if let Ok(entries) = fs::read_dir(data_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            if let Ok(cat_file) = read_category_file(&path) {
+                ids.extend(cat_file.templates.into_iter().map(|t| t.id));
+            }
+        }
+    }
+    ids
+}
+
+/// 复制模板到目标分类（默认原分类）
+///
+/// 新模板使用不重复的 `{id}-copy[-N]` 作为 ID，名称追加「(副本)」；
+/// 同分类时 order 紧跟原模板，其后的模板顺延。返回新模板的 path（`category_key::template_id`）。
+pub fn duplicate_json_template(
+    data_dir: &str,
+    category_key: &str,
+    template_id: &str,
+    target_category: Option<&str>,
+) -> Result<String, String> {
+    let json_path = category_file_path(data_dir, category_key)?;
+    let mut cat_file = read_category_file(&json_path)?;
+    let source = cat_file.templates.iter()
+        .find(|t| t.id == template_id)
+        .cloned()
+        .ok_or_else(|| format!("模板 {} 未找到", template_id))?;
+
+    let new_id = crate::id_service::make_unique(
+        &format!("{}-copy", source.id),
+        &crate::id_service::existing_ids(data_dir),
+    );
+
+    let mut copy = source.clone();
+    copy.id = new_id.clone();
+    copy.name = format!("{}(副本)", source.name);
+    copy.aliases.clear();
+
+    let target_category = target_category.unwrap_or(category_key);
+    if target_category == category_key {
+        for tmpl in cat_file.templates.iter_mut() {
+            if tmpl.order > source.order {
+                tmpl.order += 1;
+            }
+        }
+        copy.order = source.order + 1;
+        cat_file.templates.push(copy);
+        cat_file.templates.sort_by_key(|t| t.order);
+        write_category_file(&json_path, &cat_file)?;
+    } else {
+        let to_path = category_file_path(data_dir, target_category)?;
+        let mut to_file = if to_path.exists() {
+            read_category_file(&to_path)?
+        } else {
+            CategoryJsonFile {
+                key: target_category.to_string(),
+                name: target_category.to_string(),
+                icon: "📋".to_string(),
+                order: 999,
+                templates: Vec::new(),
+            }
+        };
+        copy.order = to_file.templates.iter().map(|t| t.order).max().unwrap_or(-1) + 1;
+        to_file.templates.push(copy);
+        write_category_file(&to_path, &to_file)?;
+    }
+
+    Ok(format!("{}::{}", target_category, new_id))
+}
+
+// ============================================================
+// 模板 ID 重命名
+// ============================================================
+
+/// 重命名模板 ID（全部分类内唯一），`keep_alias` 为 true 时旧 ID 记入 `aliases`
+pub fn rename_json_template_id(
+    data_dir: &str,
+    category_key: &str,
+    template_id: &str,
+    new_id: &str,
+    keep_alias: bool,
+) -> Result<crate::types::RenameIdResult, String> {
+    let new_id = new_id.trim();
+    if template_id != new_id {
+        crate::id_service::ensure_available(data_dir, new_id)?;
+    }
+
+    let json_path = category_file_path(data_dir, category_key)?;
+    let mut cat_file = read_category_file(&json_path)?;
+    let tmpl = cat_file.templates.iter_mut()
+        .find(|t| t.id == template_id)
+        .ok_or_else(|| format!("模板 {} 未找到", template_id))?;
+
+    if template_id != new_id {
+        tmpl.id = new_id.to_string();
+        tmpl.aliases.retain(|a| a != new_id);
+        if keep_alias && !tmpl.aliases.iter().any(|a| a == template_id) {
+            tmpl.aliases.push(template_id.to_string());
+        }
+        write_category_file(&json_path, &cat_file)?;
+    }
+
+    let updated_references = if template_id != new_id {
+        rewrite_template_references(data_dir, template_id, new_id)?
+    } else {
+        0
+    };
+
+    Ok(crate::types::RenameIdResult {
+        new_path: format!("{}::{}", category_key, new_id),
+        updated_references,
+    })
+}
+
+/// 改写所有分类 JSON 中其他模板对旧 ID 的引用，返回被改写的模板数
+///
+/// 按原始 JSON 处理，保留结构体未声明的字段（如 roles、dependencies）。
+fn rewrite_template_references(data_dir: &str, old_id: &str, new_id: &str) -> Result<u32, String> {
+    let entries = fs::read_dir(data_dir).map_err(|e| format!("读取目录失败: {}", e))?;
+    let mut updated = 0u32;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(mut value) = serde_json::from_str::<serde_json::Value>(&content) else {
+            continue;
+        };
+        let Some(templates) = value.get_mut("templates").and_then(|t| t.as_array_mut()) else {
+            continue;
+        };
+        let mut changed = false;
+        for tmpl in templates.iter_mut() {
+            if tmpl.get("id").and_then(|v| v.as_str()) == Some(new_id) {
+                continue;
+            }
+            if crate::resource_ops::replace_references(tmpl, old_id, new_id) > 0 {
+                updated += 1;
+                changed = true;
+            }
+        }
+        if changed {
+            let content = serde_json::to_string_pretty(&value)
+                .map_err(|e| format!("序列化失败: {}", e))?;
+            fs::write(&path, content)
+                .map_err(|e| format!("写入文件失败 {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(updated)
+}
@@ -1,7 +1,23 @@
 pub mod ai;
+pub mod ai_diagnostics;
+pub mod ai_limits;
+pub mod ai_output;
+pub mod ai_provider;
+pub mod ai_services;
+pub mod ai_usage;
 pub mod category_ops;
 pub mod commands;
+pub mod fs_utils;
+pub mod http_client;
+pub mod id_service;
 pub mod import_export;
 pub mod json_file_ops;
+pub mod markdown;
+pub mod package;
+pub mod path_guard;
 pub mod resource_ops;
+pub mod secret_store;
+pub mod spreadsheet;
+pub mod sse;
+pub mod tasks;
 pub mod types;
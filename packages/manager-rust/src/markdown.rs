@@ -0,0 +1,523 @@
+use crate::import_export::{
+    apply_resource_import, apply_template_import, group_template_paths, template_content_file,
+    template_to_manifest, validate_content_file_name, CandidateKind, ImportCandidate,
+    ImportFailure, ImportOptions, ImportResult, DEFAULT_IMPORT_CATEGORY,
+};
+use crate::json_file_ops::{CategoryJsonFile, JsonTemplateEntry};
+use crate::types::GenericManifest;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+// ============================================================
+// Markdown 文件夹导入
+// ============================================================
+//
+// 递归读取文件夹中的 .md 文件：一级文件夹对应分类，二级文件夹对应子分类（仅目录模式），
+// 根目录下的文件放入 imported 分类。YAML front matter 提供 name、description、tags、variables，
+// 正文作为模板内容。front matter 中有合法 id 时沿用（冲突按导入策略处理），否则按名称生成
+// 全仓库唯一的 ID。分类文件夹中的 `_category.yml` 提供分类的 key、显示名称、图标与排序。
+
+/// 单次导入的文件数上限
+const MAX_MARKDOWN_FILES: usize = 10_000;
+/// 单个 Markdown 文件大小上限
+const MAX_MARKDOWN_FILE_SIZE: u64 = 8 * 1024 * 1024;
+/// 分类文件夹中的分类信息文件（以 `_` 开头，导入时不会被当作模板）
+const CATEGORY_INFO_FILE: &str = "_category.yml";
+
+/// 从一个 Markdown 文件读出的模板
+struct MarkdownTemplate {
+    /// 相对于导入文件夹的路径，作为导入来源
+    source: String,
+    category: FolderCategory,
+    sub_category: Option<FolderCategory>,
+    tmpl: JsonTemplateEntry,
+}
+
+/// 文件夹对应的分类：key 用于存储，name 为显示名称（新建分类时使用）
+#[derive(Clone)]
+struct FolderCategory {
+    key: String,
+    name: String,
+    icon: Option<String>,
+    order: Option<i32>,
+}
+
+/// 从 Markdown 文件夹导入模板（目录模式）
+///
+/// 内容写入 `options.contentFile`（默认 content.md）；新建的分类以文件夹名作为显示名称。
+pub fn import_markdown(
+    folder: &str,
+    data_dir: &str,
+    options: &ImportOptions,
+) -> Result<ImportResult, String> {
+    let content_file = options.content_file.as_deref().unwrap_or("content.md");
+    validate_content_file_name(content_file)?;
+    let meta = crate::category_ops::read_meta(data_dir)?;
+    let known: Vec<(String, String)> =
+        meta.categories.iter().map(|c| (c.key.clone(), c.name.clone())).collect();
+    let (templates, mut failed) = read_markdown_folder(folder, data_dir, &known)?;
+
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut candidates = Vec::new();
+    for md in templates {
+        let sub = md.sub_category.clone();
+        let mut manifest = template_to_manifest(&md.tmpl, &md.category.key);
+        let now = chrono::Utc::now().to_rfc3339();
+        if let Some(obj) = manifest.as_object_mut() {
+            let sub_key = sub.as_ref().map(|s| s.key.clone()).unwrap_or_default();
+            obj.insert("subCategory".to_string(), serde_json::json!(sub_key));
+            obj.insert("createdAt".to_string(), serde_json::json!(now));
+            obj.insert("updatedAt".to_string(), serde_json::json!(now));
+        }
+        let parsed = serde_json::from_value::<GenericManifest>(manifest.clone())
+            .and_then(|m| serde_json::to_vec_pretty(&manifest).map(|bytes| (m, bytes)))
+            .map_err(|e| format!("生成 manifest 失败: {}", e));
+        let (generic, manifest_bytes) = match parsed {
+            Ok(p) => p,
+            Err(e) => {
+                failed.push(ImportFailure { id: md.source, error: e });
+                continue;
+            }
+        };
+
+        names.insert(md.category.key.clone(), md.category.name.clone());
+        if let Some(sub) = &sub {
+            names.insert(format!("{}/{}", md.category.key, sub.key), sub.name.clone());
+        }
+        let mut files = BTreeMap::new();
+        files.insert("manifest.json".to_string(), manifest_bytes);
+        files.insert(
+            content_file.to_string(),
+            template_content_file(&md.tmpl, content_file).into_bytes(),
+        );
+        candidates.push(ImportCandidate {
+            source: md.source,
+            manifest: generic,
+            kind: CandidateKind::Files(files),
+        });
+    }
+
+    let result = apply_resource_import(data_dir, candidates, failed, options, None)?;
+    if !result.dry_run && !result.created_categories.is_empty() {
+        name_created_categories(data_dir, &result.created_categories, &names)?;
+    }
+    Ok(result)
+}
+
+/// 从 Markdown 文件夹导入模板（JSON 文件模式）
+///
+/// 只使用一级文件夹作为分类，更深的文件夹并入所属分类；新建的分类文件以文件夹名作为显示名称。
+pub fn import_json_templates_markdown(
+    folder: &str,
+    data_dir: &str,
+    options: &ImportOptions,
+) -> Result<ImportResult, String> {
+    let known: Vec<(String, String)> = crate::json_file_ops::read_json_categories(data_dir)?
+        .into_iter()
+        .map(|c| (c.key, c.name))
+        .collect();
+    let (templates, failed) = read_markdown_folder(folder, data_dir, &known)?;
+    let candidates = templates
+        .into_iter()
+        .map(|md| {
+            let cat_file = CategoryJsonFile {
+                key: md.category.key,
+                name: md.category.name,
+                icon: md.category.icon.unwrap_or_else(|| "📋".to_string()),
+                order: md.category.order.unwrap_or(999),
+                templates: Vec::new(),
+            };
+            (md.source, cat_file, md.tmpl)
+        })
+        .collect();
+    apply_template_import(data_dir, candidates, failed, options)
+}
+
+/// 遍历文件夹，解析所有 Markdown 文件
+///
+/// known 为仓库现有分类（key, 名称）：文件夹名与现有分类的 key 或名称相同时归入该分类，
+/// 否则将文件夹名音译为 key。
+fn read_markdown_folder(
+    folder: &str,
+    data_dir: &str,
+    known: &[(String, String)],
+) -> Result<(Vec<MarkdownTemplate>, Vec<ImportFailure>), String> {
+    let root = Path::new(folder);
+    if !root.is_dir() {
+        return Err(format!("文件夹不存在: {}", folder));
+    }
+    let mut taken = crate::id_service::existing_ids(data_dir);
+    let mut templates = Vec::new();
+    let mut failed = Vec::new();
+    let mut count = 0usize;
+
+    let walker = walkdir::WalkDir::new(root)
+        .follow_links(false)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            e.depth() == 0 || !(name.starts_with('.') || name.starts_with('_'))
+        });
+    for entry in walker {
+        let entry = entry.map_err(|e| format!("遍历文件夹失败: {}", e))?;
+        if !entry.file_type().is_file() || !is_markdown(entry.path()) {
+            continue;
+        }
+        count += 1;
+        if count > MAX_MARKDOWN_FILES {
+            return Err(format!("Markdown 文件数超过上限 {}", MAX_MARKDOWN_FILES));
+        }
+
+        let rel = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        let folders: Vec<String> = rel
+            .parent()
+            .map(|p| p.iter().map(|c| c.to_string_lossy().to_string()).collect())
+            .unwrap_or_default();
+        let source = rel
+            .iter()
+            .map(|c| c.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        match read_markdown_file(entry.path(), &mut taken) {
+            Ok(tmpl) => templates.push(MarkdownTemplate {
+                source,
+                category: folders
+                    .first()
+                    .map(|f| folder_category(&root.join(f), f, known))
+                    .unwrap_or_else(|| FolderCategory {
+                        key: DEFAULT_IMPORT_CATEGORY.to_string(),
+                        name: DEFAULT_IMPORT_CATEGORY.to_string(),
+                        icon: None,
+                        order: None,
+                    }),
+                sub_category: folders.get(1).map(|f| FolderCategory {
+                    key: category_key(f),
+                    name: f.clone(),
+                    icon: None,
+                    order: None,
+                }),
+                tmpl,
+            }),
+            Err(e) => failed.push(ImportFailure { id: source, error: e }),
+        }
+    }
+    // 同一分类内按 front matter 的 order 排列（未指定时为 0，保持文件名顺序），导入后相对顺序不变
+    templates.sort_by(|a, b| {
+        a.category
+            .key
+            .cmp(&b.category.key)
+            .then(a.tmpl.order.cmp(&b.tmpl.order))
+    });
+    Ok((templates, failed))
+}
+
+/// 解析单个 Markdown 文件；未提供合法 id 时生成新 ID 并登记到 taken
+fn read_markdown_file(
+    path: &Path,
+    taken: &mut HashSet<String>,
+) -> Result<JsonTemplateEntry, String> {
+    let size = fs::metadata(path).map_err(|e| format!("读取文件失败: {}", e))?.len();
+    if size > MAX_MARKDOWN_FILE_SIZE {
+        return Err(format!("文件超过大小上限 {} 字节", MAX_MARKDOWN_FILE_SIZE));
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?;
+    let (front, body) = split_front_matter(&text)?;
+    let front = front.unwrap_or_default();
+
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = yaml_str(&front, "name")
+        .or_else(|| yaml_str(&front, "title"))
+        .or_else(|| first_heading(body))
+        .unwrap_or(stem);
+
+    let id = match yaml_str(&front, "id") {
+        Some(id) if crate::id_service::validate_id(&id).is_ok() => id,
+        _ => {
+            let mut base = crate::id_service::slugify_name(&name);
+            if base.is_empty() {
+                let short = uuid::Uuid::new_v4().simple().to_string();
+                base = format!("prompt-{}", &short[..8]);
+            }
+            crate::id_service::make_unique(&base, taken)
+        }
+    };
+    taken.insert(id.clone());
+
+    let flag = |key: &str| front.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+    let plugin_data = match front.get("pluginData") {
+        Some(value) if !value.is_null() => Some(
+            serde_json::to_value(value).map_err(|e| format!("pluginData 转换失败: {}", e))?,
+        ),
+        _ => None,
+    };
+
+    Ok(JsonTemplateEntry {
+        id,
+        name,
+        description: yaml_text(&front, "description"),
+        content: body.to_string(),
+        variables: yaml_list(&front, "variables"),
+        order: front
+            .get("order")
+            .and_then(|v| v.as_i64())
+            .and_then(|o| i32::try_from(o).ok())
+            .unwrap_or(0),
+        author_notes: yaml_text(&front, "authorNotes"),
+        tags: yaml_list(&front, "tags"),
+        ai_generated_content: yaml_text(&front, "aiGeneratedContent"),
+        enabled_plugins: yaml_list(&front, "enabledPlugins"),
+        plugin_data,
+        include_content: flag("includeContent"),
+        include_ai_content: flag("includeAiContent"),
+        aliases: yaml_list(&front, "aliases"),
+    })
+}
+
+/// 拆分 YAML front matter 与正文
+///
+/// 文件以 `---` 行开头且之后有 `---` 或 `...` 结束行时视为 front matter；
+/// 结束行后的第一个空行属于分隔，不计入正文。
+fn split_front_matter(text: &str) -> Result<(Option<serde_yaml::Mapping>, &str), String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let Some(rest) = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))
+    else {
+        return Ok((None, text));
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            let yaml = &rest[..offset];
+            let mut body = &rest[offset + line.len()..];
+            body = body
+                .strip_prefix("\r\n")
+                .or_else(|| body.strip_prefix('\n'))
+                .unwrap_or(body);
+            let value: serde_yaml::Value = serde_yaml::from_str(yaml)
+                .map_err(|e| format!("front matter 解析失败: {}", e))?;
+            let mapping = match value {
+                serde_yaml::Value::Mapping(m) => m,
+                serde_yaml::Value::Null => serde_yaml::Mapping::new(),
+                _ => return Err("front matter 必须是键值对".to_string()),
+            };
+            return Ok((Some(mapping), body));
+        }
+        offset += line.len();
+    }
+    // 没有结束行，整个文件都是正文
+    Ok((None, text))
+}
+
+/// 读取字符串字段（数字、布尔值按文本处理）
+fn yaml_str(front: &serde_yaml::Mapping, key: &str) -> Option<String> {
+    let value = match front.get(key)? {
+        serde_yaml::Value::String(s) => s.clone(),
+        serde_yaml::Value::Number(n) => n.to_string(),
+        serde_yaml::Value::Bool(b) => b.to_string(),
+        _ => return None,
+    };
+    let value = value.trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+/// 读取多行文本字段，原样保留空白
+fn yaml_text(front: &serde_yaml::Mapping, key: &str) -> String {
+    front
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .unwrap_or_default()
+}
+
+/// 读取列表字段：YAML 序列，或以逗号分隔的字符串
+fn yaml_list(front: &serde_yaml::Mapping, key: &str) -> Vec<String> {
+    let items: Vec<String> = match front.get(key) {
+        Some(serde_yaml::Value::Sequence(seq)) => seq
+            .iter()
+            .filter_map(|v| match v {
+                serde_yaml::Value::String(s) => Some(s.clone()),
+                serde_yaml::Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .collect(),
+        Some(serde_yaml::Value::String(s)) => {
+            s.split([',', '，']).map(String::from).collect()
+        }
+        _ => Vec::new(),
+    };
+    items
+        .into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// 正文中第一个一级标题
+fn first_heading(body: &str) -> Option<String> {
+    body.lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("md") || e.eq_ignore_ascii_case("markdown"))
+        .unwrap_or(false)
+}
+
+/// 文件夹 → 分类：`_category.yml` 中合法的 key 优先（导出时写入，保证原样还原），
+/// 其次匹配现有分类的 key 或名称，最后由文件夹名音译；`_category.yml` 同时提供显示名称、图标与排序
+fn folder_category(dir: &Path, folder: &str, known: &[(String, String)]) -> FolderCategory {
+    let info: serde_yaml::Mapping = fs::read_to_string(dir.join(CATEGORY_INFO_FILE))
+        .ok()
+        .and_then(|c| serde_yaml::from_str(&c).ok())
+        .unwrap_or_default();
+    let key = yaml_str(&info, "key")
+        .filter(|key| crate::category_ops::validate_category_key(key).is_ok())
+        .or_else(|| {
+            known
+                .iter()
+                .find(|(key, name)| key == folder || name == folder)
+                .map(|(key, _)| key.clone())
+        })
+        .unwrap_or_else(|| category_key(folder));
+    FolderCategory {
+        key,
+        name: yaml_str(&info, "name").unwrap_or_else(|| folder.to_string()),
+        icon: yaml_str(&info, "icon"),
+        order: info
+            .get("order")
+            .and_then(|v| v.as_i64())
+            .and_then(|o| i32::try_from(o).ok()),
+    }
+}
+
+/// 文件夹名音译为分类 key，无法音译时使用 imported
+fn category_key(folder: &str) -> String {
+    let key = crate::id_service::slugify_name(folder);
+    if key.is_empty() {
+        DEFAULT_IMPORT_CATEGORY.to_string()
+    } else {
+        key
+    }
+}
+
+/// 导入时新建的分类（及 `分类/子分类`）改用文件夹原名作为显示名称
+fn name_created_categories(
+    data_dir: &str,
+    created: &[String],
+    names: &HashMap<String, String>,
+) -> Result<(), String> {
+    let mut meta = crate::category_ops::read_meta(data_dir)?;
+    for cat in &mut meta.categories {
+        if created.contains(&cat.key) {
+            if let Some(name) = names.get(&cat.key) {
+                cat.name = name.clone();
+            }
+        }
+        for sub in &mut cat.sub_categories {
+            let path = format!("{}/{}", cat.key, sub.key);
+            if created.contains(&path) {
+                if let Some(name) = names.get(&path) {
+                    sub.name = name.clone();
+                }
+            }
+        }
+    }
+    crate::category_ops::write_meta_atomic(Path::new(data_dir), &meta)
+}
+
+// ============================================================
+// 导出为 Markdown 文件夹（JSON 文件模式）
+// ============================================================
+
+/// 导出 JSON 文件模式的模板为 Markdown 文件夹
+///
+/// 每个模板写为 `{category}/{id}.md`：YAML front matter 保存 id、name、description、tags、
+/// variables、order 以及非默认的其他字段，正文为 content；每个分类文件夹附带 `_category.yml`
+/// 记录分类名称、图标与排序。用 Markdown 导入可无损还原。返回写出的文件数。
+pub fn export_json_templates_markdown(
+    data_dir: &str,
+    template_paths: &[String],
+    output_dir: &str,
+) -> Result<u32, String> {
+    let selected = group_template_paths(template_paths)?;
+    let mut count = 0u32;
+    for (cat_key, ids) in &selected {
+        crate::category_ops::validate_category_key(cat_key)?;
+        let json_path = Path::new(data_dir).join(format!("{}.json", cat_key));
+        let cat_file = crate::json_file_ops::read_category_file(&json_path)?;
+        let cat_dir = Path::new(output_dir).join(cat_key);
+        fs::create_dir_all(&cat_dir).map_err(|e| format!("创建目录失败: {}", e))?;
+
+        let mut info = serde_yaml::Mapping::new();
+        info.insert("key".into(), cat_file.key.clone().into());
+        info.insert("name".into(), cat_file.name.clone().into());
+        info.insert("icon".into(), cat_file.icon.clone().into());
+        info.insert("order".into(), cat_file.order.into());
+        let info =
+            serde_yaml::to_string(&info).map_err(|e| format!("序列化分类信息失败: {}", e))?;
+        fs::write(cat_dir.join(CATEGORY_INFO_FILE), info)
+            .map_err(|e| format!("写入 {} 失败: {}", CATEGORY_INFO_FILE, e))?;
+
+        for tmpl in cat_file.templates.iter().filter(|t| ids.contains(&t.id)) {
+            crate::id_service::validate_id(&tmpl.id)?;
+            let text = template_to_markdown(tmpl)?;
+            fs::write(cat_dir.join(format!("{}.md", tmpl.id)), text)
+                .map_err(|e| format!("写入 {}.md 失败: {}", tmpl.id, e))?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// 模板 → 带 front matter 的 Markdown；默认值字段省略，正文与 front matter 之间空一行
+fn template_to_markdown(tmpl: &JsonTemplateEntry) -> Result<String, String> {
+    let list = |items: &[String]| -> serde_yaml::Value {
+        serde_yaml::Value::Sequence(items.iter().map(|s| s.clone().into()).collect())
+    };
+    let mut front = serde_yaml::Mapping::new();
+    front.insert("id".into(), tmpl.id.clone().into());
+    front.insert("name".into(), tmpl.name.clone().into());
+    front.insert("description".into(), tmpl.description.clone().into());
+    front.insert("tags".into(), list(&tmpl.tags));
+    front.insert("variables".into(), list(&tmpl.variables));
+    front.insert("order".into(), tmpl.order.into());
+    if !tmpl.author_notes.is_empty() {
+        front.insert("authorNotes".into(), tmpl.author_notes.clone().into());
+    }
+    if !tmpl.ai_generated_content.is_empty() {
+        front.insert("aiGeneratedContent".into(), tmpl.ai_generated_content.clone().into());
+    }
+    if !tmpl.enabled_plugins.is_empty() {
+        front.insert("enabledPlugins".into(), list(&tmpl.enabled_plugins));
+    }
+    if let Some(plugin_data) = &tmpl.plugin_data {
+        let value = serde_yaml::to_value(plugin_data)
+            .map_err(|e| format!("pluginData 转换失败: {}", e))?;
+        front.insert("pluginData".into(), value);
+    }
+    if tmpl.include_content {
+        front.insert("includeContent".into(), true.into());
+    }
+    if tmpl.include_ai_content {
+        front.insert("includeAiContent".into(), true.into());
+    }
+    if !tmpl.aliases.is_empty() {
+        front.insert("aliases".into(), list(&tmpl.aliases));
+    }
+    let yaml = serde_yaml::to_string(&front)
+        .map_err(|e| format!("序列化 front matter 失败: {}", e))?;
+    Ok(format!("---\n{}---\n\n{}", yaml, tmpl.content))
+}
@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Read;
+
+// ============================================================
+// .aidocpack 资源包格式
+// ============================================================
+//
+// 资源包本身是普通 ZIP，根目录下的 aidocpack.json 描述包内容：
+// 格式版本、资源类型、导出器版本、创建时间、资源列表，以及每个文件的大小与 SHA-256。
+// 导入时逐个校验，发现损坏或被篡改的资源整体拒绝。
+
+/// 资源包描述文件名（位于 ZIP 根目录）
+pub const PACKAGE_MANIFEST_FILE: &str = "aidocpack.json";
+/// 资源包文件扩展名
+pub const PACKAGE_EXTENSION: &str = "aidocpack";
+/// 当前资源包格式版本；导入时拒绝更高版本
+pub const PACKAGE_FORMAT_VERSION: u32 = 1;
+
+/// 资源包描述（aidocpack.json）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageManifest {
+    #[serde(rename = "formatVersion")]
+    pub format_version: u32,
+    #[serde(default, rename = "resourceType")]
+    pub resource_type: String,
+    /// 导出时的数据模式：`directory` 或 `json-file`
+    #[serde(default, rename = "dataMode")]
+    pub data_mode: String,
+    #[serde(default, rename = "exporterVersion")]
+    pub exporter_version: String,
+    #[serde(default, rename = "createdAt")]
+    pub created_at: String,
+    #[serde(default)]
+    pub resources: Vec<PackageResource>,
+    #[serde(default)]
+    pub files: Vec<PackageFile>,
+}
+
+/// 资源包中的一个资源
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageResource {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default, rename = "majorCategory")]
+    pub major_category: String,
+    /// 资源在包内的位置：目录模式为顶层目录名，JSON 文件模式为分类文件路径
+    #[serde(default)]
+    pub path: String,
+}
+
+/// 资源包中的一个文件及其校验值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageFile {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// 校验发现的问题
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageProblem {
+    pub path: String,
+    pub error: String,
+}
+
+impl PackageManifest {
+    /// 创建新的资源包描述，填入格式版本、导出器版本与当前时间
+    pub fn new(resource_type: &str, data_mode: &str) -> Self {
+        Self {
+            format_version: PACKAGE_FORMAT_VERSION,
+            resource_type: resource_type.to_string(),
+            data_mode: data_mode.to_string(),
+            exporter_version: format!("aidocplus-manager {}", env!("CARGO_PKG_VERSION")),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            resources: Vec::new(),
+            files: Vec::new(),
+        }
+    }
+
+    /// 记录一个已写入包内的文件
+    pub fn add_file(&mut self, path: &str, content: &[u8]) {
+        let mut digest = FileDigest::default();
+        digest.update(content);
+        self.files.push(digest.finish(path));
+    }
+
+    /// 解析并检查格式版本
+    pub fn parse(content: &[u8]) -> Result<Self, String> {
+        let manifest: PackageManifest = serde_json::from_slice(content)
+            .map_err(|e| format!("{} 解析失败: {}", PACKAGE_MANIFEST_FILE, e))?;
+        if manifest.format_version > PACKAGE_FORMAT_VERSION {
+            return Err(format!(
+                "资源包格式版本 {} 高于当前支持的版本 {}，请升级管理器",
+                manifest.format_version, PACKAGE_FORMAT_VERSION
+            ));
+        }
+        Ok(manifest)
+    }
+
+    /// 将实际文件（路径 → (SHA-256, 大小)）与描述比对：缺失、大小或校验值不符、未登记的文件都算问题
+    pub fn verify(&self, actual: &BTreeMap<String, (String, u64)>) -> Vec<PackageProblem> {
+        let mut problems = Vec::new();
+        for file in &self.files {
+            match actual.get(&file.path) {
+                None => problems.push(PackageProblem {
+                    path: file.path.clone(),
+                    error: "文件缺失".to_string(),
+                }),
+                Some((_, size)) if *size != file.size => problems.push(PackageProblem {
+                    path: file.path.clone(),
+                    error: format!("大小不符（应为 {}，实际 {}）", file.size, size),
+                }),
+                Some((hash, _)) if !hash.eq_ignore_ascii_case(&file.sha256) => {
+                    problems.push(PackageProblem {
+                        path: file.path.clone(),
+                        error: "SHA-256 校验失败".to_string(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for path in actual.keys() {
+            if !self.files.iter().any(|f| &f.path == path) {
+                problems.push(PackageProblem {
+                    path: path.clone(),
+                    error: "文件未在资源包描述中登记".to_string(),
+                });
+            }
+        }
+        problems
+    }
+}
+
+/// 边写入边计算的文件校验值（流式导出时避免把整个文件读入内存）
+#[derive(Default)]
+pub struct FileDigest {
+    hasher: Sha256,
+    size: u64,
+}
+
+impl FileDigest {
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+        self.size += chunk.len() as u64;
+    }
+
+    pub fn finish(self, path: &str) -> PackageFile {
+        PackageFile {
+            path: path.to_string(),
+            size: self.size,
+            sha256: format!("{:x}", self.hasher.finalize()),
+        }
+    }
+}
+
+/// 流式计算 SHA-256，读取超过 limit 字节即失败；返回（SHA-256, 实际大小）
+pub fn sha256_reader(reader: impl Read, limit: u64) -> Result<(String, u64), String> {
+    let mut hasher = Sha256::new();
+    let mut reader = reader.take(limit + 1);
+    let mut buffer = [0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = reader
+            .read(&mut buffer)
+            .map_err(|e| format!("读取文件失败: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        size += n as u64;
+    }
+    if size > limit {
+        return Err(format!("文件超过大小上限 {} 字节", limit));
+    }
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
@@ -0,0 +1,291 @@
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+// ============================================================
+// 路径沙箱：所有接收路径的命令都只能访问允许的根目录
+// ============================================================
+
+/// 路径校验错误（消息以错误码开头，前端可据此区分权限错误与普通失败）
+#[derive(Debug, thiserror::Error)]
+pub enum PathGuardError {
+    #[error("[PERMISSION_DENIED] 路径不在允许访问的目录范围内: {0}")]
+    OutsideAllowedRoots(String),
+    #[error("[PERMISSION_DENIED] 不允许直接操作根目录: {0}")]
+    RootItself(String),
+    #[error("[PERMISSION_DENIED] 尚未设置数据目录，无法解析相对路径: {0}")]
+    NoDataDir(String),
+    #[error("[INVALID_PATH] 无效路径 {path}: {reason}")]
+    InvalidPath { path: String, reason: String },
+}
+
+impl From<PathGuardError> for String {
+    fn from(e: PathGuardError) -> Self {
+        e.to_string()
+    }
+}
+
+/// 数据目录之外额外允许访问的位置
+///
+/// - `roots`：整棵目录树可访问（如 bundled-resources）
+/// - `files`：仅单个文件可访问（通过系统对话框选择的导出目标 / 导入文件）
+#[derive(Debug, Default)]
+pub struct AllowedRoots {
+    roots: Mutex<Vec<PathBuf>>,
+    files: Mutex<HashSet<PathBuf>>,
+}
+
+impl AllowedRoots {
+    /// 允许访问整个目录树
+    pub fn add_root(&self, dir: &Path) {
+        if let Ok(dir) = normalize(dir) {
+            if let Ok(mut roots) = self.roots.lock() {
+                if !roots.contains(&dir) {
+                    roots.push(dir);
+                }
+            }
+        }
+    }
+
+    /// 允许访问单个文件（文件可以尚不存在，如导出目标）
+    pub fn allow_file(&self, file: &Path) {
+        if let Ok(file) = normalize(file) {
+            if let Ok(mut files) = self.files.lock() {
+                files.insert(file);
+            }
+        }
+    }
+
+    /// 目录是否位于某个已登记的根目录之内（含根目录本身）
+    pub fn contains_dir(&self, dir: &Path) -> bool {
+        self.roots().iter().any(|root| dir.starts_with(root))
+    }
+
+    fn roots(&self) -> Vec<PathBuf> {
+        self.roots.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+
+    fn contains_file(&self, file: &Path) -> bool {
+        self.files.lock().map(|f| f.contains(file)).unwrap_or(false)
+    }
+}
+
+/// 规范化路径：对已存在的最长前缀做 canonicalize（解析符号链接），再拼接尚不存在的部分
+///
+/// 尚不存在的部分中不允许出现 `..`，防止借助新建目录逃逸。
+pub fn normalize(path: &Path) -> Result<PathBuf, PathGuardError> {
+    let invalid = |reason: &str| PathGuardError::InvalidPath {
+        path: path.display().to_string(),
+        reason: reason.to_string(),
+    };
+    if !path.is_absolute() {
+        return Err(invalid("必须是绝对路径"));
+    }
+
+    let mut existing = path.to_path_buf();
+    let mut rest: Vec<std::ffi::OsString> = Vec::new();
+    while !existing.exists() {
+        let name = existing.file_name().map(|n| n.to_os_string());
+        match name {
+            Some(name) => rest.push(name),
+            None => return Err(invalid("无法解析路径")),
+        }
+        if !existing.pop() {
+            return Err(invalid("无法解析路径"));
+        }
+    }
+    // file_name() 会跳过末尾的 `..`，这里对原始路径整体再检查一次
+    if !rest.is_empty() && path.components().any(|c| c == Component::ParentDir) {
+        return Err(invalid("不允许包含 .."));
+    }
+
+    // dunce 在 Windows 上避免返回 \\?\ 前缀的路径
+    let mut resolved = dunce::canonicalize(&existing).map_err(|e| invalid(&e.to_string()))?;
+    for name in rest.iter().rev() {
+        resolved.push(name);
+    }
+    Ok(resolved)
+}
+
+/// 将前端传入的路径解析为沙箱内的绝对路径
+///
+/// 相对路径相对于 data_root 解析；结果必须位于 data_root 或额外允许的根目录之内，
+/// 或者是经对话框选择登记过的单个文件。
+pub fn resolve(
+    data_root: Option<&str>,
+    allowed: &AllowedRoots,
+    raw: &str,
+) -> Result<PathBuf, PathGuardError> {
+    let raw_path = Path::new(raw);
+    let absolute = if raw_path.is_absolute() {
+        raw_path.to_path_buf()
+    } else {
+        let root = data_root.ok_or_else(|| PathGuardError::NoDataDir(raw.to_string()))?;
+        Path::new(root).join(raw_path)
+    };
+    let path = normalize(&absolute)?;
+
+    if allowed_roots(data_root, allowed).iter().any(|root| path.starts_with(root))
+        || allowed.contains_file(&path)
+    {
+        return Ok(path);
+    }
+    Err(PathGuardError::OutsideAllowedRoots(raw.to_string()))
+}
+
+/// 解析资源路径：除沙箱检查外，还要求路径严格位于某个根目录之下（不能是根目录本身），
+/// 用于删除、移动等破坏性操作。
+pub fn resolve_child(
+    data_root: Option<&str>,
+    allowed: &AllowedRoots,
+    raw: &str,
+) -> Result<PathBuf, PathGuardError> {
+    let path = resolve(data_root, allowed, raw)?;
+    if allowed_roots(data_root, allowed).contains(&path) {
+        return Err(PathGuardError::RootItself(raw.to_string()));
+    }
+    Ok(path)
+}
+
+fn allowed_roots(data_root: Option<&str>, allowed: &AllowedRoots) -> Vec<PathBuf> {
+    let mut roots = allowed.roots();
+    if let Some(root) = data_root.and_then(|r| normalize(Path::new(r)).ok()) {
+        roots.push(root);
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 临时目录下的 data 与 data2（共享前缀的同级目录）
+    struct Sandbox {
+        base: PathBuf,
+        data: PathBuf,
+        sibling: PathBuf,
+    }
+
+    impl Sandbox {
+        fn new() -> Self {
+            let base = std::env::temp_dir().join(format!("path-guard-{}", uuid::Uuid::new_v4()));
+            let data = base.join("data");
+            let sibling = base.join("data2");
+            std::fs::create_dir_all(data.join("cat/res")).unwrap();
+            std::fs::create_dir_all(&sibling).unwrap();
+            let base = dunce::canonicalize(base).unwrap();
+            Self { data: base.join("data"), sibling: base.join("data2"), base }
+        }
+
+        fn root(&self) -> Option<&str> {
+            self.data.to_str()
+        }
+    }
+
+    impl Drop for Sandbox {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.base);
+        }
+    }
+
+    fn is_outside(result: Result<PathBuf, PathGuardError>) -> bool {
+        matches!(result, Err(PathGuardError::OutsideAllowedRoots(_)))
+    }
+
+    #[test]
+    fn resolve_accepts_paths_inside_data_root() {
+        let sb = Sandbox::new();
+        let allowed = AllowedRoots::default();
+        let absolute = sb.data.join("cat/res");
+        let resolved = resolve(sb.root(), &allowed, absolute.to_str().unwrap()).unwrap();
+        assert_eq!(resolved, absolute);
+        // 相对路径基于数据目录，尚不存在的部分原样拼接
+        let resolved = resolve(sb.root(), &allowed, "cat/new/file.md").unwrap();
+        assert_eq!(resolved, sb.data.join("cat/new/file.md"));
+        // 已存在部分中的 .. 由 canonicalize 解析，仍在根目录内即可
+        assert!(resolve(sb.root(), &allowed, "cat/res/../res").is_ok());
+        assert!(matches!(
+            resolve(None, &allowed, "cat/res"),
+            Err(PathGuardError::NoDataDir(_))
+        ));
+    }
+
+    #[test]
+    fn resolve_rejects_sibling_with_shared_prefix() {
+        let sb = Sandbox::new();
+        let allowed = AllowedRoots::default();
+        let sibling = sb.sibling.join("x.md");
+        assert!(is_outside(resolve(sb.root(), &allowed, sibling.to_str().unwrap())));
+        assert!(is_outside(resolve(sb.root(), &allowed, "../data2")));
+        assert!(resolve(sb.root(), &allowed, "../data2/x.md").is_err());
+        assert!(!allowed.contains_dir(&sb.sibling));
+        allowed.add_root(&sb.data);
+        assert!(allowed.contains_dir(&sb.data.join("cat")));
+        assert!(!allowed.contains_dir(&sb.sibling));
+    }
+
+    #[test]
+    fn normalize_rejects_parent_dir_in_missing_tail() {
+        let sb = Sandbox::new();
+        let escape = sb.data.join("missing/../../data2");
+        assert!(matches!(normalize(&escape), Err(PathGuardError::InvalidPath { .. })));
+        let trailing = sb.data.join("missing/..");
+        assert!(matches!(normalize(&trailing), Err(PathGuardError::InvalidPath { .. })));
+        assert!(normalize(Path::new("relative/path")).is_err());
+        let allowed = AllowedRoots::default();
+        assert!(resolve(sb.root(), &allowed, "missing/../../data2").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_rejects_symlink_escaping_root() {
+        let sb = Sandbox::new();
+        let allowed = AllowedRoots::default();
+        std::os::unix::fs::symlink(&sb.sibling, sb.data.join("link")).unwrap();
+        assert!(is_outside(resolve(sb.root(), &allowed, "link")));
+        assert!(is_outside(resolve(sb.root(), &allowed, "link/new-file.md")));
+        // 指向根目录内的链接允许
+        std::os::unix::fs::symlink(sb.data.join("cat"), sb.data.join("inner")).unwrap();
+        assert_eq!(
+            resolve(sb.root(), &allowed, "inner/res").unwrap(),
+            sb.data.join("cat/res")
+        );
+    }
+
+    #[test]
+    fn resolve_child_rejects_roots_themselves() {
+        let sb = Sandbox::new();
+        let allowed = AllowedRoots::default();
+        allowed.add_root(&sb.sibling);
+        for root in [&sb.data, &sb.sibling] {
+            let raw = root.to_str().unwrap();
+            assert!(resolve(sb.root(), &allowed, raw).is_ok());
+            assert!(matches!(
+                resolve_child(sb.root(), &allowed, raw),
+                Err(PathGuardError::RootItself(_))
+            ));
+        }
+        let raw = format!("{}/", sb.data.display());
+        assert!(matches!(
+            resolve_child(sb.root(), &allowed, &raw),
+            Err(PathGuardError::RootItself(_))
+        ));
+        assert!(resolve_child(sb.root(), &allowed, "cat/res").is_ok());
+        assert!(resolve_child(sb.root(), &allowed, sb.sibling.join("a").to_str().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn allowed_files_grant_only_the_file_itself() {
+        let sb = Sandbox::new();
+        let allowed = AllowedRoots::default();
+        let export = sb.sibling.join("export.zip");
+        allowed.allow_file(&export);
+        assert!(resolve(sb.root(), &allowed, export.to_str().unwrap()).is_ok());
+        assert!(resolve_child(sb.root(), &allowed, export.to_str().unwrap()).is_ok());
+        // 同目录的其他文件、所在目录本身都不允许
+        let other = sb.sibling.join("other.zip");
+        assert!(is_outside(resolve(sb.root(), &allowed, other.to_str().unwrap())));
+        assert!(is_outside(resolve(sb.root(), &allowed, sb.sibling.to_str().unwrap())));
+        assert!(!allowed.contains_dir(&sb.sibling));
+    }
+}
@@ -1,9 +1,12 @@
-use crate::types::{ContentFileEntry, GenericManifest, ResourceSummary};
+use crate::fs_utils::write_atomic;
+use crate::types::{ContentFileEntry, GenericManifest, RenameIdResult, ResourceSummary};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// 扫描数据目录，返回所有资源摘要
-pub fn scan_resources(data_dir: &str) -> Result<Vec<ResourceSummary>, String> {
+///
+/// `nested` 为 true 时额外识别 `{major}/{sub}/{id}/` 三级结构（不含 manifest.json 的子目录视为子分类目录）。
+pub fn scan_resources(data_dir: &str, nested: bool) -> Result<Vec<ResourceSummary>, String> {
     let data_path = Path::new(data_dir);
     eprintln!("[DEBUG] scan_resources: data_dir={}", data_dir);
     if !data_path.exists() {
@@ -54,6 +57,13 @@ pub fn scan_resources(data_dir: &str) -> Result<Vec<ResourceSummary>, String> {
                     if let Ok(summary) = read_resource_summary(&sub_path) {
                         resources.push(summary);
                     }
+                } else if nested && !is_hidden_dir(&sub_path) {
+                    // 分类/子分类/资源 三级结构
+                    for nested_dir in list_manifest_dirs(&sub_path) {
+                        if let Ok(summary) = read_resource_summary(&nested_dir) {
+                            resources.push(summary);
+                        }
+                    }
                 }
             }
         }
@@ -69,6 +79,70 @@ pub fn scan_resources(data_dir: &str) -> Result<Vec<ResourceSummary>, String> {
     Ok(resources)
 }
 
+/// 列出目录下直接包含 manifest.json 的子目录
+fn list_manifest_dirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() && path.join("manifest.json").exists() {
+                dirs.push(path);
+            }
+        }
+    }
+    dirs
+}
+
+fn is_hidden_dir(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.starts_with('_') || name.starts_with('.')
+}
+
+/// 收集分类目录下的全部资源目录（同时包含 `{major}/{id}` 与 `{major}/{sub}/{id}` 两种结构）
+pub(crate) fn collect_resource_dirs(category_dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(entries) = fs::read_dir(category_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() || is_hidden_dir(&path) {
+                continue;
+            }
+            if path.join("manifest.json").exists() {
+                dirs.push(path);
+            } else {
+                dirs.extend(list_manifest_dirs(&path));
+            }
+        }
+    }
+    dirs
+}
+
+/// 从资源目录向上查找数据根目录（包含 _meta.json 的目录）
+///
+/// 仓库没有 _meta.json 时，按 manifest 的 majorCategory 找到 `{major}/{id}` 或
+/// `{major}/{sub}/{id}` 中的分类目录，取其父目录；仍无法确定时返回 None。
+pub(crate) fn find_data_root(resource_dir: &Path) -> Option<PathBuf> {
+    if let Some(root) = resource_dir
+        .ancestors()
+        .skip(1)
+        .find(|p| p.join("_meta.json").exists())
+    {
+        return Some(root.to_path_buf());
+    }
+    let manifest = read_manifest(&resource_dir.to_string_lossy()).ok()?;
+    let major = manifest.get("majorCategory").and_then(|v| v.as_str())?;
+    if major.is_empty() {
+        return None;
+    }
+    resource_dir
+        .ancestors()
+        .skip(1)
+        .take(2)
+        .find(|p| p.file_name().is_some_and(|n| n == major))
+        .and_then(|p| p.parent())
+        .map(|p| p.to_path_buf())
+}
+
 /// 读取单个资源摘要
 fn read_resource_summary(resource_dir: &Path) -> Result<ResourceSummary, String> {
     let manifest_path = resource_dir.join("manifest.json");
@@ -90,6 +164,14 @@ pub fn read_manifest(resource_path: &str) -> Result<serde_json::Value, String> {
 /// 保存 manifest JSON
 pub fn save_manifest(resource_path: &str, manifest: serde_json::Value) -> Result<(), String> {
     let manifest_path = Path::new(resource_path).join("manifest.json");
+    if let Some(data_root) = find_data_root(Path::new(resource_path)) {
+        let meta = crate::category_ops::read_meta(&data_root.to_string_lossy())?;
+        crate::category_ops::validate_sub_category(
+            &meta,
+            manifest.get("majorCategory").and_then(|v| v.as_str()).unwrap_or(""),
+            manifest.get("subCategory").and_then(|v| v.as_str()).unwrap_or(""),
+        )?;
+    }
     let content = serde_json::to_string_pretty(&manifest)
         .map_err(|e| format!("序列化 manifest 失败: {}", e))?;
     fs::write(&manifest_path, content).map_err(|e| format!("写入 manifest 失败: {}", e))
@@ -103,12 +185,21 @@ pub fn create_resource(
     manifest: serde_json::Value,
     content_files: Vec<ContentFileEntry>,
 ) -> Result<String, String> {
+    crate::category_ops::validate_category_key(category)?;
+    crate::id_service::ensure_available(data_dir, id)?;
     let resource_dir = Path::new(data_dir).join(category).join(id);
 
     if resource_dir.exists() {
         return Err(format!("资源目录已存在: {}", resource_dir.display()));
     }
 
+    let meta = crate::category_ops::read_meta(data_dir)?;
+    crate::category_ops::validate_sub_category(
+        &meta,
+        manifest.get("majorCategory").and_then(|v| v.as_str()).unwrap_or(category),
+        manifest.get("subCategory").and_then(|v| v.as_str()).unwrap_or(""),
+    )?;
+
     // 自动计算同分类下的最大 order + 1
     let mut manifest = manifest;
     let category_dir = Path::new(data_dir).join(category);
@@ -242,10 +333,16 @@ pub fn batch_set_enabled(resource_paths: &[String], enabled: bool) -> Result<u32
 }
 
 /// 批量移动分类
+///
+/// - `new_sub_category` 为 Some 时同时改写 subCategory（传空字符串表示清除子分类）
+/// - `nested` 为 true 且子分类非空时，目录移动到 `{major}/{sub}/{id}`，否则为 `{major}/{id}`
 pub fn batch_move_category(
     resource_paths: &[String],
     new_category: &str,
+    new_sub_category: Option<&str>,
+    nested: bool,
 ) -> Result<u32, String> {
+    crate::category_ops::validate_category_key(new_category)?;
     let mut count = 0u32;
     for resource_path in resource_paths {
         let manifest_path = Path::new(resource_path).join("manifest.json");
@@ -257,11 +354,47 @@ pub fn batch_move_category(
         let mut manifest: serde_json::Value =
             serde_json::from_str(&content).map_err(|e| format!("解析失败: {}", e))?;
 
+        let old_path = Path::new(resource_path);
+        let data_dir = find_data_root(old_path).ok_or("无法获取数据目录")?;
+
+        // 未指定子分类时沿用原值，但仍需校验其在新大类下是否存在
+        let sub_category = match new_sub_category {
+            Some(sub) => sub.to_string(),
+            None => manifest
+                .get("subCategory")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+        };
+        // 子分类会拼进目标路径，_meta.json 未定义该大类时也必须校验
+        if !sub_category.is_empty() {
+            crate::category_ops::validate_category_key(&sub_category)?;
+        }
+        let meta = crate::category_ops::read_meta(&data_dir.to_string_lossy())?;
+        crate::category_ops::validate_sub_category(&meta, new_category, &sub_category)?;
+
+        // 先确定目标目录并检查冲突，再改写 manifest
+        let resource_name = old_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let mut new_dir = data_dir.join(new_category);
+        if nested && !sub_category.is_empty() {
+            new_dir = new_dir.join(&sub_category);
+        }
+        let new_dir = new_dir.join(&resource_name);
+        let moving = new_dir != old_path;
+        if moving && new_dir.exists() {
+            return Err(format!("目标位置已存在同名资源: {}", new_dir.display()));
+        }
+
         if let Some(obj) = manifest.as_object_mut() {
             obj.insert(
                 "majorCategory".to_string(),
                 serde_json::json!(new_category),
             );
+            obj.insert("subCategory".to_string(), serde_json::json!(sub_category));
             obj.insert(
                 "updatedAt".to_string(),
                 serde_json::json!(chrono::Utc::now().to_rfc3339()),
@@ -270,25 +403,19 @@ pub fn batch_move_category(
 
         let new_content = serde_json::to_string_pretty(&manifest)
             .map_err(|e| format!("序列化失败: {}", e))?;
-        fs::write(&manifest_path, new_content).map_err(|e| format!("写入失败: {}", e))?;
-
-        // 移动目录到新分类下
-        let old_path = Path::new(resource_path);
-        let resource_name = old_path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        let data_dir = old_path
-            .parent()
-            .and_then(|p| p.parent())
-            .ok_or("无法获取数据目录")?;
-        let new_dir = data_dir.join(new_category).join(&resource_name);
-
-        if new_dir != old_path {
-            fs::create_dir_all(new_dir.parent().unwrap_or(data_dir))
-                .map_err(|e| format!("创建目录失败: {}", e))?;
-            fs::rename(old_path, &new_dir).map_err(|e| format!("移动目录失败: {}", e))?;
+        write_atomic(&manifest_path, &new_content)?;
+
+        // 移动目录到新分类（及子分类）下，失败时恢复原 manifest
+        if moving {
+            let moved = fs::create_dir_all(new_dir.parent().unwrap_or(&data_dir))
+                .map_err(|e| format!("创建目录失败: {}", e))
+                .and_then(|_| {
+                    fs::rename(old_path, &new_dir).map_err(|e| format!("移动目录失败: {}", e))
+                });
+            if let Err(e) = moved {
+                let _ = write_atomic(&manifest_path, &content);
+                return Err(e);
+            }
         }
 
         count += 1;
@@ -315,16 +442,8 @@ pub fn reindex_all_orders(data_dir: &str) -> Result<u32, String> {
             continue;
         }
 
-        // 收集分类下的资源目录
-        let mut resource_dirs: Vec<std::path::PathBuf> = Vec::new();
-        if let Ok(sub_entries) = fs::read_dir(&cat_path) {
-            for sub_entry in sub_entries.flatten() {
-                let sub_path = sub_entry.path();
-                if sub_path.is_dir() && sub_path.join("manifest.json").exists() {
-                    resource_dirs.push(sub_path);
-                }
-            }
-        }
+        // 收集分类下的资源目录（含子分类目录中的资源）
+        let mut resource_dirs = collect_resource_dirs(&cat_path);
 
         if resource_dirs.is_empty() {
             continue;
@@ -363,3 +482,272 @@ fn read_manifest_name(resource_dir: &Path) -> Option<String> {
     let manifest: serde_json::Value = serde_json::from_str(&content).ok()?;
     manifest.get("name").and_then(|v| v.as_str()).map(|s| s.to_string())
 }
+
+/// 收集数据目录下所有分类中的资源目录
+pub(crate) fn collect_all_resource_dirs(data_dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let Ok(entries) = fs::read_dir(data_dir) else {
+        return dirs;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() || is_hidden_dir(&path) {
+            continue;
+        }
+        if path.join("manifest.json").exists() {
+            dirs.push(path);
+        } else {
+            dirs.extend(collect_resource_dirs(&path));
+        }
+    }
+    dirs
+}
+
+/// 收集数据目录下所有资源的 ID（manifest 中的 id 与目录名都计入）
+pub(crate) fn collect_all_resource_ids(data_dir: &Path) -> std::collections::HashSet<String> {
+    let mut ids = std::collections::HashSet::new();
+    for dir in collect_all_resource_dirs(data_dir) {
+        ids.insert(dir.file_name().unwrap_or_default().to_string_lossy().to_string());
+        if let Ok(m) = read_manifest(&dir.to_string_lossy()) {
+            if let Some(id) = m.get("id").and_then(|v| v.as_str()) {
+                ids.insert(id.to_string());
+            }
+        }
+    }
+    ids
+}
+
+/// 复制资源：深拷贝整个资源目录（manifest + 全部内容文件与嵌套资源）到目标分类
+///
+/// 新资源使用不重复的 `{id}-copy[-N]` 作为 ID，名称追加「(副本)」，重置时间戳并标记为 custom；
+/// 复制到同一分类时 order 紧跟原资源，其后的资源顺延。返回新资源目录路径。
+pub fn duplicate_resource(
+    data_dir: &str,
+    resource_path: &str,
+    target_category: Option<&str>,
+) -> Result<String, String> {
+    let data_path = Path::new(data_dir);
+    let source_dir = Path::new(resource_path);
+    let mut manifest = read_manifest(resource_path)?;
+
+    let source_id = manifest
+        .get("id")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| source_dir.file_name().unwrap_or_default().to_string_lossy().to_string());
+    let source_category = manifest
+        .get("majorCategory")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    if let Some(target) = target_category {
+        crate::category_ops::validate_category_key(target)?;
+    }
+    let target_category = target_category.unwrap_or(&source_category).to_string();
+    let same_category = target_category == source_category;
+    let source_order = manifest.get("order").and_then(|v| v.as_i64()).unwrap_or(0);
+
+    // 生成全仓库唯一的新 ID
+    let new_id = crate::id_service::make_unique(
+        &format!("{}-copy", source_id),
+        &crate::id_service::existing_ids(data_dir),
+    );
+
+    // 同分类复制时保留原有的子分类目录层级
+    let target_dir = if same_category {
+        source_dir.parent().unwrap_or(data_path).join(&new_id)
+    } else {
+        data_path.join(&target_category).join(&new_id)
+    };
+    let target_category_dir = data_path.join(&target_category);
+
+    let siblings = collect_resource_dirs(&target_category_dir);
+    crate::fs_utils::copy_dir_recursive(source_dir, &target_dir)?;
+
+    // 计算 order：同分类紧跟原资源（其后的资源顺延），跨分类追加到末尾
+    let new_order = if same_category {
+        if let Err(e) = shift_orders_after(&siblings, source_order) {
+            let _ = fs::remove_dir_all(&target_dir);
+            return Err(e);
+        }
+        source_order + 1
+    } else {
+        siblings
+            .iter()
+            .filter_map(|d| read_manifest(&d.to_string_lossy()).ok())
+            .filter_map(|m| m.get("order").and_then(|v| v.as_i64()))
+            .max()
+            .unwrap_or(-1)
+            + 1
+    };
+
+    // 跨分类时原有子分类可能不属于目标分类，直接清空
+    if !same_category {
+        let meta = crate::category_ops::read_meta(data_dir)?;
+        let sub = manifest.get("subCategory").and_then(|v| v.as_str()).unwrap_or("");
+        if crate::category_ops::validate_sub_category(&meta, &target_category, sub).is_err() {
+            if let Some(obj) = manifest.as_object_mut() {
+                obj.insert("subCategory".to_string(), serde_json::json!(""));
+            }
+        }
+    }
+
+    let name = manifest.get("name").and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    if let Some(obj) = manifest.as_object_mut() {
+        obj.insert("id".to_string(), serde_json::json!(new_id));
+        obj.insert("name".to_string(), serde_json::json!(format!("{}(副本)", name)));
+        obj.insert("majorCategory".to_string(), serde_json::json!(target_category));
+        obj.insert("order".to_string(), serde_json::json!(new_order));
+        obj.insert("source".to_string(), serde_json::json!("custom"));
+        obj.insert("createdAt".to_string(), serde_json::json!(now));
+        obj.insert("updatedAt".to_string(), serde_json::json!(now));
+        // 历史 ID 只属于原资源，副本不能继承，否则旧 ID 会解析出两个资源
+        obj.remove("aliases");
+    }
+    let target_path = target_dir.to_string_lossy().to_string();
+    if let Err(e) = save_manifest(&target_path, manifest) {
+        let _ = fs::remove_dir_all(&target_dir);
+        return Err(e);
+    }
+
+    Ok(target_path)
+}
+
+/// 将 order 大于 after 的资源整体后移一位，为插入腾出位置
+fn shift_orders_after(resource_dirs: &[PathBuf], after: i64) -> Result<(), String> {
+    for dir in resource_dirs {
+        let path = dir.to_string_lossy();
+        let mut manifest = read_manifest(&path)?;
+        let order = manifest.get("order").and_then(|v| v.as_i64()).unwrap_or(0);
+        if order > after {
+            if let Some(obj) = manifest.as_object_mut() {
+                obj.insert("order".to_string(), serde_json::json!(order + 1));
+            }
+            save_manifest(&path, manifest)?;
+        }
+    }
+    Ok(())
+}
+
+// ============================================================
+// 资源 ID 重命名
+// ============================================================
+
+/// 会被视为「引用其他资源 ID」的 manifest 字段（数组或字符串，任意嵌套层级）
+const REFERENCE_FIELDS: &[&str] = &["roles", "exampleResources", "relatedResources", "dependencies"];
+
+/// 将 value 中引用字段里等于 old_id 的值替换为 new_id，返回替换次数
+pub(crate) fn replace_references(value: &mut serde_json::Value, old_id: &str, new_id: &str) -> u32 {
+    let mut count = 0;
+    match value {
+        serde_json::Value::Object(obj) => {
+            for (key, v) in obj.iter_mut() {
+                if REFERENCE_FIELDS.contains(&key.as_str()) {
+                    match v {
+                        serde_json::Value::String(s) if s == old_id => {
+                            *s = new_id.to_string();
+                            count += 1;
+                        }
+                        serde_json::Value::Array(items) => {
+                            for item in items.iter_mut() {
+                                if item.as_str() == Some(old_id) {
+                                    *item = serde_json::json!(new_id);
+                                    count += 1;
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                count += replace_references(v, old_id, new_id);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items.iter_mut() {
+                count += replace_references(item, old_id, new_id);
+            }
+        }
+        _ => {}
+    }
+    count
+}
+
+/// 重命名资源 ID：重命名资源目录、更新 manifest 的 id，并改写仓库内其他资源对旧 ID 的引用
+///
+/// `keep_alias` 为 true 时旧 ID 记入 manifest 的 `aliases` 列表，供下游仍按旧 ID 解析。
+pub fn rename_resource_id(
+    data_dir: &str,
+    resource_path: &str,
+    new_id: &str,
+    keep_alias: bool,
+) -> Result<RenameIdResult, String> {
+    let new_id = new_id.trim();
+    crate::id_service::validate_id(new_id)?;
+
+    let data_path = Path::new(data_dir);
+    let old_dir = Path::new(resource_path);
+    let mut manifest = read_manifest(resource_path)?;
+    let old_id = manifest
+        .get("id")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| old_dir.file_name().unwrap_or_default().to_string_lossy().to_string());
+    if old_id == new_id {
+        return Ok(RenameIdResult {
+            new_path: resource_path.to_string(),
+            updated_references: 0,
+        });
+    }
+
+    crate::id_service::ensure_available(data_dir, new_id)?;
+    let new_dir = old_dir.parent().ok_or("无法获取分类目录")?.join(new_id);
+    if new_dir.exists() {
+        return Err(format!("资源目录已存在: {}", new_dir.display()));
+    }
+
+    fs::rename(old_dir, &new_dir).map_err(|e| format!("重命名资源目录失败: {}", e))?;
+
+    if let Some(obj) = manifest.as_object_mut() {
+        obj.insert("id".to_string(), serde_json::json!(new_id));
+        let mut aliases: Vec<String> = obj
+            .get("aliases")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default();
+        aliases.retain(|a| a != new_id);
+        if keep_alias && !aliases.contains(&old_id) {
+            aliases.push(old_id.clone());
+        }
+        if aliases.is_empty() {
+            obj.remove("aliases");
+        } else {
+            obj.insert("aliases".to_string(), serde_json::json!(aliases));
+        }
+        obj.insert(
+            "updatedAt".to_string(),
+            serde_json::json!(chrono::Utc::now().to_rfc3339()),
+        );
+    }
+    let new_path = new_dir.to_string_lossy().to_string();
+    if let Err(e) = save_manifest(&new_path, manifest) {
+        let _ = fs::rename(&new_dir, old_dir);
+        return Err(e);
+    }
+
+    // 改写其他资源中对旧 ID 的引用
+    let mut updated_references = 0u32;
+    for dir in collect_all_resource_dirs(data_path).iter().filter(|d| **d != new_dir) {
+        let Ok(mut m) = read_manifest(&dir.to_string_lossy()) else {
+            continue;
+        };
+        if replace_references(&mut m, &old_id, new_id) > 0 {
+            let content = serde_json::to_string_pretty(&m)
+                .map_err(|e| format!("序列化 manifest 失败: {}", e))?;
+            fs::write(dir.join("manifest.json"), content)
+                .map_err(|e| format!("写入 manifest 失败: {}", e))?;
+            updated_references += 1;
+        }
+    }
+
+    Ok(RenameIdResult { new_path, updated_references })
+}
@@ -0,0 +1,436 @@
+//! API Key 加密存储
+//!
+//! Key 以 `enc:v1:` 前缀 + base64(nonce ‖ 密文) 的形式保存在服务列表 JSON 中，
+//! 使用 ChaCha20-Poly1305 认证加密，附加数据为调用方给出的上下文（服务 ID 等），
+//! 密文被挪用到其他条目时解密失败。
+//!
+//! 加密密钥有两种来源：
+//! - 密钥文件（默认）：首次使用时生成随机密钥，保存在 `~/.aidocplus/manager-secret.json`；
+//! - 口令：密钥由 Argon2id 从用户口令派生，不落盘，每次启动后需调用 [`unlock`] 解锁。
+
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// 加密值前缀，未带前缀的值视为明文（旧版本文件）
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// 口令模式下尚未解锁时的错误前缀
+pub const LOCKED_ERROR: &str = "[LOCKED]";
+
+const KEY_FILE_NAME: &str = "manager-secret.json";
+const KEY_FILE_VERSION: u32 = 1;
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+
+/// 用于校验口令是否正确的固定明文
+const CHECK_PLAINTEXT: &str = "aidocplus";
+const CHECK_CONTEXT: &str = "secret-check";
+
+/// 口令模式解锁后的密钥，仅保存在内存中
+static SESSION_KEY: Mutex<Option<SecretKey>> = Mutex::new(None);
+
+/// 串行化密钥文件的首次创建，避免并发调用各自生成不同的密钥
+static KEY_CREATION: Mutex<()> = Mutex::new(());
+
+// ============================================================
+// 类型定义
+// ============================================================
+
+/// 加密密钥来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyMode {
+    #[serde(rename = "keyFile")]
+    KeyFile,
+    #[serde(rename = "passphrase")]
+    Passphrase,
+}
+
+/// 密钥文件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyFileData {
+    pub version: u32,
+    pub mode: KeyMode,
+    /// 密钥文件模式下的随机密钥（base64）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub key: String,
+    /// 口令模式下的 Argon2 盐（base64）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub salt: String,
+    /// 用派生密钥加密的校验值，用于判断口令是否正确
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub check: String,
+}
+
+/// 256 位对称密钥
+#[derive(Clone)]
+pub struct SecretKey([u8; 32]);
+
+impl std::fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretKey(..)")
+    }
+}
+
+/// 加密状态（供设置界面显示）
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretStatus {
+    pub mode: KeyMode,
+    /// 口令模式且尚未解锁
+    pub locked: bool,
+}
+
+// ============================================================
+// 密钥管理
+// ============================================================
+
+pub fn key_file_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("无法获取用户主目录")?;
+    Ok(home.join(".aidocplus").join(KEY_FILE_NAME))
+}
+
+fn read_key_file() -> Result<Option<KeyFileData>, String> {
+    let path = key_file_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("读取密钥文件失败: {}", e))?;
+    let data: KeyFileData =
+        serde_json::from_str(&content).map_err(|e| format!("解析密钥文件失败: {}", e))?;
+    if data.version > KEY_FILE_VERSION {
+        return Err(format!("不支持的密钥文件版本: {}", data.version));
+    }
+    Ok(Some(data))
+}
+
+/// 写入密钥文件，返回被替换的旧内容（用于失败时回滚）
+fn write_key_file(data: &KeyFileData) -> Result<Option<String>, String> {
+    let path = key_file_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    let previous = std::fs::read_to_string(&path).ok();
+    let content =
+        serde_json::to_string_pretty(data).map_err(|e| format!("序列化密钥文件失败: {}", e))?;
+    write_private_atomic(&path, &content)?;
+    Ok(previous)
+}
+
+/// 原子写入仅当前用户可读写的文件
+///
+/// 临时文件在写入密钥之前就以 0600 权限创建，密钥任何时候都不会以默认 umask 权限落盘。
+fn write_private_atomic(path: &std::path::Path, content: &str) -> Result<(), String> {
+    use std::io::Write;
+    let tmp_path = path.with_file_name(format!(".{}.tmp", KEY_FILE_NAME));
+    // 残留的临时文件可能权限更宽，删除后重新创建
+    let _ = std::fs::remove_file(&tmp_path);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options.open(&tmp_path).and_then(|mut file| {
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(format!("写入密钥文件失败: {}", e));
+    }
+    std::fs::rename(&tmp_path, path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp_path);
+        format!("写入密钥文件失败: {}", e)
+    })
+}
+
+/// 当前使用的密钥；密钥文件不存在时生成新的随机密钥
+pub fn current_key() -> Result<SecretKey, String> {
+    let data = match read_key_file()? {
+        Some(data) => data,
+        None => {
+            let _guard = KEY_CREATION.lock().map_err(|_| "密钥状态异常".to_string())?;
+            // 等待期间其他调用方可能已经创建了密钥文件，必须沿用它
+            match read_key_file()? {
+                Some(data) => data,
+                None => {
+                    let (data, key) = prepare_key(None)?;
+                    write_key_file(&data)?;
+                    return Ok(key);
+                }
+            }
+        }
+    };
+    match data.mode {
+        KeyMode::KeyFile => decode_key(&data.key),
+        KeyMode::Passphrase => SESSION_KEY
+            .lock()
+            .ok()
+            .and_then(|k| k.clone())
+            .ok_or_else(|| format!("{} API Key 已用口令加密，请先解锁", LOCKED_ERROR)),
+    }
+}
+
+pub fn status() -> Result<SecretStatus, String> {
+    let mode = read_key_file()?.map(|d| d.mode).unwrap_or(KeyMode::KeyFile);
+    let locked = mode == KeyMode::Passphrase
+        && SESSION_KEY.lock().map(|k| k.is_none()).unwrap_or(true);
+    Ok(SecretStatus { mode, locked })
+}
+
+/// 用口令解锁（仅口令模式），口令错误时返回错误
+pub fn unlock(passphrase: &str) -> Result<(), String> {
+    let data = read_key_file()?.ok_or("尚未设置加密口令")?;
+    if data.mode != KeyMode::Passphrase {
+        return Err("尚未设置加密口令".to_string());
+    }
+    let salt = BASE64
+        .decode(&data.salt)
+        .map_err(|e| format!("密钥文件已损坏: {}", e))?;
+    let key = derive_key(passphrase, &salt)?;
+    match open_with(&key, &data.check, CHECK_CONTEXT) {
+        Ok(check) if check == CHECK_PLAINTEXT => {}
+        _ => return Err("口令错误".to_string()),
+    }
+    if let Ok(mut session) = SESSION_KEY.lock() {
+        *session = Some(key);
+    }
+    Ok(())
+}
+
+/// 清除内存中的口令密钥
+pub fn lock() {
+    if let Ok(mut session) = SESSION_KEY.lock() {
+        *session = None;
+    }
+}
+
+/// 生成新的密钥材料（尚未保存）：`passphrase` 为 None 时使用随机密钥文件
+pub fn prepare_key(passphrase: Option<&str>) -> Result<(KeyFileData, SecretKey), String> {
+    match passphrase {
+        None => {
+            let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+            let data = KeyFileData {
+                version: KEY_FILE_VERSION,
+                mode: KeyMode::KeyFile,
+                key: BASE64.encode(key),
+                salt: String::new(),
+                check: String::new(),
+            };
+            Ok((data, SecretKey(key.into())))
+        }
+        Some(passphrase) => {
+            if passphrase.is_empty() {
+                return Err("口令不能为空".to_string());
+            }
+            let mut salt = [0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            let key = derive_key(passphrase, &salt)?;
+            let data = KeyFileData {
+                version: KEY_FILE_VERSION,
+                mode: KeyMode::Passphrase,
+                key: String::new(),
+                salt: BASE64.encode(salt),
+                check: seal_with(&key, CHECK_PLAINTEXT, CHECK_CONTEXT)?,
+            };
+            Ok((data, key))
+        }
+    }
+}
+
+/// 保存新的密钥文件并切换当前密钥，返回旧密钥文件内容（用于 [`restore_key_file`] 回滚）
+pub fn install_key(data: &KeyFileData, key: &SecretKey) -> Result<Option<String>, String> {
+    let previous = write_key_file(data)?;
+    if let Ok(mut session) = SESSION_KEY.lock() {
+        *session = (data.mode == KeyMode::Passphrase).then(|| key.clone());
+    }
+    Ok(previous)
+}
+
+/// 回滚到 [`install_key`] 之前的密钥文件
+pub fn restore_key_file(previous: Option<String>) -> Result<(), String> {
+    let path = key_file_path()?;
+    match previous {
+        Some(content) => write_private_atomic(&path, &content)?,
+        None => {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+    lock();
+    Ok(())
+}
+
+fn decode_key(encoded: &str) -> Result<SecretKey, String> {
+    let bytes = BASE64
+        .decode(encoded)
+        .map_err(|e| format!("密钥文件已损坏: {}", e))?;
+    let key: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| "密钥文件已损坏: 密钥长度错误".to_string())?;
+    Ok(SecretKey(key))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<SecretKey, String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("派生密钥失败: {}", e))?;
+    Ok(SecretKey(key))
+}
+
+// ============================================================
+// 加密 / 解密
+// ============================================================
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// 用指定密钥加密；空字符串保持为空
+pub fn seal_with(key: &SecretKey, plaintext: &str, context: &str) -> Result<String, String> {
+    if plaintext.is_empty() {
+        return Ok(String::new());
+    }
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&key.0));
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let payload = Payload {
+        msg: plaintext.as_bytes(),
+        aad: context.as_bytes(),
+    };
+    let ciphertext = cipher
+        .encrypt(&nonce, payload)
+        .map_err(|_| "加密失败".to_string())?;
+    let mut bytes = nonce.to_vec();
+    bytes.extend_from_slice(&ciphertext);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, BASE64.encode(bytes)))
+}
+
+/// 用指定密钥解密；未加密的值原样返回
+pub fn open_with(key: &SecretKey, value: &str, context: &str) -> Result<String, String> {
+    let Some(encoded) = value.strip_prefix(ENCRYPTED_PREFIX) else {
+        return Ok(value.to_string());
+    };
+    let bytes = BASE64
+        .decode(encoded)
+        .map_err(|e| format!("加密数据已损坏: {}", e))?;
+    if bytes.len() <= NONCE_LEN {
+        return Err("加密数据已损坏".to_string());
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&key.0));
+    let payload = Payload {
+        msg: ciphertext,
+        aad: context.as_bytes(),
+    };
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), payload)
+        .map_err(|_| "解密失败：密钥不匹配或数据已被篡改".to_string())?;
+    String::from_utf8(plaintext).map_err(|_| "解密失败：内容不是有效的 UTF-8".to_string())
+}
+
+/// 用当前密钥加密
+pub fn seal(plaintext: &str, context: &str) -> Result<String, String> {
+    if plaintext.is_empty() {
+        return Ok(String::new());
+    }
+    seal_with(&current_key()?, plaintext, context)
+}
+
+/// 用当前密钥解密；明文值不需要密钥，直接返回
+pub fn open(value: &str, context: &str) -> Result<String, String> {
+    if !is_encrypted(value) {
+        return Ok(value.to_string());
+    }
+    open_with(&current_key()?, value, context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key(byte: u8) -> SecretKey {
+        SecretKey([byte; 32])
+    }
+
+    /// 解码密文、修改后重新编码
+    fn reencode(value: &str, edit: impl FnOnce(&mut Vec<u8>)) -> String {
+        let mut bytes = BASE64.decode(value.strip_prefix(ENCRYPTED_PREFIX).unwrap()).unwrap();
+        edit(&mut bytes);
+        format!("{}{}", ENCRYPTED_PREFIX, BASE64.encode(bytes))
+    }
+
+    #[test]
+    fn seal_and_open_round_trip() {
+        let key = test_key(1);
+        let sealed = seal_with(&key, "sk-测试-123", "svc:a").unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.contains("sk-"));
+        assert_eq!(open_with(&key, &sealed, "svc:a").unwrap(), "sk-测试-123");
+        // 每次加密使用新的 nonce
+        assert_ne!(seal_with(&key, "sk-测试-123", "svc:a").unwrap(), sealed);
+    }
+
+    #[test]
+    fn open_fails_with_wrong_context_or_key() {
+        let sealed = seal_with(&test_key(1), "secret", "svc:a").unwrap();
+        assert!(open_with(&test_key(1), &sealed, "svc:b").is_err());
+        assert!(open_with(&test_key(2), &sealed, "svc:a").is_err());
+    }
+
+    #[test]
+    fn open_rejects_truncated_or_tampered_ciphertext() {
+        let key = test_key(1);
+        let sealed = seal_with(&key, "secret", "ctx").unwrap();
+
+        let tampered = reencode(&sealed, |b| *b.last_mut().unwrap() ^= 1);
+        assert!(open_with(&key, &tampered, "ctx").is_err());
+        let truncated = reencode(&sealed, |b| b.truncate(b.len() - 1));
+        assert!(open_with(&key, &truncated, "ctx").is_err());
+        let nonce_only = reencode(&sealed, |b| b.truncate(NONCE_LEN));
+        assert!(open_with(&key, &nonce_only, "ctx").is_err());
+        let not_base64 = format!("{}@@@", ENCRYPTED_PREFIX);
+        assert!(open_with(&key, &not_base64, "ctx").is_err());
+    }
+
+    #[test]
+    fn plaintext_and_empty_values_pass_through() {
+        let key = test_key(1);
+        assert_eq!(open_with(&key, "sk-plain", "ctx").unwrap(), "sk-plain");
+        assert_eq!(open_with(&key, "", "ctx").unwrap(), "");
+        assert_eq!(seal_with(&key, "", "ctx").unwrap(), "");
+        assert!(!is_encrypted("sk-plain"));
+    }
+
+    #[test]
+    fn passphrase_check_detects_wrong_passphrase() {
+        let (data, key) = prepare_key(Some("correct horse")).unwrap();
+        assert_eq!(data.mode, KeyMode::Passphrase);
+        assert!(data.key.is_empty());
+        let salt = BASE64.decode(&data.salt).unwrap();
+        assert_eq!(salt.len(), SALT_LEN);
+
+        let derived = derive_key("correct horse", &salt).unwrap();
+        assert_eq!(derived.0, key.0);
+        assert_eq!(open_with(&derived, &data.check, CHECK_CONTEXT).unwrap(), CHECK_PLAINTEXT);
+        let wrong = derive_key("wrong horse", &salt).unwrap();
+        assert!(open_with(&wrong, &data.check, CHECK_CONTEXT).is_err());
+        assert!(prepare_key(Some("")).is_err());
+    }
+
+    #[test]
+    fn key_file_mode_key_round_trips_through_encoding() {
+        let (data, key) = prepare_key(None).unwrap();
+        assert_eq!(data.mode, KeyMode::KeyFile);
+        assert!(data.check.is_empty());
+        assert_eq!(decode_key(&data.key).unwrap().0, key.0);
+        assert!(decode_key(&BASE64.encode([0u8; 16])).is_err());
+    }
+}
@@ -0,0 +1,833 @@
+use crate::import_export::ImportFailure;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// ============================================================
+// 表格导出与批量编辑导入（CSV / XLSX）
+// ============================================================
+//
+// 每个资源一行，列为 id、category、name、description、tags、enabled、
+// i18n.{语言}.{name|description}，可选 content。导入时按 id 匹配现有资源，
+// 只比较表格中出现的列，生成字段级差异；确认后通过常规保存路径写回。
+// CSV 中以 `=`、`+`、`-`、`@` 开头的单元格导出时前置 `'`，避免 Excel 当作公式执行，导入时去掉。
+
+/// 固定列
+const BASE_COLUMNS: [&str; 6] = ["id", "category", "name", "description", "tags", "enabled"];
+/// JSON 文件模式模板没有启用状态与多语言字段
+const JSON_MODE_COLUMNS: [&str; 5] = ["id", "category", "name", "description", "tags"];
+const CONTENT_COLUMN: &str = "content";
+const I18N_PREFIX: &str = "i18n.";
+const I18N_FIELDS: [&str; 2] = ["name", "description"];
+/// XLSX 单元格字符数上限
+const XLSX_CELL_LIMIT: usize = 32767;
+/// 导入表格文件大小上限
+const MAX_SHEET_SIZE: u64 = 64 * 1024 * 1024;
+/// Excel 打开 CSV 时会把以这些字符开头的单元格当作公式
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// 表格格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SheetFormat {
+    Csv,
+    Xlsx,
+}
+
+impl SheetFormat {
+    /// 按扩展名识别：.xlsx / .xls 为 XLSX（.xls 只能读取），其余按 CSV 处理
+    pub fn from_path(path: &str) -> Self {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".xlsx") || lower.ends_with(".xls") {
+            SheetFormat::Xlsx
+        } else {
+            SheetFormat::Csv
+        }
+    }
+}
+
+/// 表格中的一行：列名 → 单元格文本
+type SheetRow = BTreeMap<String, String>;
+
+/// 表格导出选项
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct SheetExportOptions {
+    /// 未指定时按输出文件扩展名决定
+    #[serde(default)]
+    pub format: Option<SheetFormat>,
+    /// 是否附带 content 列
+    #[serde(default, rename = "includeContent")]
+    pub include_content: bool,
+    /// 目录模式 content 列对应的内容文件；为 .json 文件时读写其中的 content 字段
+    #[serde(default, rename = "contentFile")]
+    pub content_file: Option<String>,
+}
+
+/// 表格导入选项
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct SheetImportOptions {
+    /// 目录模式 content 列对应的内容文件
+    #[serde(default, rename = "contentFile")]
+    pub content_file: Option<String>,
+    /// 只计算差异，不写盘
+    #[serde(default, rename = "dryRun")]
+    pub dry_run: bool,
+}
+
+/// 单个字段的变更
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// 一行（一个资源）的变更
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SheetRowDiff {
+    pub id: String,
+    /// 资源路径（目录模式为目录，JSON 文件模式为 `category::id`）
+    pub path: String,
+    pub changes: Vec<FieldChange>,
+}
+
+/// 表格导入结果（dry-run 时为差异预览）
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SheetImportResult {
+    pub changed: Vec<SheetRowDiff>,
+    pub unchanged: u32,
+    /// 表格中存在、仓库中找不到的 ID
+    #[serde(rename = "notFound")]
+    pub not_found: Vec<String>,
+    /// 当前数据模式不支持或无法识别的列
+    #[serde(rename = "ignoredColumns")]
+    pub ignored_columns: Vec<String>,
+    pub failed: Vec<ImportFailure>,
+    /// 实际写回的资源 ID（dry-run 时为空）
+    pub applied: Vec<String>,
+    #[serde(rename = "dryRun")]
+    pub dry_run: bool,
+}
+
+/// 表格行对应的资源
+enum SheetTarget {
+    Directory(PathBuf),
+    Template { category: String, id: String },
+}
+
+impl SheetTarget {
+    fn path(&self) -> String {
+        match self {
+            SheetTarget::Directory(dir) => dir.to_string_lossy().to_string(),
+            SheetTarget::Template { category, id } => format!("{}::{}", category, id),
+        }
+    }
+}
+
+// ============================================================
+// 导出
+// ============================================================
+
+/// 导出资源为 CSV / XLSX 表格（目录模式）
+pub fn export_sheet(
+    resource_paths: &[String],
+    output_path: &str,
+    options: &SheetExportOptions,
+) -> Result<String, String> {
+    let content_file = options
+        .content_file
+        .as_deref()
+        .filter(|_| options.include_content);
+    if let Some(name) = content_file {
+        crate::import_export::validate_content_file_name(name)?;
+    }
+    let mut rows = Vec::new();
+    let mut languages = BTreeSet::new();
+    for resource_path in resource_paths {
+        let row = read_directory_row(Path::new(resource_path), content_file)?;
+        languages.extend(
+            row.keys()
+                .filter_map(|k| parse_i18n_column(k))
+                .map(|(lang, _)| lang.to_string()),
+        );
+        rows.push(row);
+    }
+
+    let mut columns: Vec<String> = BASE_COLUMNS.iter().map(|c| c.to_string()).collect();
+    for lang in &languages {
+        for field in I18N_FIELDS {
+            columns.push(format!("{}{}.{}", I18N_PREFIX, lang, field));
+        }
+    }
+    if options.include_content {
+        columns.push(CONTENT_COLUMN.to_string());
+    }
+    write_sheet(&columns, &rows, output_path, options.format)
+}
+
+/// 导出 JSON 文件模式的模板为 CSV / XLSX 表格（template_paths 为 `category::id`）
+pub fn export_json_templates_sheet(
+    data_dir: &str,
+    template_paths: &[String],
+    output_path: &str,
+    options: &SheetExportOptions,
+) -> Result<String, String> {
+    let mut rows = Vec::new();
+    for path in template_paths {
+        let (category, id) = path
+            .split_once("::")
+            .ok_or_else(|| format!("无效的模板路径: {}", path))?;
+        rows.push(read_template_row(data_dir, category, id)?);
+    }
+
+    let mut columns: Vec<String> = JSON_MODE_COLUMNS.iter().map(|c| c.to_string()).collect();
+    if options.include_content {
+        columns.push(CONTENT_COLUMN.to_string());
+    }
+    write_sheet(&columns, &rows, output_path, options.format)
+}
+
+/// 按列写出表格；CSV 带 UTF-8 BOM，便于 Excel 正确识别中文。XLSX 只能写入 .xlsx 文件
+fn write_sheet(
+    columns: &[String],
+    rows: &[SheetRow],
+    output_path: &str,
+    format: Option<SheetFormat>,
+) -> Result<String, String> {
+    let cell = |row: &SheetRow, column: &str| {
+        row.get(column).cloned().unwrap_or_default()
+    };
+    let format = format.unwrap_or_else(|| SheetFormat::from_path(output_path));
+    if format == SheetFormat::Xlsx && !output_path.to_lowercase().ends_with(".xlsx") {
+        return Err("XLSX 表格只能导出为 .xlsx 文件（不支持旧版 .xls）".to_string());
+    }
+    let result = match format {
+        SheetFormat::Csv => (|| -> Result<(), String> {
+            let mut file =
+                fs::File::create(output_path).map_err(|e| format!("创建表格文件失败: {}", e))?;
+            file.write_all("\u{feff}".as_bytes())
+                .map_err(|e| format!("写入表格文件失败: {}", e))?;
+            let mut writer = csv::Writer::from_writer(file);
+            writer
+                .write_record(columns)
+                .map_err(|e| format!("写入表格文件失败: {}", e))?;
+            for row in rows {
+                writer
+                    .write_record(columns.iter().map(|c| escape_csv_cell(cell(row, c))))
+                    .map_err(|e| format!("写入表格文件失败: {}", e))?;
+            }
+            writer.flush().map_err(|e| format!("写入表格文件失败: {}", e))
+        })(),
+        SheetFormat::Xlsx => (|| -> Result<(), String> {
+            let mut workbook = rust_xlsxwriter::Workbook::new();
+            let header = rust_xlsxwriter::Format::new().set_bold();
+            let sheet = workbook.add_worksheet();
+            let xlsx_err = |e: rust_xlsxwriter::XlsxError| format!("写入 XLSX 失败: {}", e);
+            for (col, name) in columns.iter().enumerate() {
+                sheet
+                    .write_string_with_format(0, col as u16, name, &header)
+                    .map_err(xlsx_err)?;
+            }
+            for (i, row) in rows.iter().enumerate() {
+                for (col, name) in columns.iter().enumerate() {
+                    let value = cell(row, name);
+                    if value.chars().count() > XLSX_CELL_LIMIT {
+                        return Err(format!(
+                            "{} 的 {} 超过 XLSX 单元格上限 {} 字符，请改用 CSV",
+                            cell(row, "id"),
+                            name,
+                            XLSX_CELL_LIMIT
+                        ));
+                    }
+                    sheet
+                        .write_string(i as u32 + 1, col as u16, value)
+                        .map_err(xlsx_err)?;
+                }
+            }
+            sheet.set_freeze_panes(1, 0).map_err(xlsx_err)?;
+            workbook.save(output_path).map_err(xlsx_err)
+        })(),
+    };
+    if let Err(e) = result {
+        let _ = fs::remove_file(output_path);
+        return Err(e);
+    }
+    Ok(output_path.to_string())
+}
+
+// ============================================================
+// 批量编辑导入
+// ============================================================
+
+/// 从 CSV / XLSX 表格批量更新资源（目录模式）
+///
+/// 按 id 匹配现有资源；category 只能改为 `_meta.json` 中已有的分类，
+/// 修改分类时资源目录随之移动。dry-run 时只返回差异。
+pub fn import_sheet(
+    data_dir: &str,
+    sheet_path: &str,
+    options: &SheetImportOptions,
+) -> Result<SheetImportResult, String> {
+    let index: HashMap<String, SheetTarget> =
+        crate::import_export::index_existing_resources(Path::new(data_dir))
+            .into_iter()
+            .map(|(id, dir)| (id, SheetTarget::Directory(dir)))
+            .collect();
+    let meta = crate::category_ops::read_meta(data_dir)?;
+    let categories: HashSet<String> = meta.categories.into_iter().map(|c| c.key).collect();
+    import_rows(data_dir, sheet_path, options, &index, Some(&categories))
+}
+
+/// 从 CSV / XLSX 表格批量更新模板（JSON 文件模式）
+///
+/// enabled 与 i18n 列在 JSON 文件模式下不适用，会被忽略；修改 category 时模板移动到对应分类文件。
+pub fn import_json_templates_sheet(
+    data_dir: &str,
+    sheet_path: &str,
+    options: &SheetImportOptions,
+) -> Result<SheetImportResult, String> {
+    let index: HashMap<String, SheetTarget> = crate::json_file_ops::scan_json_resources(data_dir)?
+        .into_iter()
+        .map(|r| {
+            let target = SheetTarget::Template {
+                category: r.major_category,
+                id: r.id.clone(),
+            };
+            (r.id, target)
+        })
+        .collect();
+    import_rows(data_dir, sheet_path, options, &index, None)
+}
+
+/// 比较表格行与现有资源，非 dry-run 时逐个写回
+///
+/// `categories` 为 Some 时是目录模式，分类只能改为其中之一（即使集合为空）；
+/// None 表示 JSON 文件模式，不限制分类（按需创建分类文件）。
+fn import_rows(
+    data_dir: &str,
+    sheet_path: &str,
+    options: &SheetImportOptions,
+    index: &HashMap<String, SheetTarget>,
+    categories: Option<&HashSet<String>>,
+) -> Result<SheetImportResult, String> {
+    let (columns, rows) = read_sheet(sheet_path)?;
+    if !columns.iter().any(|c| c == "id") {
+        return Err("表格缺少 id 列".to_string());
+    }
+    let json_mode = categories.is_none();
+    let supported = |column: &str| match column {
+        "category" | "name" | "description" | "tags" | CONTENT_COLUMN => true,
+        "enabled" => !json_mode,
+        _ => !json_mode && parse_i18n_column(column).is_some(),
+    };
+    let compared: Vec<String> = columns
+        .iter()
+        .filter(|c| c.as_str() != "id" && supported(c))
+        .cloned()
+        .collect();
+    let ignored_columns: Vec<String> = columns
+        .iter()
+        .filter(|c| c.as_str() != "id" && !supported(c))
+        .cloned()
+        .collect();
+    let content_file = options.content_file.as_deref();
+    if let Some(name) = content_file {
+        crate::import_export::validate_content_file_name(name)?;
+    }
+    if !json_mode && content_file.is_none() && compared.iter().any(|c| c == CONTENT_COLUMN) {
+        return Err("表格包含 content 列，但未指定内容文件".to_string());
+    }
+
+    let mut changed = Vec::new();
+    let mut unchanged = 0u32;
+    let mut not_found = Vec::new();
+    let mut failed = Vec::new();
+    let mut seen = HashSet::new();
+    for (line, row) in rows.iter().enumerate() {
+        let id = row.get("id").map(|v| v.trim().to_string()).unwrap_or_default();
+        if id.is_empty() {
+            continue;
+        }
+        if !seen.insert(id.clone()) {
+            failed.push(ImportFailure {
+                id,
+                error: format!("第 {} 行：ID 重复", line + 2),
+            });
+            continue;
+        }
+        let Some(target) = index.get(&id) else {
+            not_found.push(id);
+            continue;
+        };
+        let current = match target {
+            SheetTarget::Directory(dir) => read_directory_row(dir, content_file),
+            SheetTarget::Template { category, id } => read_template_row(data_dir, category, id),
+        };
+        let diff = current.and_then(|current| {
+            diff_row(&compared, &current, row, categories).map(|changes| SheetRowDiff {
+                id: id.clone(),
+                path: target.path(),
+                changes,
+            })
+        });
+        match diff {
+            Ok(diff) if diff.changes.is_empty() => unchanged += 1,
+            Ok(diff) => changed.push(diff),
+            Err(e) => failed.push(ImportFailure { id, error: e }),
+        }
+    }
+
+    let mut applied = Vec::new();
+    if !options.dry_run {
+        for diff in &changed {
+            let result = match &index[&diff.id] {
+                SheetTarget::Directory(dir) => apply_directory_changes(dir, diff, content_file),
+                SheetTarget::Template { category, id } => {
+                    apply_template_changes(data_dir, category, id, diff)
+                }
+            };
+            match result {
+                Ok(()) => applied.push(diff.id.clone()),
+                Err(e) => failed.push(ImportFailure {
+                    id: diff.id.clone(),
+                    error: e,
+                }),
+            }
+        }
+    }
+
+    Ok(SheetImportResult {
+        changed,
+        unchanged,
+        not_found,
+        ignored_columns,
+        failed,
+        applied,
+        dry_run: options.dry_run,
+    })
+}
+
+/// 逐列比较，返回变更；enabled 值无法识别或分类不存在时整行失败
+fn diff_row(
+    columns: &[String],
+    current: &SheetRow,
+    row: &SheetRow,
+    categories: Option<&HashSet<String>>,
+) -> Result<Vec<FieldChange>, String> {
+    let mut changes = Vec::new();
+    for column in columns {
+        let old = current.get(column).cloned().unwrap_or_default();
+        let raw = row.get(column).cloned().unwrap_or_default().replace("\r\n", "\n");
+        if column == "enabled" && raw.trim().is_empty() {
+            continue;
+        }
+        let new = match column.as_str() {
+            "tags" => join_tags(&parse_tags(&raw)),
+            "enabled" => parse_bool(&raw)
+                .ok_or_else(|| format!("enabled 值无法识别: {}", raw))?
+                .to_string(),
+            "category" | "name" => raw.trim().to_string(),
+            _ => raw,
+        };
+        if new == old {
+            continue;
+        }
+        if column == "category" {
+            if new.is_empty() {
+                return Err("category 不能为空".to_string());
+            }
+            crate::category_ops::validate_category_key(&new)?;
+            if categories.is_some_and(|c| !c.contains(&new)) {
+                return Err(format!("分类 {} 不存在", new));
+            }
+        }
+        changes.push(FieldChange {
+            field: column.clone(),
+            old,
+            new,
+        });
+    }
+    Ok(changes)
+}
+
+/// 通过常规保存路径写回目录模式资源：manifest → 内容文件 → 移动分类
+fn apply_directory_changes(
+    dir: &Path,
+    diff: &SheetRowDiff,
+    content_file: Option<&str>,
+) -> Result<(), String> {
+    let resource_path = dir.to_string_lossy().to_string();
+    let mut manifest = crate::resource_ops::read_manifest(&resource_path)?;
+    let obj = manifest
+        .as_object_mut()
+        .ok_or_else(|| "manifest.json 不是 JSON 对象".to_string())?;
+    let mut new_category = None;
+    let mut new_content = None;
+    for change in &diff.changes {
+        match change.field.as_str() {
+            "name" | "description" => {
+                obj.insert(change.field.clone(), serde_json::json!(change.new));
+            }
+            "tags" => {
+                obj.insert("tags".to_string(), serde_json::json!(parse_tags(&change.new)));
+            }
+            "enabled" => {
+                obj.insert("enabled".to_string(), serde_json::json!(change.new == "true"));
+            }
+            "category" => new_category = Some(change.new.as_str()),
+            CONTENT_COLUMN => new_content = Some(change.new.as_str()),
+            column => {
+                if let Some((lang, field)) = parse_i18n_column(column) {
+                    set_i18n(obj, lang, field, &change.new);
+                }
+            }
+        }
+    }
+    obj.insert(
+        "updatedAt".to_string(),
+        serde_json::json!(chrono::Utc::now().to_rfc3339()),
+    );
+    let sub_category = obj
+        .get("subCategory")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    // 先确认子分类在新分类下有效，避免 manifest 已写入而移动失败
+    if let (Some(category), Some(data_root)) =
+        (new_category, crate::resource_ops::find_data_root(dir))
+    {
+        if !sub_category.is_empty() {
+            crate::category_ops::validate_category_key(&sub_category)?;
+        }
+        let meta = crate::category_ops::read_meta(&data_root.to_string_lossy())?;
+        crate::category_ops::validate_sub_category(&meta, category, &sub_category)?;
+    }
+    crate::resource_ops::save_manifest(&resource_path, manifest)?;
+
+    if let (Some(content), Some(content_file)) = (new_content, content_file) {
+        let file_path = dir.join(content_file);
+        let text = if content_file.ends_with(".json") {
+            // 文档模板结构：只替换正文，保留作者备注等其他字段
+            let mut doc: serde_json::Value = fs::read_to_string(&file_path)
+                .ok()
+                .and_then(|c| serde_json::from_str(&c).ok())
+                .filter(|v: &serde_json::Value| v.is_object())
+                .unwrap_or_else(|| serde_json::json!({}));
+            doc["content"] = serde_json::json!(content);
+            serde_json::to_string_pretty(&doc)
+                .map_err(|e| format!("序列化 {} 失败: {}", content_file, e))?
+        } else {
+            content.to_string()
+        };
+        crate::resource_ops::save_content_file(&file_path.to_string_lossy(), &text)?;
+    }
+
+    if let Some(category) = new_category {
+        // 保持原有的目录结构：资源位于 {major}/{sub}/{id} 时移动后仍嵌套
+        let nested = !sub_category.is_empty()
+            && dir.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str())
+                == Some(sub_category.as_str());
+        crate::resource_ops::batch_move_category(&[resource_path], category, None, nested)?;
+    }
+    Ok(())
+}
+
+/// 通过常规保存路径写回 JSON 文件模式模板，修改分类时再移动
+fn apply_template_changes(
+    data_dir: &str,
+    category: &str,
+    id: &str,
+    diff: &SheetRowDiff,
+) -> Result<(), String> {
+    let detail = crate::json_file_ops::read_json_template(data_dir, category, id)?;
+    let value = |field: &str| {
+        diff.changes
+            .iter()
+            .find(|c| c.field == field)
+            .map(|c| c.new.clone())
+    };
+    let tags = value("tags").map(|t| parse_tags(&t));
+    crate::json_file_ops::save_json_template(
+        data_dir,
+        category,
+        id,
+        &value("name").unwrap_or(detail.name),
+        &value("description").unwrap_or(detail.description),
+        &value(CONTENT_COLUMN).unwrap_or(detail.content),
+        detail.variables,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        tags,
+    )?;
+    if let Some(new_category) = value("category") {
+        crate::json_file_ops::move_json_template(data_dir, category, id, &new_category)?;
+    }
+    Ok(())
+}
+
+// ============================================================
+// 内部工具
+// ============================================================
+
+/// 目录模式资源 → 表格行；content_file 为 Some 时附带 content 列
+fn read_directory_row(
+    dir: &Path,
+    content_file: Option<&str>,
+) -> Result<SheetRow, String> {
+    let manifest = crate::resource_ops::read_manifest(&dir.to_string_lossy())?;
+    let text = |key: &str| manifest.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let tags: Vec<String> = manifest
+        .get("tags")
+        .and_then(|v| v.as_array())
+        .map(|a| a.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    let enabled = manifest.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true);
+
+    let mut row = BTreeMap::new();
+    row.insert("id".to_string(), text("id"));
+    row.insert("category".to_string(), text("majorCategory"));
+    row.insert("name".to_string(), text("name"));
+    row.insert("description".to_string(), text("description"));
+    row.insert("tags".to_string(), join_tags(&tags));
+    row.insert("enabled".to_string(), enabled.to_string());
+    if let Some(i18n) = manifest.get("i18n").and_then(|v| v.as_object()) {
+        for (lang, entry) in i18n {
+            for field in I18N_FIELDS {
+                if let Some(value) = entry.get(field).and_then(|v| v.as_str()) {
+                    row.insert(format!("{}{}.{}", I18N_PREFIX, lang, field), value.to_string());
+                }
+            }
+        }
+    }
+    if let Some(content_file) = content_file {
+        let raw = fs::read_to_string(dir.join(content_file)).unwrap_or_default();
+        let content = if content_file.ends_with(".json") {
+            serde_json::from_str::<serde_json::Value>(&raw)
+                .ok()
+                .and_then(|v| v.get("content").and_then(|c| c.as_str()).map(String::from))
+                .unwrap_or_default()
+        } else {
+            raw
+        };
+        row.insert(CONTENT_COLUMN.to_string(), content);
+    }
+    Ok(row)
+}
+
+/// JSON 文件模式模板 → 表格行（总是带 content，导出时按需取列）
+fn read_template_row(
+    data_dir: &str,
+    category: &str,
+    id: &str,
+) -> Result<SheetRow, String> {
+    let detail = crate::json_file_ops::read_json_template(data_dir, category, id)?;
+    let mut row = BTreeMap::new();
+    row.insert("id".to_string(), detail.id);
+    row.insert("category".to_string(), detail.category_key);
+    row.insert("name".to_string(), detail.name);
+    row.insert("description".to_string(), detail.description);
+    row.insert("tags".to_string(), join_tags(&detail.tags));
+    row.insert(CONTENT_COLUMN.to_string(), detail.content);
+    Ok(row)
+}
+
+/// 读取表格：返回（列名, 行）；XLSX 取第一个工作表，首行为列名
+fn read_sheet(sheet_path: &str) -> Result<(Vec<String>, Vec<SheetRow>), String> {
+    let size = fs::metadata(sheet_path)
+        .map_err(|e| format!("读取表格文件失败: {}", e))?
+        .len();
+    if size > MAX_SHEET_SIZE {
+        return Err(format!("表格文件超过大小上限 {} 字节", MAX_SHEET_SIZE));
+    }
+
+    let mut records: Vec<Vec<String>> = Vec::new();
+    match SheetFormat::from_path(sheet_path) {
+        SheetFormat::Csv => {
+            let content =
+                fs::read_to_string(sheet_path).map_err(|e| format!("读取表格文件失败: {}", e))?;
+            let content = content.strip_prefix('\u{feff}').unwrap_or(&content);
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_reader(content.as_bytes());
+            for record in reader.records() {
+                let record = record.map_err(|e| format!("解析 CSV 失败: {}", e))?;
+                records.push(record.iter().map(unescape_csv_cell).collect());
+            }
+        }
+        SheetFormat::Xlsx => {
+            use calamine::Reader;
+            let mut workbook = calamine::open_workbook_auto(sheet_path)
+                .map_err(|e| format!("打开表格文件失败: {}", e))?;
+            let range = workbook
+                .worksheet_range_at(0)
+                .ok_or_else(|| "表格中没有工作表".to_string())?
+                .map_err(|e| format!("读取工作表失败: {}", e))?;
+            for row in range.rows() {
+                records.push(row.iter().map(|cell| cell.to_string()).collect());
+            }
+        }
+    }
+
+    let mut records = records.into_iter();
+    let columns: Vec<String> = records
+        .next()
+        .ok_or_else(|| "表格为空".to_string())?
+        .into_iter()
+        .map(|c| c.trim().to_string())
+        .collect();
+    let rows = records
+        .map(|record| {
+            columns
+                .iter()
+                .cloned()
+                .zip(record.into_iter().chain(std::iter::repeat(String::new())))
+                .collect()
+        })
+        .collect();
+    Ok((columns, rows))
+}
+
+/// 单元格是否需要转义：以公式字符开头，或本身就像转义过的值（保证导入时能原样还原）
+fn needs_formula_escape(value: &str) -> bool {
+    match value.strip_prefix('\'') {
+        Some(rest) => needs_formula_escape(rest),
+        None => value.starts_with(FORMULA_PREFIXES),
+    }
+}
+
+/// CSV 导出：可能被 Excel 当作公式执行的单元格前置 `'`，使其按文本显示
+fn escape_csv_cell(value: String) -> String {
+    if needs_formula_escape(&value) {
+        format!("'{}", value)
+    } else {
+        value
+    }
+}
+
+/// CSV 导入：去掉 [`escape_csv_cell`] 添加的 `'`
+fn unescape_csv_cell(value: &str) -> String {
+    match value.strip_prefix('\'') {
+        Some(rest) if needs_formula_escape(rest) => rest.to_string(),
+        _ => value.to_string(),
+    }
+}
+
+/// 解析 `i18n.{语言}.{字段}` 列名
+fn parse_i18n_column(column: &str) -> Option<(&str, &str)> {
+    let (lang, field) = column.strip_prefix(I18N_PREFIX)?.split_once('.')?;
+    (!lang.is_empty() && I18N_FIELDS.contains(&field)).then_some((lang, field))
+}
+
+/// 写入 i18n 字段；值为空时删除该字段，语言下没有字段时删除该语言
+fn set_i18n(
+    manifest: &mut serde_json::Map<String, serde_json::Value>,
+    lang: &str,
+    field: &str,
+    value: &str,
+) {
+    let i18n = manifest
+        .entry("i18n")
+        .or_insert_with(|| serde_json::json!({}));
+    if !i18n.is_object() {
+        *i18n = serde_json::json!({});
+    }
+    let Some(i18n_obj) = i18n.as_object_mut() else {
+        return;
+    };
+    let entry = i18n_obj
+        .entry(lang)
+        .or_insert_with(|| serde_json::json!({}));
+    if let Some(entry_obj) = entry.as_object_mut() {
+        if value.is_empty() {
+            entry_obj.remove(field);
+        } else {
+            entry_obj.insert(field.to_string(), serde_json::json!(value));
+        }
+        if entry_obj.is_empty() {
+            i18n_obj.remove(lang);
+        }
+    }
+    if manifest.get("i18n").and_then(|v| v.as_object()).is_some_and(|o| o.is_empty()) {
+        manifest.remove("i18n");
+    }
+}
+
+/// 标签以逗号（中英文）或分号分隔
+fn parse_tags(value: &str) -> Vec<String> {
+    value
+        .split([',', '，', ';', '；'])
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn join_tags(tags: &[String]) -> String {
+    tags.join(", ")
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "1" | "yes" | "y" | "是" => Some(true),
+        "false" | "0" | "no" | "n" | "否" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_cells_with_formula_prefixes_are_escaped_and_restored() {
+        let cases = [
+            ("=HYPERLINK(\"http://x\")", "'=HYPERLINK(\"http://x\")"),
+            ("+1", "'+1"),
+            ("-1", "'-1"),
+            ("@SUM(A1)", "'@SUM(A1)"),
+            ("\tcmd", "'\tcmd"),
+            ("'=already", "''=already"),
+            ("'plain", "'plain"),
+            ("a=b", "a=b"),
+            ("", ""),
+        ];
+        for (value, escaped) in cases {
+            assert_eq!(escape_csv_cell(value.to_string()), escaped, "{:?}", value);
+            assert_eq!(unescape_csv_cell(escaped), value, "{:?}", escaped);
+        }
+    }
+
+    #[test]
+    fn csv_export_round_trips_formula_like_cells() {
+        let path = std::env::temp_dir().join(format!("sheet-{}.csv", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        let columns = vec!["id".to_string(), "description".to_string()];
+        let row: SheetRow = [("id", "a"), ("description", "=1+1")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        write_sheet(&columns, std::slice::from_ref(&row), path, None).unwrap();
+        let raw = fs::read_to_string(path).unwrap();
+        assert!(raw.contains("'=1+1"), "{}", raw);
+        let (read_columns, rows) = read_sheet(path).unwrap();
+        let _ = fs::remove_file(path);
+        assert_eq!(read_columns, columns);
+        assert_eq!(rows, [row]);
+    }
+
+    #[test]
+    fn xlsx_export_requires_xlsx_extension() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("sheet-{}.xls", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        assert!(write_sheet(&["id".to_string()], &[], path, None).is_err());
+        assert!(write_sheet(&["id".to_string()], &[], path, Some(SheetFormat::Xlsx)).is_err());
+        assert!(!Path::new(path).exists());
+    }
+}
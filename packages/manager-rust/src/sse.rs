@@ -0,0 +1,334 @@
+//! 流式响应解码
+//!
+//! 网络分块可能在任意字节处截断（包括 UTF-8 多字节字符与 `\r\n` 之间），
+//! 因此按字节缓冲、只在完整的一行结束后才解码为字符串。
+//! [`SseDecoder`] 按 Server-Sent Events 规范组装事件，[`LineDecoder`] 用于 NDJSON 等逐行协议。
+
+/// UTF-8 BOM，仅允许出现在流的开头
+const BOM: char = '\u{feff}';
+
+// ============================================================
+// 逐行解码
+// ============================================================
+
+/// 增量行解码器，行结束符可以是 `\n`、`\r\n` 或单独的 `\r`
+#[derive(Debug, Default)]
+pub struct LineDecoder {
+    buffer: Vec<u8>,
+    /// 上一个分块以 `\r` 结尾，下一个分块开头的 `\n` 属于同一个行结束符
+    pending_cr: bool,
+    /// 已经输出过第一行（之后不再剥离 BOM）
+    started: bool,
+}
+
+impl LineDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 输入一个分块，返回其中所有完整的行（不含行结束符）
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut chunk = chunk;
+        if self.pending_cr && !chunk.is_empty() {
+            self.pending_cr = false;
+            if let Some(rest) = chunk.strip_prefix(b"\n") {
+                chunk = rest;
+            }
+        }
+
+        let offset = self.buffer.len();
+        self.buffer.extend_from_slice(chunk);
+
+        let mut start = 0;
+        let mut i = offset;
+        while i < self.buffer.len() {
+            match self.buffer[i] {
+                b'\n' => {
+                    lines.push(self.decode(start, i));
+                    start = i + 1;
+                }
+                b'\r' => {
+                    lines.push(self.decode(start, i));
+                    if i + 1 == self.buffer.len() {
+                        self.pending_cr = true;
+                    } else if self.buffer[i + 1] == b'\n' {
+                        i += 1;
+                    }
+                    start = i + 1;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        // 每个分块只移动一次剩余数据
+        self.buffer.drain(..start);
+        lines
+    }
+
+    /// 流结束时取出最后一行（没有行结束符的残留数据）
+    pub fn finish(&mut self) -> Option<String> {
+        self.pending_cr = false;
+        if self.buffer.is_empty() {
+            return None;
+        }
+        let line = self.decode(0, self.buffer.len());
+        self.buffer.clear();
+        Some(line)
+    }
+
+    fn decode(&mut self, start: usize, end: usize) -> String {
+        let line = String::from_utf8_lossy(&self.buffer[start..end]);
+        if self.started {
+            return line.into_owned();
+        }
+        self.started = true;
+        line.strip_prefix(BOM).unwrap_or(&line).to_string()
+    }
+}
+
+// ============================================================
+// SSE 解码
+// ============================================================
+
+/// 一个完整的 SSE 事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// `event:` 字段，未指定时为 None（即默认的 `message` 事件）
+    pub event: Option<String>,
+    /// 所有 `data:` 行以 `\n` 连接后的内容
+    pub data: String,
+    /// 截至该事件的最后一个 `id:`
+    pub id: Option<String>,
+}
+
+/// 增量 SSE 解码器
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    lines: LineDecoder,
+    event: Option<String>,
+    data: String,
+    has_data: bool,
+    last_id: Option<String>,
+    retry: Option<u64>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 输入一个分块，返回其中已完整结束（遇到空行）的事件
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        for line in self.lines.feed(chunk) {
+            if let Some(event) = self.process_line(&line) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// 流结束时处理残留数据
+    ///
+    /// 规范要求丢弃没有以空行结束的事件，但不少服务端在最后一个事件后直接断开连接，
+    /// 这里仍将其作为事件返回。
+    pub fn finish(&mut self) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        if let Some(line) = self.lines.finish() {
+            if let Some(event) = self.process_line(&line) {
+                events.push(event);
+            }
+        }
+        if let Some(event) = self.dispatch() {
+            events.push(event);
+        }
+        events
+    }
+
+    /// 服务端通过 `retry:` 建议的重连间隔（毫秒）
+    pub fn retry(&self) -> Option<u64> {
+        self.retry
+    }
+
+    /// 最后一个 `id:` 字段，可用作重连时的 `Last-Event-ID`
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_id.as_deref()
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        // 以冒号开头的行是注释（常用作心跳）
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "event" => self.event = Some(value.to_string()),
+            "id" if !value.contains('\0') => self.last_id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                self.retry = value.parse().ok();
+            }
+            // 未知字段忽略
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take().filter(|e| !e.is_empty());
+        if !self.has_data {
+            return None;
+        }
+        self.has_data = false;
+        Some(SseEvent {
+            event,
+            data: std::mem::take(&mut self.data),
+            id: self.last_id.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event: Option<&str>, data: &str, id: Option<&str>) -> SseEvent {
+        SseEvent {
+            event: event.map(|e| e.to_string()),
+            data: data.to_string(),
+            id: id.map(|i| i.to_string()),
+        }
+    }
+
+    /// 按给定分块依次输入，最后调用 finish
+    fn decode_chunks(chunks: &[&[u8]]) -> Vec<SseEvent> {
+        let mut decoder = SseDecoder::new();
+        let mut events = Vec::new();
+        for chunk in chunks {
+            events.extend(decoder.feed(chunk));
+        }
+        events.extend(decoder.finish());
+        events
+    }
+
+    /// 在每一个字节位置切成两块，以及逐字节输入，结果都必须与整块输入一致
+    fn assert_any_chunking(input: &[u8], expected: &[SseEvent]) {
+        assert_eq!(decode_chunks(&[input]), expected, "整块输入");
+        for split in 0..=input.len() {
+            let (a, b) = input.split_at(split);
+            assert_eq!(decode_chunks(&[a, b]), expected, "在第 {} 字节处切分", split);
+        }
+        let bytes: Vec<&[u8]> = input.chunks(1).collect();
+        assert_eq!(decode_chunks(&bytes), expected, "逐字节输入");
+    }
+
+    #[test]
+    fn utf8_character_split_across_chunks() {
+        let input = "data: 你好🌍\n\n".as_bytes();
+        // "你" 占 3 个字节，从中间切开
+        let events = decode_chunks(&[&input[..7], &input[7..]]);
+        assert_eq!(events, vec![event(None, "你好🌍", None)]);
+        assert_any_chunking(input, &[event(None, "你好🌍", None)]);
+    }
+
+    #[test]
+    fn crlf_split_between_chunks() {
+        let events = decode_chunks(&[b"data: a\r", b"\n\r", b"\ndata: b\r\n\r\n"]);
+        assert_eq!(events, vec![event(None, "a", None), event(None, "b", None)]);
+        assert_any_chunking(
+            b"data: a\r\n\r\ndata: b\r\n\r\n",
+            &[event(None, "a", None), event(None, "b", None)],
+        );
+    }
+
+    #[test]
+    fn bare_cr_line_endings() {
+        let expected = [event(Some("x"), "a", None), event(None, "b", None)];
+        assert_any_chunking(b"event: x\rdata: a\r\rdata: b\r\r", &expected);
+        // 混用三种行结束符
+        assert_any_chunking(b"event: x\ndata: a\r\n\rdata: b\n\r\n", &expected);
+    }
+
+    #[test]
+    fn multi_line_data_joined_with_newline() {
+        let input = b"data: first\ndata:second\ndata\ndata:  indented\n\n";
+        assert_any_chunking(input, &[event(None, "first\nsecond\n\n indented", None)]);
+    }
+
+    #[test]
+    fn comments_and_fields() {
+        let input = concat!(
+            ": heartbeat\n",
+            ":\n",
+            "retry: 3000\n",
+            "id: 42\n",
+            "event: update\n",
+            "unknown: ignored\n",
+            "data: {\"a\":1}\n",
+            "\n",
+            // 只有 event 没有 data 的块不产生事件，且 event 不会延续到下一个事件
+            "event: orphan\n",
+            "\n",
+            "retry: soon\n",
+            "data: plain\n",
+            "\n",
+        );
+        let expected = [
+            event(Some("update"), "{\"a\":1}", Some("42")),
+            event(None, "plain", Some("42")),
+        ];
+        assert_any_chunking(input.as_bytes(), &expected);
+
+        let mut decoder = SseDecoder::new();
+        decoder.feed(input.as_bytes());
+        // 非数字的 retry 被忽略，保留之前的值
+        assert_eq!(decoder.retry(), Some(3000));
+        assert_eq!(decoder.last_event_id(), Some("42"));
+    }
+
+    #[test]
+    fn leading_bom_is_stripped_once() {
+        let input = "\u{feff}data: a\n\ndata: \u{feff}b\n\n".as_bytes();
+        let expected = [event(None, "a", None), event(None, "\u{feff}b", None)];
+        assert_any_chunking(input, &expected);
+
+        let mut lines = LineDecoder::new();
+        assert_eq!(lines.feed(&[0xEF, 0xBB]), Vec::<String>::new());
+        assert_eq!(lines.feed(&[0xBF, b'{', b'}', b'\n']), vec!["{}".to_string()]);
+    }
+
+    #[test]
+    fn event_without_trailing_blank_line_at_eof() {
+        let expected = [event(None, "a", None), event(None, "last", None)];
+        assert_any_chunking(b"data: a\n\ndata: last", &expected);
+        assert_any_chunking(b"data: last\n", &[event(None, "last", None)]);
+        assert_any_chunking(b"data: last\r", &[event(None, "last", None)]);
+        assert!(decode_chunks(&[b": only a comment"]).is_empty());
+    }
+
+    #[test]
+    fn line_decoder_ndjson() {
+        let mut lines = LineDecoder::new();
+        let mut out = lines.feed(b"{\"a\":1}\r");
+        out.extend(lines.feed(b"\n{\"b\":"));
+        out.extend(lines.feed(b"2}"));
+        assert_eq!(out, vec!["{\"a\":1}".to_string()]);
+        assert_eq!(lines.finish(), Some("{\"b\":2}".to_string()));
+        assert_eq!(lines.finish(), None);
+    }
+}
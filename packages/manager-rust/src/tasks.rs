@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+// ============================================================
+// 可取消的长时间任务
+// ============================================================
+
+/// 长时间任务（导出等）的取消标志登记表，作为 Tauri state 管理
+///
+/// 命令开始时以前端传入的任务 ID 登记，工作线程轮询返回的标志；
+/// 前端调用取消命令时置位，任务结束后注销。
+#[derive(Debug, Default)]
+pub struct TaskRegistry {
+    tasks: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl TaskRegistry {
+    /// 登记任务并返回其取消标志；已存在同 ID 的任务时先取消旧任务
+    pub fn register(&self, id: &str) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        if let Ok(mut tasks) = self.tasks.lock() {
+            if let Some(old) = tasks.insert(id.to_string(), flag.clone()) {
+                old.store(true, Ordering::Relaxed);
+            }
+        }
+        flag
+    }
+
+    /// 请求取消任务，任务不存在（已结束）时返回 false
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.tasks.lock().ok().and_then(|t| t.get(id).cloned()) {
+            Some(flag) => {
+                flag.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 任务结束后注销（只注销仍是同一个任务的登记，同 ID 重新登记的新任务不受影响）
+    pub fn finish(&self, id: &str, flag: &Arc<AtomicBool>) {
+        if let Ok(mut tasks) = self.tasks.lock() {
+            if tasks.get(id).is_some_and(|f| Arc::ptr_eq(f, flag)) {
+                tasks.remove(id);
+            }
+        }
+    }
+}
+
+// ============================================================
+// 可取消的异步请求
+// ============================================================
+
+/// 异步请求（AI 生成等）的取消通知登记表，作为 Tauri state 管理
+///
+/// 异步请求可能长时间阻塞在网络读取上，轮询标志无法及时中止，
+/// 因此用 `Notify` 唤醒等待中的请求；请求在取消时丢弃进行中的 HTTP 连接。
+#[derive(Debug, Default)]
+pub struct RequestRegistry {
+    requests: Mutex<HashMap<String, Arc<Notify>>>,
+}
+
+impl RequestRegistry {
+    /// 登记请求并返回其取消通知；已存在同 ID 的请求时先取消旧请求
+    pub fn register(&self, id: &str) -> Arc<Notify> {
+        let notify = Arc::new(Notify::new());
+        if let Ok(mut requests) = self.requests.lock() {
+            if let Some(old) = requests.insert(id.to_string(), notify.clone()) {
+                old.notify_one();
+            }
+        }
+        notify
+    }
+
+    /// 请求取消，请求不存在（已结束）时返回 false
+    ///
+    /// `notify_one` 会保留一次通知，即使请求尚未开始等待也不会丢失。
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.requests.lock().ok().and_then(|r| r.get(id).cloned()) {
+            Some(notify) => {
+                notify.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 请求结束后注销（只注销仍是同一个请求的登记）
+    pub fn finish(&self, id: &str, notify: &Arc<Notify>) {
+        if let Ok(mut requests) = self.requests.lock() {
+            if requests.get(id).is_some_and(|n| Arc::ptr_eq(n, notify)) {
+                requests.remove(id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finishing_replaced_task_keeps_new_registration() {
+        let registry = TaskRegistry::default();
+        let first = registry.register("export");
+        let second = registry.register("export");
+        assert!(first.load(Ordering::Relaxed), "重新登记时取消旧任务");
+
+        registry.finish("export", &first);
+        assert!(registry.cancel("export"));
+        assert!(second.load(Ordering::Relaxed));
+
+        registry.finish("export", &second);
+        assert!(!registry.cancel("export"));
+    }
+
+    #[test]
+    fn finishing_replaced_request_keeps_new_registration() {
+        let registry = RequestRegistry::default();
+        let first = registry.register("req");
+        let second = registry.register("req");
+        registry.finish("req", &first);
+        assert!(registry.cancel("req"));
+        registry.finish("req", &second);
+        assert!(!registry.cancel("req"));
+    }
+}
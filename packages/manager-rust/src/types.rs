@@ -139,6 +139,9 @@ impl GenericManifest {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIServiceConfig {
+    /// 提供商（openai / anthropic / gemini / ollama 等），决定请求协议，为空时按 OpenAI 兼容处理
+    #[serde(default)]
+    pub provider: String,
     #[serde(rename = "baseUrl")]
     pub base_url: String,
     #[serde(rename = "apiKey")]
@@ -221,6 +224,74 @@ pub struct LocalAIServiceItem {
     pub temperature: f32,
 }
 
+// ============================================================
+// AI 网络设置（~/.aidocplus/manager-network.json）
+// ============================================================
+
+/// 所有 AI 请求共用的 HTTP 客户端设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AIHttpSettings {
+    /// 建立连接的超时（秒）
+    #[serde(default = "default_connect_timeout", rename = "connectTimeoutSecs")]
+    pub connect_timeout_secs: u64,
+    /// 两次读取之间的最长等待（秒），流式响应只要持续有数据就不会超时；0 表示不限制
+    #[serde(default = "default_read_timeout", rename = "readTimeoutSecs")]
+    pub read_timeout_secs: u64,
+    /// 连接失败、超时、429 与 5xx 时的最大重试次数
+    #[serde(default = "default_max_retries", rename = "maxRetries")]
+    pub max_retries: u32,
+    /// 指数退避的初始间隔（毫秒）
+    #[serde(default = "default_retry_base_delay", rename = "retryBaseDelayMs")]
+    pub retry_base_delay_ms: u64,
+    /// 单次重试等待的上限（毫秒），服务端 Retry-After 超过该值时不再重试
+    #[serde(default = "default_retry_max_delay", rename = "retryMaxDelayMs")]
+    pub retry_max_delay_ms: u64,
+    /// 代理地址（http://、https://、socks5://、socks5h://），为空时按 use_system_proxy 处理
+    #[serde(default, rename = "proxyUrl")]
+    pub proxy_url: String,
+    /// 不走代理的主机列表（逗号分隔，语法同 NO_PROXY 环境变量）
+    #[serde(default, rename = "noProxy")]
+    pub no_proxy: String,
+    /// 未指定代理时是否使用 HTTP_PROXY / HTTPS_PROXY / ALL_PROXY 环境变量
+    #[serde(default = "default_true", rename = "useSystemProxy")]
+    pub use_system_proxy: bool,
+    /// 额外信任的 CA 证书（PEM 文件，可包含多个证书），用于公司网络的 TLS 代理
+    #[serde(default, rename = "caCertPath")]
+    pub ca_cert_path: String,
+}
+
+impl Default for AIHttpSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: default_connect_timeout(),
+            read_timeout_secs: default_read_timeout(),
+            max_retries: default_max_retries(),
+            retry_base_delay_ms: default_retry_base_delay(),
+            retry_max_delay_ms: default_retry_max_delay(),
+            proxy_url: String::new(),
+            no_proxy: String::new(),
+            use_system_proxy: true,
+            ca_cert_path: String::new(),
+        }
+    }
+}
+
+fn default_connect_timeout() -> u64 {
+    10
+}
+fn default_read_timeout() -> u64 {
+    300
+}
+fn default_max_retries() -> u32 {
+    3
+}
+fn default_retry_base_delay() -> u64 {
+    1000
+}
+fn default_retry_max_delay() -> u64 {
+    30000
+}
+
 // ============================================================
 // 内容文件
 // ============================================================
@@ -242,3 +313,17 @@ pub struct BatchUpdateRequest {
     pub field: String,
     pub value: serde_json::Value,
 }
+
+// ============================================================
+// 资源 ID 重命名
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameIdResult {
+    /// 重命名后的资源路径（JSON 文件模式为 `category_key::template_id`）
+    #[serde(rename = "newPath")]
+    pub new_path: String,
+    /// 被改写引用的资源数量
+    #[serde(rename = "updatedReferences")]
+    pub updated_references: u32,
+}
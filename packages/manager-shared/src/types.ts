@@ -42,6 +42,8 @@ export interface AIGenerateConfig {
 }
 
 export interface AIServiceConfig {
+  /** 提供商（openai / anthropic / gemini / ollama 等），决定请求协议 */
+  provider?: string;
   /** API Base URL */
   baseUrl: string;
   /** API Key */
@@ -1,12 +1,11 @@
 import { useState, useRef, useCallback, useEffect } from 'react';
-import { jsonrepair } from 'jsonrepair';
 import { invoke } from '@tauri-apps/api/core';
 import {
   X, Sparkles, Loader2, Check, ChevronDown, ChevronRight,
   Plus, RotateCcw, Square, RefreshCw, Zap, AlertCircle,
 } from 'lucide-react';
 import type { ResourceTypeConfig, CategoryDefinition, AIServiceConfig } from '@aidocplus/manager-shared';
-import { loadAIConfig, aiGenerateStream } from '../hooks/useAIGenerate';
+import { loadAIConfig, aiGenerateStreamWithService, isAIBudgetError } from '../hooks/useAIGenerate';
 import { useResourceStore } from '../stores/useResourceStore';
 import { cn } from './ui/cn';
 import { Dialog, DialogContent, DialogHeader, DialogTitle } from './ui/dialog';
@@ -53,10 +52,22 @@ interface LocalAIServices {
 interface ParsedResource {
   manifest: Record<string, unknown>;
   contentFiles: Record<string, string>;
+  /** 解析时对该资源所做的修正 */
+  warnings: string[];
   selected: boolean;
   expanded: boolean;
 }
 
+/** 后端 cmd_ai_parse_output 的返回值 */
+interface AIParseResult {
+  resources: Array<{
+    manifest: Record<string, unknown>;
+    contentFiles: Array<{ filename: string; content: string }>;
+    warnings: string[];
+  }>;
+  warnings: string[];
+}
+
 interface AICreateDialogProps {
   config: ResourceTypeConfig;
   onBatchCreated: (
@@ -80,48 +91,11 @@ const STEPS: { key: Step; label: string }[] = [
   { key: 'creating', label: '创建' },
 ];
 
-/** 从 JSON 文本中逐个提取顶层对象，每个对象用 jsonrepair 修复后解析（兜底策略） */
-function extractObjects(jsonStr: string): Record<string, unknown>[] {
-  const items: Record<string, unknown>[] = [];
-  let depth = 0;
-  let inStr = false;
-  let esc = false;
-  let objStart = -1;
-
-  for (let i = 0; i < jsonStr.length; i++) {
-    const ch = jsonStr[i];
-    if (esc) { esc = false; continue; }
-    if (ch === '\\' && inStr) { esc = true; continue; }
-    if (ch === '"') { inStr = !inStr; continue; }
-    if (inStr) continue;
-
-    if (ch === '[' && depth === 0) { depth = 1; continue; }
-    if (ch === '{') {
-      if (depth === 1 && objStart === -1) objStart = i;
-      depth++;
-    } else if (ch === '}') {
-      depth--;
-      if (depth === 1 && objStart >= 0) {
-        const raw = jsonStr.slice(objStart, i + 1);
-        // 先直接解析，失败再修复后重试
-        try {
-          items.push(JSON.parse(raw));
-        } catch {
-          try {
-            items.push(JSON.parse(jsonrepair(raw)));
-          } catch { /* 跳过无法修复的对象 */ }
-        }
-        objStart = -1;
-      }
-    }
-  }
-  return items;
-}
-
-/** 将本地服务转为 AIServiceConfig（maxTokens 始终为 0，由 AI 自行决定输出长度） */
+/** 将本地服务转为 AIServiceConfig 用于显示与校验（apiKey 为后端返回的脱敏值） */
 function localToConfig(svc: LocalService): AIServiceConfig {
   const baseUrl = svc.baseUrl || PROVIDER_BASE_URLS[svc.provider] || '';
   return {
+    provider: svc.provider,
     baseUrl,
     apiKey: svc.apiKey,
     model: svc.model,
@@ -169,6 +143,7 @@ export function AICreateDialog({ config, onBatchCreated, onClose }: AICreateDial
 
   // Step 3: 预览
   const [parsedResources, setParsedResources] = useState<ParsedResource[]>([]);
+  const [parseWarnings, setParseWarnings] = useState<string[]>([]);
 
   // Step 4: 创建进度
   const [createProgress, setCreateProgress] = useState({ current: 0, total: 0, errors: [] as string[] });
@@ -262,7 +237,7 @@ export function AICreateDialog({ config, onBatchCreated, onClose }: AICreateDial
   }, []);
 
   // 开始生成
-  const handleGenerate = async () => {
+  const handleGenerate = async (allowOverBudget = false) => {
     const aiConfig = await getSelectedConfig();
     if (!aiConfig || !aiConfig.apiKey) {
       setError('请先选择一个 AI 服务，或在主程序中配置 AI 服务后刷新');
@@ -297,39 +272,59 @@ export function AICreateDialog({ config, onBatchCreated, onClose }: AICreateDial
     try {
       let fullContent = '';
       let firstChunk = true;
-      const unlisten = await aiGenerateStream(
-        aiConfig,
+      // 凭据由后端按服务 ID 解析；未选中本地服务时使用后端默认服务
+      const serviceId = localServices.some((s) => s.id === selectedServiceId) ? selectedServiceId : '';
+      const unlisten = await aiGenerateStreamWithService(
+        serviceId,
         buildSystemPrompt(),
         userPrompt,
-        (delta) => {
-          if (stoppedRef.current) return;
-          if (firstChunk) {
-            appendLog('收到 AI 响应，正在流式接收数据...');
-            firstChunk = false;
-          }
-          fullContent += delta;
-          setRawOutput(fullContent);
-        },
-        (finalContent) => {
-          if (stoppedRef.current) return;
-          setRawOutput(finalContent);
-          setEditableOutput(finalContent);
-          setGenerating(false);
-          appendLog(`接收完成，共 ${finalContent.length} 字符。请点击「解析结果」继续。`);
-          // 检测 JSON 是否被截断（未以 ] 结尾）
-          const trimmed = finalContent.trim();
-          const endsWithBracket = trimmed.endsWith(']') || trimmed.endsWith('```');
-          if (!endsWithBracket && trimmed.length > 0) {
-            appendLog('⚠️ 警告：AI 输出可能被截断（未正常结束），建议减少生成数量或检查 AI 服务的 maxTokens 设置。');
-            setError('AI 输出可能被截断，部分资源不完整。可尝试减少生成数量后重新生成，或点击「解析结果」尝试恢复已完成的部分。');
-          }
-        },
-        (errMsg) => {
-          if (stoppedRef.current) return;
-          setGenerating(false);
-          appendLog(`❌ AI 请求失败: ${errMsg}`);
-          setError('AI 请求失败: ' + errMsg);
+        {
+          onDelta: (delta) => {
+            if (stoppedRef.current) return;
+            if (firstChunk) {
+              appendLog('收到 AI 响应，正在流式接收数据...');
+              firstChunk = false;
+            }
+            fullContent += delta;
+            setRawOutput(fullContent);
+          },
+          onDone: (finalContent) => {
+            if (stoppedRef.current) return;
+            setRawOutput(finalContent);
+            setEditableOutput(finalContent);
+            setGenerating(false);
+            appendLog(`接收完成，共 ${finalContent.length} 字符。请点击「解析结果」继续。`);
+            // 检测 JSON 是否被截断（未以 ] 结尾）
+            const trimmed = finalContent.trim();
+            const endsWithBracket = trimmed.endsWith(']') || trimmed.endsWith('```');
+            if (!endsWithBracket && trimmed.length > 0) {
+              appendLog('⚠️ 警告：AI 输出可能被截断（未正常结束），建议减少生成数量或检查 AI 服务的 maxTokens 设置。');
+              setError('AI 输出可能被截断，部分资源不完整。可尝试减少生成数量后重新生成，或点击「解析结果」尝试恢复已完成的部分。');
+            }
+          },
+          onError: (errMsg) => {
+            if (stoppedRef.current) return;
+            setGenerating(false);
+            // 预算已用完：用户确认后忽略预算重新生成
+            if (isAIBudgetError(errMsg) && !allowOverBudget) {
+              appendLog(`⚠️ ${errMsg}`);
+              if (confirm(`${errMsg}\n\n是否仍然继续生成？`)) {
+                handleGenerate(true);
+                return;
+              }
+            }
+            appendLog(`❌ AI 请求失败: ${errMsg}`);
+            setError('AI 请求失败: ' + errMsg);
+          },
+          onCancelled: (partialContent) => {
+            // 已停止生成：保留已收到的部分内容，仍可尝试解析
+            setRawOutput(partialContent);
+            setEditableOutput(partialContent);
+            appendLog(`已停止生成，保留已接收的 ${partialContent.length} 字符。`);
+          },
         },
+        // maxTokens 始终为 0，由 AI 自行决定输出长度
+        { maxTokens: 0, resourceType: config.resourceType, allowOverBudget },
       );
       unlistenRef.current = unlisten;
     } catch (e) {
@@ -340,60 +335,36 @@ export function AICreateDialog({ config, onBatchCreated, onClose }: AICreateDial
     }
   };
 
-  // 解析 AI 输出
-  const tryParse = (text: string) => {
+  // 解析 AI 输出（后端提取 JSON 或文件代码块、修复常见错误并校验 manifest）
+  const tryParse = async (text: string) => {
     try {
-      let jsonStr = text.trim();
-
-      // 1. 用 jsonrepair 修复整个 JSON（处理截断、缺引号、尾逗号、代码块包裹等）
-      let repaired: string;
-      try {
-        repaired = jsonrepair(jsonStr);
-      } catch {
-        // jsonrepair 也无法修复时，尝试先提取 JSON 数组部分再修复
-        const startIdx = jsonStr.indexOf('[');
-        if (startIdx >= 0) {
-          jsonStr = jsonStr.slice(startIdx);
-        }
-        try {
-          repaired = jsonrepair(jsonStr);
-        } catch {
-          repaired = jsonStr;
-        }
-      }
-
-      let arr: unknown[];
-      try {
-        arr = JSON.parse(repaired);
-      } catch {
-        // jsonrepair 修复后仍无法整体解析：逐对象提取 + 逐个修复（兜底）
-        const recovered = extractObjects(repaired.length > jsonStr.length ? repaired : jsonStr);
-        if (recovered.length > 0) {
-          arr = recovered;
-          setError(`JSON 整体解析失败，已通过逐对象容错恢复 ${recovered.length} 个资源`);
-        } else {
-          throw new Error('JSON 格式无效且无法恢复');
-        }
-      }
-      if (!Array.isArray(arr) || arr.length === 0) {
-        setError('AI 输出不是有效的 JSON 数组');
-        return;
-      }
+      const result = await invoke<AIParseResult>('cmd_ai_parse_output', {
+        text,
+        spec: {
+          resourceType: config.resourceType,
+          majorCategory: getCategoryKey(),
+          contentFiles: config.contentFiles,
+          defaultManifest: config.defaultManifest,
+        },
+        dataDir: useResourceStore.getState().dataDir || undefined,
+      });
 
-      const parsed: ParsedResource[] = (arr as Record<string, unknown>[]).map((item) => ({
-        manifest: (item.manifest || item) as Record<string, unknown>,
-        contentFiles: (item.contentFiles || item.content_files || {}) as Record<string, string>,
+      const parsed: ParsedResource[] = result.resources.map((item) => ({
+        manifest: item.manifest,
+        contentFiles: Object.fromEntries(item.contentFiles.map((f) => [f.filename, f.content])),
+        warnings: item.warnings,
         selected: true,
         expanded: false,
       }));
 
       setParsedResources(parsed);
+      setParseWarnings(result.warnings);
       setError('');
       setStep('preview');
     } catch (e) {
-      const errMsg = e instanceof Error ? e.message : String(e);
-      console.error('JSON 解析失败:', errMsg, '\n原始输出前500字符:', text.slice(0, 500));
-      setError(`解析 JSON 失败: ${errMsg}。可点击「编辑原始输出」手动修正后重试`);
+      const errMsg = String(e);
+      console.error('AI 输出解析失败:', errMsg, '\n原始输出前500字符:', text.slice(0, 500));
+      setError(`${errMsg}。可点击「编辑原始输出」手动修正后重试`);
       setEditableOutput(text);
     }
   };
@@ -762,6 +733,12 @@ export function AICreateDialog({ config, onBatchCreated, onClose }: AICreateDial
                 </button>
               </div>
 
+              {parseWarnings.length > 0 && (
+                <div className="text-xs text-amber-700 bg-amber-50 dark:bg-amber-500/10 dark:text-amber-400 rounded-lg px-3 py-2 space-y-0.5">
+                  {parseWarnings.map((w, i) => <div key={i}>{w}</div>)}
+                </div>
+              )}
+
               <div className="space-y-2 max-h-[420px] overflow-y-auto pr-1">
                 {parsedResources.map((res, idx) => {
                   const m = res.manifest;
@@ -801,10 +778,20 @@ export function AICreateDialog({ config, onBatchCreated, onClose }: AICreateDial
                             </div>
                           )}
                         </div>
+                        {res.warnings.length > 0 && (
+                          <span title={res.warnings.join('\n')} className="shrink-0">
+                            <AlertCircle className="h-3.5 w-3.5 text-amber-500" />
+                          </span>
+                        )}
                         <span className="text-xs text-muted-foreground shrink-0 font-mono">{(m.id as string) || ''}</span>
                       </div>
                       {res.expanded && (
                         <div className="px-4 pb-4 space-y-3 border-t bg-muted/20">
+                          {res.warnings.length > 0 && (
+                            <div className="pt-3 text-xs text-amber-700 dark:text-amber-400 space-y-0.5">
+                              {res.warnings.map((w, wi) => <div key={wi}>⚠️ {w}</div>)}
+                            </div>
+                          )}
                           <div className="grid grid-cols-2 gap-3 pt-3">
                             <div className="space-y-1.5">
                               <label className="text-xs font-medium text-muted-foreground">ID</label>
@@ -927,7 +914,7 @@ export function AICreateDialog({ config, onBatchCreated, onClose }: AICreateDial
 
             {step === 'config' && (
               <Button
-                onClick={handleGenerate}
+                onClick={() => handleGenerate()}
                 disabled={!canGenerate}
               >
                 <Sparkles className="h-4 w-4" /> 开始生成
@@ -0,0 +1,126 @@
+import { useState } from 'react';
+import { X } from 'lucide-react';
+import { Dialog, DialogContent, DialogHeader, DialogTitle } from './ui/dialog';
+import { Button } from './ui/button';
+
+export type ConflictStrategy = 'skip' | 'overwrite' | 'keepBoth' | 'mergeNewer';
+
+/** dry-run 返回的单项导入计划 */
+export interface ImportPlanItem {
+  source: string;
+  id: string;
+  targetId: string;
+  category: string;
+  subCategory: string;
+  action: string;
+  reason: string;
+  existingPath: string | null;
+  existingUpdatedAt: string | null;
+  incomingUpdatedAt: string;
+  targetPath: string;
+}
+
+const STRATEGY_LABELS: Array<[ConflictStrategy, string]> = [
+  ['skip', '跳过'],
+  ['overwrite', '覆盖'],
+  ['keepBoth', '保留两者（使用新 ID）'],
+  ['mergeNewer', '仅当导入的版本更新时覆盖'],
+];
+
+const selectClass = 'h-9 rounded-md border border-input bg-background px-2 text-sm shadow-sm outline-none focus:ring-1 focus:ring-ring';
+
+interface ImportConflictDialogProps {
+  /** 计划导入的资源总数 */
+  total: number;
+  /** 与现有资源 ID 冲突的项 */
+  conflicts: ImportPlanItem[];
+  onConfirm: (strategy: ConflictStrategy, perItem: Record<string, ConflictStrategy>) => void;
+  onClose: () => void;
+}
+
+export function ImportConflictDialog({ total, conflicts, onConfirm, onClose }: ImportConflictDialogProps) {
+  const [strategy, setStrategy] = useState<ConflictStrategy>('skip');
+  // 未单独指定的项使用全局策略
+  const [perItem, setPerItem] = useState<Record<string, ConflictStrategy>>({});
+
+  const setItemStrategy = (id: string, value: string) => {
+    setPerItem((prev) => {
+      const next = { ...prev };
+      if (value) next[id] = value as ConflictStrategy;
+      else delete next[id];
+      return next;
+    });
+  };
+
+  return (
+    <Dialog open onOpenChange={() => onClose()}>
+      <DialogContent className="max-w-3xl max-h-[80vh] top-[5vh] translate-y-0 overflow-hidden flex flex-col p-0">
+        <DialogHeader className="flex-row items-center justify-between px-6 pt-6 pb-4 border-b space-y-0">
+          <DialogTitle>导入预览（共 {total} 个，{conflicts.length} 个 ID 已存在）</DialogTitle>
+          <Button variant="ghost" size="icon" onClick={onClose}>
+            <X className="h-4 w-4" />
+          </Button>
+        </DialogHeader>
+
+        <div className="p-6 space-y-4 flex-1 overflow-y-auto">
+          {/* 全局策略 */}
+          <div className="flex items-center gap-2">
+            <label className="text-sm font-medium">默认处理方式</label>
+            <select
+              value={strategy}
+              onChange={(e) => setStrategy(e.target.value as ConflictStrategy)}
+              className={selectClass}
+            >
+              {STRATEGY_LABELS.map(([value, label]) => (
+                <option key={value} value={value}>{label}</option>
+              ))}
+            </select>
+          </div>
+
+          {/* 冲突列表：逐项指定处理方式 */}
+          <table className="w-full text-sm">
+            <thead>
+              <tr className="text-left text-muted-foreground border-b">
+                <th className="py-2 pr-2 font-medium">ID</th>
+                <th className="py-2 pr-2 font-medium">分类</th>
+                <th className="py-2 pr-2 font-medium">更新时间（现有 / 导入）</th>
+                <th className="py-2 font-medium">处理方式</th>
+              </tr>
+            </thead>
+            <tbody>
+              {conflicts.map((item) => (
+                <tr key={`${item.source}:${item.id}`} className="border-b last:border-b-0">
+                  <td className="py-2 pr-2">
+                    <div className="font-mono">{item.id}</div>
+                    <div className="text-xs text-muted-foreground">{item.reason}</div>
+                  </td>
+                  <td className="py-2 pr-2">{item.subCategory ? `${item.category}/${item.subCategory}` : item.category}</td>
+                  <td className="py-2 pr-2 text-xs text-muted-foreground">
+                    {item.existingUpdatedAt || '-'} / {item.incomingUpdatedAt || '-'}
+                  </td>
+                  <td className="py-2">
+                    <select
+                      value={perItem[item.id] ?? ''}
+                      onChange={(e) => setItemStrategy(item.id, e.target.value)}
+                      className={selectClass}
+                    >
+                      <option value="">使用默认</option>
+                      {STRATEGY_LABELS.map(([value, label]) => (
+                        <option key={value} value={value}>{label}</option>
+                      ))}
+                    </select>
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        </div>
+
+        <div className="flex justify-end gap-2 px-6 py-4 border-t">
+          <Button variant="outline" onClick={onClose}>取消</Button>
+          <Button onClick={() => onConfirm(strategy, perItem)}>导入</Button>
+        </div>
+      </DialogContent>
+    </Dialog>
+  );
+}
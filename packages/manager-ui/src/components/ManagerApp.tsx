@@ -1,6 +1,6 @@
 import { useEffect, useCallback, useState, useRef, type ComponentType } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { save, open } from '@tauri-apps/plugin-dialog';
+import { listen } from '@tauri-apps/api/event';
 import type {
   ResourceTypeConfig,
   ResourceSummary,
@@ -19,6 +19,7 @@ import { SettingsDialog } from './SettingsDialog';
 import { AICreateDialog } from './AICreateDialog';
 import { BuildDialog } from './BuildDialog';
 import { CreateCategoryDialog } from './CreateCategoryDialog';
+import { ImportConflictDialog, type ConflictStrategy, type ImportPlanItem } from './ImportConflictDialog';
 
 interface ManagerAppProps {
   config: ResourceTypeConfig<ComponentType<EditorPanelProps>>;
@@ -26,6 +27,36 @@ interface ManagerAppProps {
 
 type DialogType = 'create' | 'batch' | 'settings' | 'ai-create' | 'build' | 'create-category' | null;
 
+type ImportResult = {
+  imported: string[];
+  skipped: string[];
+  failed: Array<{ id: string; error: string }>;
+  actions: ImportPlanItem[];
+  createdCategories: string[];
+};
+
+/** dry-run 发现冲突后等待用户选择策略的导入 */
+interface PendingImport {
+  command: string;
+  source: { bundlePath: string } | { zipPath: string };
+  contentFile: string | undefined;
+  total: number;
+  conflicts: ImportPlanItem[];
+}
+
+/** 按扩展名判断是否为 JSON / JSONL 清单，其余按资源包处理 */
+function bundleFormatOf(path: string): 'json' | 'jsonl' | null {
+  const lower = path.toLowerCase();
+  if (lower.endsWith('.jsonl')) return 'jsonl';
+  if (lower.endsWith('.json')) return 'json';
+  return null;
+}
+
+/** CSV / XLSX 表格用于批量编辑 */
+function isSheetPath(path: string): boolean {
+  return /\.(csv|xlsx)$/i.test(path);
+}
+
 export function ManagerApp({ config }: ManagerAppProps) {
   const isJsonMode = config.dataMode === 'json-file';
   const dataDir = useResourceStore((s) => s.dataDir);
@@ -40,6 +71,8 @@ export function ManagerApp({ config }: ManagerAppProps) {
 
   const [isDirty, setIsDirty] = useState(false);
   const [activeDialog, setActiveDialog] = useState<DialogType>(null);
+  const [pendingImport, setPendingImport] = useState<PendingImport | null>(null);
+  const [exportProgress, setExportProgress] = useState<{ exportId: string; filesDone: number; filesTotal: number; bytesDone: number; bytesTotal: number } | null>(null);
 
   const pushUndo = useUndoStore((s) => s.pushUndo);
   const undo = useUndoStore((s) => s.undo);
@@ -51,9 +84,15 @@ export function ManagerApp({ config }: ManagerAppProps) {
 
   // 初始化数据目录：config.defaultDataDir 由 App.tsx 根据资源类型计算好，直接使用
   // 仅当 config.defaultDataDir 为空时才 fallback 到 cmd_get_data_dir（--data-dir 启动参数）
+  // 数据目录需同步给后端：后端所有路径都限制在该目录内
   useEffect(() => {
     if (config.defaultDataDir) {
-      setDataDir(config.defaultDataDir);
+      invoke<string>('cmd_set_data_dir', { dir: config.defaultDataDir })
+        .then((dir) => setDataDir(dir))
+        .catch((e) => {
+          console.error('设置数据目录失败:', e);
+          alert('设置数据目录失败: ' + String(e));
+        });
     } else {
       invoke<string | null>('cmd_get_data_dir')
         .then((dir) => {
@@ -359,6 +398,53 @@ export function ManagerApp({ config }: ManagerAppProps) {
     }
   }, [selectedResource, filteredResources, reload]);
 
+  // 导出资源包：目录模式在后台流式写入并显示进度，可取消；.json / .jsonl 导出为清单
+  const runExport = useCallback(async (paths: string[], outputPath: string) => {
+    const resourceType = config.resourceType;
+    if (isSheetPath(outputPath)) {
+      const options = {
+        includeContent: confirm('是否在表格中包含正文（content 列）？'),
+        contentFile: config.contentFiles[0]?.filename,
+      };
+      if (isJsonMode) {
+        await invoke('cmd_export_json_templates_sheet', { dataDir, templatePaths: paths, outputPath, options });
+      } else {
+        await invoke('cmd_export_sheet', { resourcePaths: paths, outputPath, options });
+      }
+      return;
+    }
+    const bundleFormat = bundleFormatOf(outputPath);
+    if (bundleFormat) {
+      if (isJsonMode) {
+        await invoke('cmd_export_json_templates_bundle', { dataDir, templatePaths: paths, outputPath, format: bundleFormat });
+      } else {
+        const result = await invoke<{ path: string; skipped: string[] }>('cmd_export_bundle', { resourcePaths: paths, outputPath, format: bundleFormat });
+        if (result.skipped.length > 0) {
+          alert(`清单不包含以下二进制文件或子目录，请改用 ZIP 导出以保留完整资源：\n${result.skipped.join('\n')}`);
+        }
+      }
+      return;
+    }
+    if (isJsonMode) {
+      await invoke('cmd_export_json_templates', { dataDir, templatePaths: paths, outputPath, resourceType });
+      return;
+    }
+    const exportId = `export-${Date.now()}`;
+    const unlisten = await listen<{ exportId: string; filesDone: number; filesTotal: number; bytesDone: number; bytesTotal: number }>(
+      'export-progress',
+      (event) => {
+        if (event.payload.exportId === exportId) setExportProgress(event.payload);
+      },
+    );
+    setExportProgress({ exportId, filesDone: 0, filesTotal: 0, bytesDone: 0, bytesTotal: 0 });
+    try {
+      await invoke('cmd_export_resources', { resourcePaths: paths, outputPath, resourceType, exportId });
+    } finally {
+      unlisten();
+      setExportProgress(null);
+    }
+  }, [config.resourceType, config.contentFiles, isJsonMode, dataDir]);
+
   // 导出
   const handleExport = useCallback(async () => {
     const paths = checkedPaths.size > 0
@@ -369,31 +455,105 @@ export function ManagerApp({ config }: ManagerAppProps) {
       return;
     }
     try {
-      const outputPath = await save({
-        defaultPath: `${config.resourceType}-export.zip`,
-        filters: [{ name: 'ZIP', extensions: ['zip'] }],
+      const outputPath = await invoke<string | null>('cmd_choose_export_path', {
+        defaultName: `${config.resourceType}-export.aidocpack`,
+        filterName: 'AiDocPlus 资源包',
+        extensions: ['aidocpack', 'zip', 'json', 'jsonl', 'csv', 'xlsx'],
       });
       if (!outputPath) return;
-      await invoke('cmd_export_resources', { resourcePaths: paths, outputPath });
+      await runExport(paths, outputPath);
       alert(`导出成功：${paths.length} 个资源`);
     } catch (e) {
       alert('导出失败: ' + String(e));
     }
-  }, [checkedPaths, selectedResource, config.resourceType]);
+  }, [checkedPaths, selectedResource, config.resourceType, runExport]);
+
+  // 表格批量编辑：按 id 匹配现有资源，先预览字段级差异，确认后写回
+  const importSheet = useCallback(async (sheetPath: string) => {
+    type SheetImportResult = {
+      changed: Array<{ id: string; changes: Array<{ field: string; old: string; new: string }> }>;
+      unchanged: number;
+      notFound: string[];
+      ignoredColumns: string[];
+      failed: Array<{ id: string; error: string }>;
+      applied: string[];
+    };
+    const command = isJsonMode ? 'cmd_import_json_templates_sheet' : 'cmd_import_sheet';
+    const contentFile = config.contentFiles[0]?.filename;
+    const preview = await invoke<SheetImportResult>(command, { dataDir, sheetPath, options: { contentFile, dryRun: true } });
+    const clip = (v: string) => (v.length > 40 ? `${v.slice(0, 40)}…` : v);
+    const lines = preview.changed.slice(0, 10).map(row =>
+      `- ${row.id}\n${row.changes.map(c => `    ${c.field}: ${clip(c.old)} → ${clip(c.new)}`).join('\n')}`,
+    );
+    if (preview.changed.length > 10) lines.push(`... 共 ${preview.changed.length} 个资源有变更`);
+    if (preview.notFound.length > 0) lines.push(`未找到的 ID：${preview.notFound.slice(0, 10).join('、')}`);
+    if (preview.ignoredColumns.length > 0) lines.push(`忽略的列：${preview.ignoredColumns.join('、')}`);
+    preview.failed.slice(0, 10).forEach(f => lines.push(`错误 ${f.id}：${f.error}`));
+    if (preview.changed.length === 0) {
+      alert(`没有需要更新的资源（未变化 ${preview.unchanged} 个）${lines.length > 0 ? `\n${lines.join('\n')}` : ''}`);
+      return;
+    }
+    if (!confirm(`将更新 ${preview.changed.length} 个资源：\n${lines.join('\n')}\n\n是否应用这些修改？`)) return;
+    const result = await invoke<SheetImportResult>(command, { dataDir, sheetPath, options: { contentFile } });
+    let msg = `已更新 ${result.applied.length} 个资源`;
+    if (result.failed.length > 0) msg += `\n失败 ${result.failed.length} 个：\n${result.failed.slice(0, 10).map(f => `${f.id}：${f.error}`).join('\n')}`;
+    alert(msg);
+    await reload();
+  }, [dataDir, reload, isJsonMode, config.contentFiles]);
+
+  // 导出为 Markdown 文件夹（JSON 文件模式）：未勾选时导出当前列表中的全部模板
+  const handleExportMarkdown = useCallback(async () => {
+    if (!dataDir) return;
+    const templatePaths = checkedPaths.size > 0
+      ? Array.from(checkedPaths)
+      : filteredResources().map(r => r.path);
+    if (templatePaths.length === 0) return;
+    try {
+      const outputDir = await invoke<string | null>('cmd_choose_folder');
+      if (!outputDir) return;
+      const count = await invoke<number>('cmd_export_json_templates_markdown', { dataDir, templatePaths, outputDir });
+      alert(`导出成功：${count} 个模板`);
+    } catch (e) {
+      alert('导出失败: ' + String(e));
+    }
+  }, [dataDir, checkedPaths, filteredResources]);
 
-  // 导入
-  const handleImport = useCallback(async () => {
+  // 导入 Markdown 文件夹：一级文件夹为分类，front matter 提供元数据，正文为内容
+  const handleImportMarkdown = useCallback(async () => {
     if (!dataDir) return;
     try {
-      const selected = await open({
-        multiple: false,
-        filters: [{ name: 'ZIP', extensions: ['zip'] }],
-      });
-      if (!selected) return;
-      const zipPath = typeof selected === 'string' ? selected : selected;
-      const result = await invoke<{ imported: string[]; skipped: string[]; failed: Array<{ id: string; error: string }> }>('cmd_import_resources', { zipPath, dataDir });
+      const folder = await invoke<string | null>('cmd_choose_folder');
+      if (!folder) return;
+      const command = isJsonMode ? 'cmd_import_json_templates_markdown' : 'cmd_import_markdown';
+      const contentFile = config.contentFiles[0]?.filename;
+      const result = await invoke<{
+        imported: string[];
+        skipped: string[];
+        failed: Array<{ id: string; error: string }>;
+        createdCategories: string[];
+      }>(command, { folder, dataDir, options: { contentFile } });
+      let msg = `导入完成：\n成功 ${result.imported.length} 个`;
+      if (result.skipped.length > 0) msg += `\n跳过 ${result.skipped.length} 个（ID 已存在）`;
+      if (result.createdCategories.length > 0) msg += `\n新建分类：${result.createdCategories.join('、')}`;
+      if (result.failed.length > 0) msg += `\n失败 ${result.failed.length} 个：\n${result.failed.slice(0, 10).map(f => `${f.id}：${f.error}`).join('\n')}`;
+      alert(msg);
+      await reload();
+    } catch (e) {
+      alert('导入失败: ' + String(e));
+    }
+  }, [dataDir, reload, isJsonMode, config.contentFiles]);
+
+  // 按选定策略执行导入（perItem 按资源 ID 单独指定策略）
+  const runImport = useCallback(async (
+    { command, source, contentFile }: Pick<PendingImport, 'command' | 'source' | 'contentFile'>,
+    strategy: ConflictStrategy,
+    perItem: Record<string, ConflictStrategy> = {},
+  ) => {
+    try {
+      const result = await invoke<ImportResult>(command, { ...source, dataDir, options: { strategy, perItem, contentFile } });
       let msg = `导入完成：\n成功 ${result.imported.length} 个`;
-      if (result.skipped.length > 0) msg += `\n跳过 ${result.skipped.length} 个（已存在）`;
+      if (result.skipped.length > 0) msg += `\n跳过 ${result.skipped.length} 个`;
+      if (result.createdCategories.length > 0) msg += `\n新建分类：${result.createdCategories.join('、')}`;
       if (result.failed.length > 0) msg += `\n失败 ${result.failed.length} 个`;
       alert(msg);
       await reload();
@@ -402,6 +562,64 @@ export function ManagerApp({ config }: ManagerAppProps) {
     }
   }, [dataDir, reload]);
 
+  const handleImportConflictConfirm = useCallback(async (
+    strategy: ConflictStrategy,
+    perItem: Record<string, ConflictStrategy>,
+  ) => {
+    if (!pendingImport) return;
+    setPendingImport(null);
+    await runImport(pendingImport, strategy, perItem);
+  }, [pendingImport, runImport]);
+
+  // 导入
+  const handleImport = useCallback(async () => {
+    if (!dataDir) return;
+    try {
+      const zipPath = await invoke<string | null>('cmd_choose_import_file', {
+        filterName: 'AiDocPlus 资源包',
+        extensions: ['aidocpack', 'zip', 'json', 'jsonl', 'csv', 'xlsx'],
+      });
+      if (!zipPath) return;
+      if (isSheetPath(zipPath)) {
+        await importSheet(zipPath);
+        return;
+      }
+      const isBundle = bundleFormatOf(zipPath) !== null;
+      // 先查看资源包内容：类型不符或校验失败时提示用户（JSON / JSONL 清单无描述信息，跳过）
+      const inspection = isBundle ? null : await invoke<{
+        package: { resourceType: string; exporterVersion: string; createdAt: string } | null;
+        resources: Array<{ id: string; name: string }>;
+        problems: Array<{ id: string; error: string }>;
+        verified: boolean;
+      }>('cmd_inspect_package', { zipPath });
+      const warnings: string[] = [];
+      if (!inspection) {
+        // 清单文件：有问题的记录在导入结果中逐条报告
+      } else if (!inspection.package) warnings.push('这是不带描述信息的普通 ZIP，无法校验完整性');
+      else if (inspection.package.resourceType && inspection.package.resourceType !== config.resourceType) {
+        warnings.push(`资源包类型为 ${inspection.package.resourceType}，与当前管理器（${config.resourceType}）不符`);
+      }
+      inspection?.problems.slice(0, 10).forEach(p => warnings.push(`${p.id}：${p.error}`));
+      if (inspection && warnings.length > 0 && !confirm(`资源包共 ${inspection.resources.length} 个资源：\n${warnings.join('\n')}\n\n有问题的资源将被跳过，是否继续导入？`)) return;
+      // 先 dry-run 预览，存在 ID 冲突时在对话框中选择全局及逐项的处理策略
+      // JSON 模式导入到分类 JSON 文件；目录模式跨模式导入 JSON 模板时写入配置的首个内容文件
+      const command = isBundle
+        ? (isJsonMode ? 'cmd_import_json_templates_bundle' : 'cmd_import_bundle')
+        : (isJsonMode ? 'cmd_import_json_templates' : 'cmd_import_resources');
+      const source = isBundle ? { bundlePath: zipPath } : { zipPath };
+      const contentFile = config.contentFiles[0]?.filename;
+      const plan = await invoke<ImportResult>(command, { ...source, dataDir, options: { dryRun: true, contentFile } });
+      const conflicts = plan.actions.filter(a => a.existingPath !== null || a.action !== 'create');
+      if (conflicts.length > 0) {
+        setPendingImport({ command, source, contentFile, total: plan.actions.length, conflicts });
+        return;
+      }
+      await runImport({ command, source, contentFile }, 'skip');
+    } catch (e) {
+      alert('导入失败: ' + String(e));
+    }
+  }, [dataDir, isJsonMode, config.contentFiles, config.resourceType, importSheet, runImport]);
+
   // 批量启用/禁用
   const handleBatchEnable = useCallback(async (enabled: boolean) => {
     const paths = Array.from(checkedPaths);
@@ -461,17 +679,18 @@ export function ManagerApp({ config }: ManagerAppProps) {
     const paths = Array.from(checkedPaths);
     if (paths.length === 0) return;
     try {
-      const outputPath = await save({
-        defaultPath: `${config.resourceType}-batch-export.zip`,
-        filters: [{ name: 'ZIP', extensions: ['zip'] }],
+      const outputPath = await invoke<string | null>('cmd_choose_export_path', {
+        defaultName: `${config.resourceType}-batch-export.aidocpack`,
+        filterName: 'AiDocPlus 资源包',
+        extensions: ['aidocpack', 'zip', 'json', 'jsonl', 'csv', 'xlsx'],
       });
       if (!outputPath) return;
-      await invoke('cmd_export_resources', { resourcePaths: paths, outputPath });
+      await runExport(paths, outputPath);
       alert(`导出成功：${paths.length} 个资源`);
     } catch (e) {
       alert('导出失败: ' + String(e));
     }
-  }, [checkedPaths, config.resourceType]);
+  }, [checkedPaths, config.resourceType, runExport]);
 
   // AI 批量新建确认
   const handleAIBatchCreated = useCallback(async (
@@ -556,7 +775,9 @@ export function ManagerApp({ config }: ManagerAppProps) {
           onRedo: handleRedo,
           onBatch: () => setBatchMode(!batchMode),
           onImport: handleImport,
+          onImportFolder: handleImportMarkdown,
           onExport: handleExport,
+          onExportFolder: isJsonMode ? handleExportMarkdown : undefined,
           onReindex: handleReindex,
           onBuild: config.repoDir ? () => setActiveDialog('build') : undefined,
           onSettings: () => setActiveDialog('settings'),
@@ -575,6 +796,28 @@ export function ManagerApp({ config }: ManagerAppProps) {
         onReorderCategories={handleReorderCategories}
       />
 
+      {/* 导出进度 */}
+      {exportProgress && (
+        <div className="fixed bottom-4 right-4 z-50 w-72 rounded-md border bg-background p-3 shadow-lg text-sm">
+          <div className="mb-2">
+            正在导出 {exportProgress.filesDone}/{exportProgress.filesTotal} 个文件
+            （{(exportProgress.bytesDone / 1048576).toFixed(1)}/{(exportProgress.bytesTotal / 1048576).toFixed(1)} MB）
+          </div>
+          <div className="h-1.5 w-full rounded bg-muted mb-2">
+            <div
+              className="h-1.5 rounded bg-primary"
+              style={{ width: `${exportProgress.bytesTotal > 0 ? (exportProgress.bytesDone / exportProgress.bytesTotal) * 100 : 0}%` }}
+            />
+          </div>
+          <button
+            className="text-xs text-muted-foreground hover:text-foreground"
+            onClick={() => invoke('cmd_cancel_export', { exportId: exportProgress.exportId })}
+          >
+            取消导出
+          </button>
+        </div>
+      )}
+
       {/* 对话框 */}
       {activeDialog === 'create' && (
         <CreateDialog
@@ -605,6 +848,14 @@ export function ManagerApp({ config }: ManagerAppProps) {
           onClose={() => setActiveDialog(null)}
         />
       )}
+      {pendingImport && (
+        <ImportConflictDialog
+          total={pendingImport.total}
+          conflicts={pendingImport.conflicts}
+          onConfirm={handleImportConflictConfirm}
+          onClose={() => setPendingImport(null)}
+        />
+      )}
     </>
   );
 }
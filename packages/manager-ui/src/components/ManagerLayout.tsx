@@ -25,6 +25,8 @@ import {
   Power,
   PowerOff,
   FolderInput,
+  FolderOpen,
+  FolderOutput,
 } from 'lucide-react';
 import { CategoryTree } from './CategoryTree';
 import { SearchBar } from './SearchBar';
@@ -46,7 +48,9 @@ interface ManagerLayoutProps {
     onSave: () => void;
     onBatch: () => void;
     onImport: () => void;
+    onImportFolder?: () => void;
     onExport: () => void;
+    onExportFolder?: () => void;
     onBuild?: () => void;
     onSettings: () => void;
     onUndo: () => void;
@@ -276,10 +280,22 @@ export function ManagerLayout({
             <Upload className="h-4 w-4" />
           </button>
 
+          {toolbar.onImportFolder && (
+            <button onClick={toolbar.onImportFolder} className="toolbar-btn" title={t('common.importFolder', { defaultValue: '导入 Markdown 文件夹' })}>
+              <FolderOpen className="h-4 w-4" />
+            </button>
+          )}
+
           <button onClick={toolbar.onExport} className="toolbar-btn" title={t('common.export', { defaultValue: '导出' })}>
             <Download className="h-4 w-4" />
           </button>
 
+          {toolbar.onExportFolder && (
+            <button onClick={toolbar.onExportFolder} className="toolbar-btn" title={t('common.exportFolder', { defaultValue: '导出为 Markdown 文件夹' })}>
+              <FolderOutput className="h-4 w-4" />
+            </button>
+          )}
+
           <button onClick={toolbar.onReindex} className="toolbar-btn" title="一键重排（按名称重新编号）">
             <ListOrdered className="h-4 w-4" />
           </button>
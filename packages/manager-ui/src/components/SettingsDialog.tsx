@@ -2,11 +2,13 @@ import { useState, useEffect, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import {
   X, RefreshCw, Check, Plus, Trash2, Download, Pencil,
-  Zap, AlertCircle, Star, StarOff, Loader2,
+  Zap, AlertCircle, Star, StarOff, Loader2, Lock, Unlock, FileUp, FileDown, Globe, BarChart3, Gauge,
 } from 'lucide-react';
 import { cn } from './ui/cn';
 import { Dialog, DialogContent, DialogHeader, DialogTitle } from './ui/dialog';
 import { Button } from './ui/button';
+import { getAIUsageSummary } from '../hooks/useAIGenerate';
+import type { AIUsageGroupBy, AIUsageSummary } from '../hooks/useAIGenerate';
 
 // ============================================================
 // 类型定义
@@ -45,6 +47,54 @@ interface SharedAIServices {
   maxTokens: number;
 }
 
+/** 连接测试结果（与 Rust 端 AIServiceTestResult 对应） */
+interface ServiceTestResult {
+  ok: boolean;
+  kind?: string;
+  message: string;
+  status?: number;
+  detail?: string;
+  latencyMs: number;
+}
+
+/** AI 请求的网络设置（与 Rust 端 AIHttpSettings 对应） */
+interface NetworkSettings {
+  connectTimeoutSecs: number;
+  readTimeoutSecs: number;
+  maxRetries: number;
+  retryBaseDelayMs: number;
+  retryMaxDelayMs: number;
+  proxyUrl: string;
+  noProxy: string;
+  useSystemProxy: boolean;
+  caCertPath: string;
+}
+
+/** 单个服务的预算与限速，为空或 0 表示不限制 */
+interface ServiceLimits {
+  serviceId: string;
+  dailyTokens?: number | null;
+  monthlyTokens?: number | null;
+  dailyCost?: number | null;
+  monthlyCost?: number | null;
+  requestsPerMinute?: number | null;
+}
+
+/** 服务今日与本月的已用量 */
+interface BudgetUsage {
+  serviceId: string;
+  dailyTokens: number;
+  monthlyTokens: number;
+  dailyCost: number;
+  monthlyCost: number;
+}
+
+/** API Key 加密状态 */
+interface KeyStatus {
+  mode: 'keyFile' | 'passphrase';
+  locked: boolean;
+}
+
 const PROVIDER_BASE_URLS: Record<string, string> = {
   'openai': 'https://api.openai.com/v1',
   'anthropic': 'https://api.anthropic.com/v1',
@@ -78,15 +128,24 @@ export function SettingsDialog({ onClose }: SettingsDialogProps) {
   const [showImport, setShowImport] = useState(false);
   const [importSelection, setImportSelection] = useState<Set<string>>(new Set());
 
+  const [keyStatus, setKeyStatus] = useState<KeyStatus>({ mode: 'keyFile', locked: false });
+  const [passphrase, setPassphrase] = useState('');
+  const [editingPassphrase, setEditingPassphrase] = useState(false);
+  const [networkSettings, setNetworkSettings] = useState<NetworkSettings | null>(null);
+  const [showUsage, setShowUsage] = useState(false);
+  const [showLimits, setShowLimits] = useState(false);
+
   // 加载数据
   const loadData = useCallback(async () => {
     setLoading(true);
     try {
-      const [local, shared] = await Promise.all([
+      const [local, shared, status] = await Promise.all([
         invoke<LocalAIServices>('cmd_load_local_ai_services').catch(() => ({ services: [], activeServiceId: '' })),
         invoke<SharedAIServices>('cmd_load_shared_ai_services').catch(() => null),
+        invoke<KeyStatus>('cmd_ai_key_status').catch(() => null),
       ]);
       setLocalServices(local.services);
+      if (status) setKeyStatus(status);
       setActiveServiceId(local.activeServiceId);
       if (shared) {
         setSharedServices(shared.services.filter((s) => s.enabled));
@@ -185,6 +244,79 @@ export function SettingsDialog({ onClose }: SettingsDialogProps) {
     setImportSelection(new Set());
   };
 
+  // 口令模式：解锁 API Key
+  const handleUnlock = async () => {
+    try {
+      await invoke('cmd_ai_unlock_keys', { passphrase });
+      setPassphrase('');
+      await loadData();
+      showMsg('已解锁', 'success');
+    } catch (e) {
+      showMsg('解锁失败: ' + String(e), 'error');
+    }
+  };
+
+  // 设置口令（为空时改回随机密钥文件），已保存的 Key 会重新加密
+  const handleSetPassphrase = async (value: string | null) => {
+    try {
+      await invoke('cmd_ai_set_passphrase', { passphrase: value });
+      setPassphrase('');
+      setEditingPassphrase(false);
+      await loadData();
+      showMsg(value ? '已设置加密口令' : '已改用密钥文件加密', 'success');
+    } catch (e) {
+      showMsg('设置失败: ' + String(e), 'error');
+    }
+  };
+
+  // 打开网络设置
+  const handleEditNetwork = async () => {
+    try {
+      setNetworkSettings(await invoke<NetworkSettings>('cmd_load_ai_network_settings'));
+    } catch (e) {
+      showMsg('读取网络设置失败: ' + String(e), 'error');
+    }
+  };
+
+  // 保存网络设置（无效的代理地址或证书会被后端拒绝）
+  const handleSaveNetwork = async (settings: NetworkSettings) => {
+    await invoke('cmd_save_ai_network_settings', { settings });
+    setNetworkSettings(null);
+    showMsg('网络设置已保存', 'success');
+  };
+
+  // 导出服务列表（不含 API Key）
+  const handleExportFile = async () => {
+    try {
+      const outputPath = await invoke<string | null>('cmd_choose_export_path', {
+        defaultName: 'ai-services.json',
+        filterName: 'AI 服务列表',
+        extensions: ['json'],
+      });
+      if (!outputPath) return;
+      const count = await invoke<number>('cmd_export_ai_services', { outputPath });
+      showMsg(`已导出 ${count} 个服务（不含 API Key）`, 'success');
+    } catch (e) {
+      showMsg('导出失败: ' + String(e), 'error');
+    }
+  };
+
+  // 从文件导入服务列表（按 ID 合并，已有服务保留 API Key）
+  const handleImportFile = async () => {
+    try {
+      const inputPath = await invoke<string | null>('cmd_choose_import_file', {
+        filterName: 'AI 服务列表',
+        extensions: ['json'],
+      });
+      if (!inputPath) return;
+      const count = await invoke<number>('cmd_import_ai_services', { inputPath });
+      await loadData();
+      showMsg(`导入完成，共 ${count} 个服务，新服务请补填 API Key`, 'success');
+    } catch (e) {
+      showMsg('导入失败: ' + String(e), 'error');
+    }
+  };
+
   // 刷新主程序服务
   const handleRefreshShared = async () => {
     try {
@@ -215,7 +347,8 @@ export function SettingsDialog({ onClose }: SettingsDialogProps) {
     }
   };
 
-  const maskKey = (key: string) => key ? key.slice(0, 6) + '••••••' : '未设置';
+  // 后端返回的 Key 已脱敏
+  const maskKey = (key: string) => key || '未设置';
 
   return (
     <Dialog open onOpenChange={() => onClose()}>
@@ -242,6 +375,23 @@ export function SettingsDialog({ onClose }: SettingsDialogProps) {
             onSave={handleSaveEdit}
             onCancel={() => setEditingService(null)}
           />
+        ) : networkSettings ? (
+          /* ===== 网络设置 ===== */
+          <NetworkSettingsEditor
+            settings={networkSettings}
+            onSave={handleSaveNetwork}
+            onCancel={() => setNetworkSettings(null)}
+          />
+        ) : showUsage ? (
+          /* ===== 用量统计 ===== */
+          <UsagePanel onBack={() => setShowUsage(false)} />
+        ) : showLimits ? (
+          /* ===== 预算与限速 ===== */
+          <LimitsPanel
+            services={localServices}
+            onSaved={() => { setShowLimits(false); showMsg('预算与限速已保存', 'success'); }}
+            onCancel={() => setShowLimits(false)}
+          />
         ) : showImport ? (
           /* ===== 从主程序导入 ===== */
           <div className="flex-1 flex flex-col min-h-0">
@@ -330,6 +480,21 @@ export function SettingsDialog({ onClose }: SettingsDialogProps) {
                   : '暂无服务，请导入或手动添加'}
               </span>
               <div className="flex items-center gap-2">
+                <button onClick={() => setShowUsage(true)} className="p-1.5 rounded-md hover:bg-muted" title="用量统计">
+                  <BarChart3 className="h-3.5 w-3.5 text-muted-foreground" />
+                </button>
+                <button onClick={() => setShowLimits(true)} className="p-1.5 rounded-md hover:bg-muted" title="预算与限速">
+                  <Gauge className="h-3.5 w-3.5 text-muted-foreground" />
+                </button>
+                <button onClick={handleEditNetwork} className="p-1.5 rounded-md hover:bg-muted" title="网络设置（超时、重试、代理、证书）">
+                  <Globe className="h-3.5 w-3.5 text-muted-foreground" />
+                </button>
+                <button onClick={handleImportFile} className="p-1.5 rounded-md hover:bg-muted" title="从文件导入">
+                  <FileUp className="h-3.5 w-3.5 text-muted-foreground" />
+                </button>
+                <button onClick={handleExportFile} className="p-1.5 rounded-md hover:bg-muted" title="导出到文件（不含 API Key）">
+                  <FileDown className="h-3.5 w-3.5 text-muted-foreground" />
+                </button>
                 <Button variant="outline" size="sm" onClick={() => setShowImport(true)}>
                   <Download className="h-3.5 w-3.5" /> 从主程序导入
                 </Button>
@@ -339,6 +504,48 @@ export function SettingsDialog({ onClose }: SettingsDialogProps) {
               </div>
             </div>
 
+            {/* API Key 加密 */}
+            <div className="flex items-center gap-2 px-6 py-2 border-b text-xs text-muted-foreground shrink-0">
+              {keyStatus.locked ? <Lock className="h-3.5 w-3.5 text-amber-500" /> : <Unlock className="h-3.5 w-3.5" />}
+              {keyStatus.locked || editingPassphrase ? (
+                <>
+                  <input
+                    type="password"
+                    value={passphrase}
+                    onChange={(e) => setPassphrase(e.target.value)}
+                    onKeyDown={(e) => {
+                      if (e.key !== 'Enter' || !passphrase) return;
+                      if (keyStatus.locked) handleUnlock(); else handleSetPassphrase(passphrase);
+                    }}
+                    className="flex-1 h-7 rounded-md border border-input bg-background px-2 text-xs outline-none focus:ring-1 focus:ring-ring"
+                    placeholder={keyStatus.locked ? 'API Key 已用口令加密，输入口令解锁' : '新的加密口令'}
+                  />
+                  {keyStatus.locked ? (
+                    <Button size="sm" onClick={handleUnlock} disabled={!passphrase}>解锁</Button>
+                  ) : (
+                    <>
+                      <Button size="sm" onClick={() => handleSetPassphrase(passphrase)} disabled={!passphrase}>保存</Button>
+                      <Button variant="outline" size="sm" onClick={() => { setEditingPassphrase(false); setPassphrase(''); }}>取消</Button>
+                    </>
+                  )}
+                </>
+              ) : (
+                <>
+                  <span className="flex-1">
+                    API Key 已加密保存（{keyStatus.mode === 'passphrase' ? '口令' : '本机密钥文件'}）
+                  </span>
+                  <button onClick={() => setEditingPassphrase(true)} className="text-primary hover:underline">
+                    {keyStatus.mode === 'passphrase' ? '修改口令' : '设置口令'}
+                  </button>
+                  {keyStatus.mode === 'passphrase' && (
+                    <button onClick={() => handleSetPassphrase(null)} className="text-primary hover:underline">
+                      改用密钥文件
+                    </button>
+                  )}
+                </>
+              )}
+            </div>
+
             {/* 服务列表 */}
             <div className="flex-1 overflow-y-auto p-4 space-y-2">
               {localServices.length === 0 ? (
@@ -458,6 +665,51 @@ function ServiceEditor({
 
   const canSave = form.name.trim() && form.apiKey.trim() && form.model.trim();
 
+  const [testing, setTesting] = useState(false);
+  const [testResult, setTestResult] = useState<ServiceTestResult | null>(null);
+  const [models, setModels] = useState<string[]>([]);
+  const [loadingModels, setLoadingModels] = useState(false);
+  const [modelsError, setModelsError] = useState('');
+
+  /** 以表单当前内容作为草稿配置；脱敏的 Key 由后端按服务 ID 还原 */
+  const draftArgs = () => ({
+    serviceId: service.id,
+    config: {
+      provider: form.provider,
+      baseUrl: form.baseUrl,
+      apiKey: form.apiKey,
+      model: form.model,
+      maxTokens: form.maxTokens,
+      temperature: form.temperature,
+    },
+  });
+
+  const handleTest = async () => {
+    setTesting(true);
+    setTestResult(null);
+    try {
+      setTestResult(await invoke<ServiceTestResult>('cmd_ai_test_service', draftArgs()));
+    } catch (e) {
+      setTestResult({ ok: false, message: String(e), latencyMs: 0 });
+    } finally {
+      setTesting(false);
+    }
+  };
+
+  const handleLoadModels = async () => {
+    setLoadingModels(true);
+    setModelsError('');
+    try {
+      const list = await invoke<string[]>('cmd_ai_list_models', draftArgs());
+      setModels(list);
+      if (list.length === 0) setModelsError('未查询到可用模型');
+    } catch (e) {
+      setModelsError(String(e).split('\n')[0]);
+    } finally {
+      setLoadingModels(false);
+    }
+  };
+
   return (
     <div className="flex-1 flex flex-col min-h-0">
       <div className="flex items-center gap-2 px-6 py-3 border-b bg-muted/20 shrink-0">
@@ -509,13 +761,33 @@ function ServiceEditor({
         </div>
         <div className="space-y-1.5">
           <label className="text-sm font-medium">模型 <span className="text-destructive">*</span></label>
-          <input
-            type="text"
-            value={form.model}
-            onChange={(e) => update('model', e.target.value)}
-            className="w-full h-9 rounded-md border border-input bg-background px-3 text-sm font-mono shadow-sm outline-none focus:ring-1 focus:ring-ring"
-            placeholder="gpt-4o / deepseek-chat / ..."
-          />
+          <div className="flex gap-2">
+            <input
+              type="text"
+              list="ai-service-models"
+              value={form.model}
+              onChange={(e) => update('model', e.target.value)}
+              className="flex-1 h-9 rounded-md border border-input bg-background px-3 text-sm font-mono shadow-sm outline-none focus:ring-1 focus:ring-ring"
+              placeholder="gpt-4o / deepseek-chat / ..."
+            />
+            <datalist id="ai-service-models">
+              {models.map((m) => <option key={m} value={m} />)}
+            </datalist>
+            <Button
+              variant="outline"
+              onClick={handleLoadModels}
+              disabled={loadingModels || !form.apiKey.trim()}
+              title="从服务端查询可用模型"
+            >
+              {loadingModels ? <Loader2 className="h-4 w-4 animate-spin" /> : <RefreshCw className="h-4 w-4" />}
+              获取模型
+            </Button>
+          </div>
+          {modelsError ? (
+            <p className="text-xs text-destructive">{modelsError}</p>
+          ) : models.length > 0 && (
+            <p className="text-xs text-muted-foreground">已获取 {models.length} 个模型，可在输入框中选择</p>
+          )}
         </div>
         <div className="grid grid-cols-2 gap-4">
           <div className="space-y-1.5">
@@ -541,15 +813,438 @@ function ServiceEditor({
           </div>
         </div>
       </div>
+      <div className="flex items-center gap-2 px-6 py-4 border-t shrink-0">
+        <Button
+          variant="outline"
+          onClick={handleTest}
+          disabled={testing || !canSave}
+        >
+          {testing ? <Loader2 className="h-4 w-4 animate-spin" /> : <Zap className="h-4 w-4" />}
+          测试连接
+        </Button>
+        {testResult && (
+          <div
+            className={cn(
+              'flex-1 min-w-0 flex items-center gap-1.5 text-xs',
+              testResult.ok ? 'text-green-700' : 'text-destructive',
+            )}
+            title={testResult.detail}
+          >
+            {testResult.ok ? <Check className="h-3.5 w-3.5 shrink-0" /> : <AlertCircle className="h-3.5 w-3.5 shrink-0" />}
+            <span className="truncate">
+              {testResult.message}
+              {testResult.ok && `（${testResult.latencyMs} ms）`}
+            </span>
+          </div>
+        )}
+        <div className="flex gap-2 ml-auto">
+          <Button variant="outline" onClick={onCancel}>
+            取消
+          </Button>
+          <Button
+            onClick={() => canSave && onSave(form)}
+            disabled={!canSave}
+          >
+            <Check className="h-4 w-4" /> 保存
+          </Button>
+        </div>
+      </div>
+    </div>
+  );
+}
+
+// ============================================================
+// 网络设置子组件
+// ============================================================
+
+function NetworkSettingsEditor({
+  settings,
+  onSave,
+  onCancel,
+}: {
+  settings: NetworkSettings;
+  onSave: (settings: NetworkSettings) => Promise<void>;
+  onCancel: () => void;
+}) {
+  const [form, setForm] = useState<NetworkSettings>(settings);
+  const [saving, setSaving] = useState(false);
+  const [error, setError] = useState('');
+
+  const update = <K extends keyof NetworkSettings>(key: K, value: NetworkSettings[K]) => {
+    setForm((prev) => ({ ...prev, [key]: value }));
+  };
+
+  const handleSave = async () => {
+    setSaving(true);
+    setError('');
+    try {
+      await onSave(form);
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  const inputClass = 'w-full h-9 rounded-md border border-input bg-background px-3 text-sm shadow-sm outline-none focus:ring-1 focus:ring-ring';
+  const numberField = (key: 'connectTimeoutSecs' | 'readTimeoutSecs' | 'maxRetries' | 'retryBaseDelayMs' | 'retryMaxDelayMs', label: string, hint?: string) => (
+    <div className="space-y-1.5">
+      <label className="text-sm font-medium">{label}</label>
+      <input
+        type="number"
+        min="0"
+        value={form[key]}
+        onChange={(e) => update(key, Math.max(0, parseInt(e.target.value) || 0))}
+        className={inputClass}
+      />
+      {hint && <p className="text-xs text-muted-foreground">{hint}</p>}
+    </div>
+  );
+
+  return (
+    <div className="flex-1 flex flex-col min-h-0">
+      <div className="flex items-center gap-2 px-6 py-3 border-b bg-muted/20 shrink-0">
+        <Globe className="h-4 w-4 text-primary" />
+        <span className="text-sm font-medium">网络设置</span>
+        <span className="text-xs text-muted-foreground">应用于所有 AI 请求</span>
+      </div>
+      <div className="flex-1 overflow-y-auto p-6 space-y-4">
+        <div className="grid grid-cols-2 gap-4">
+          {numberField('connectTimeoutSecs', '连接超时（秒）')}
+          {numberField('readTimeoutSecs', '读取超时（秒）', '两次收到数据之间的最长等待，0 表示不限制')}
+        </div>
+        <div className="grid grid-cols-3 gap-4">
+          {numberField('maxRetries', '最大重试次数', '连接失败、超时、429 与 5xx 时重试')}
+          {numberField('retryBaseDelayMs', '初始重试间隔（毫秒）')}
+          {numberField('retryMaxDelayMs', '最大重试间隔（毫秒）', 'Retry-After 超过该值时不再重试')}
+        </div>
+        <div className="space-y-1.5">
+          <label className="text-sm font-medium">代理地址</label>
+          <input
+            type="text"
+            value={form.proxyUrl}
+            onChange={(e) => update('proxyUrl', e.target.value)}
+            className={cn(inputClass, 'font-mono')}
+            placeholder="http://proxy.example.com:8080 / socks5://127.0.0.1:1080"
+          />
+          <label className="flex items-center gap-2 text-xs text-muted-foreground">
+            <input
+              type="checkbox"
+              checked={form.useSystemProxy}
+              disabled={!!form.proxyUrl.trim()}
+              onChange={(e) => update('useSystemProxy', e.target.checked)}
+            />
+            未填写代理时使用系统环境变量（HTTP_PROXY / HTTPS_PROXY / ALL_PROXY）
+          </label>
+        </div>
+        <div className="space-y-1.5">
+          <label className="text-sm font-medium">不走代理的地址</label>
+          <input
+            type="text"
+            value={form.noProxy}
+            onChange={(e) => update('noProxy', e.target.value)}
+            disabled={!form.proxyUrl.trim()}
+            className={cn(inputClass, 'font-mono')}
+            placeholder="localhost, 127.0.0.1, .internal.example.com"
+          />
+        </div>
+        <div className="space-y-1.5">
+          <label className="text-sm font-medium">额外信任的 CA 证书</label>
+          <input
+            type="text"
+            value={form.caCertPath}
+            onChange={(e) => update('caCertPath', e.target.value)}
+            className={cn(inputClass, 'font-mono')}
+            placeholder="PEM 文件路径，用于公司网络的 HTTPS 代理"
+          />
+        </div>
+        {error && (
+          <div className="flex items-start gap-1.5 text-sm px-3 py-2 rounded-md bg-destructive/10 text-destructive">
+            <AlertCircle className="h-4 w-4 shrink-0 mt-0.5" />
+            <span>{error}</span>
+          </div>
+        )}
+      </div>
       <div className="flex justify-end gap-2 px-6 py-4 border-t shrink-0">
         <Button variant="outline" onClick={onCancel}>
           取消
         </Button>
-        <Button
-          onClick={() => canSave && onSave(form)}
-          disabled={!canSave}
-        >
-          <Check className="h-4 w-4" /> 保存
+        <Button onClick={handleSave} disabled={saving}>
+          {saving ? <Loader2 className="h-4 w-4 animate-spin" /> : <Check className="h-4 w-4" />} 保存
+        </Button>
+      </div>
+    </div>
+  );
+}
+
+// ============================================================
+// 用量统计子组件
+// ============================================================
+
+const USAGE_RANGES: Array<{ label: string; days: number | null }> = [
+  { label: '今天', days: 1 },
+  { label: '近 7 天', days: 7 },
+  { label: '近 30 天', days: 30 },
+  { label: '全部', days: null },
+];
+
+const USAGE_GROUPS: Array<{ label: string; column: string; value: AIUsageGroupBy }> = [
+  { label: '按日期', column: '日期', value: 'day' },
+  { label: '按模型', column: '模型', value: 'model' },
+  { label: '按资源类型', column: '资源类型', value: 'resourceType' },
+];
+
+/** 本地日期 YYYY-MM-DD */
+function localDate(date: Date): string {
+  const pad = (n: number) => String(n).padStart(2, '0');
+  return `${date.getFullYear()}-${pad(date.getMonth() + 1)}-${pad(date.getDate())}`;
+}
+
+function UsagePanel({ onBack }: { onBack: () => void }) {
+  const [rangeDays, setRangeDays] = useState<number | null>(30);
+  const [groupBy, setGroupBy] = useState<AIUsageGroupBy>('model');
+  const [summary, setSummary] = useState<AIUsageSummary | null>(null);
+  const [error, setError] = useState('');
+
+  useEffect(() => {
+    const from = rangeDays === null
+      ? undefined
+      : localDate(new Date(Date.now() - (rangeDays - 1) * 24 * 3600 * 1000));
+    setError('');
+    getAIUsageSummary({ from, groupBy: [groupBy] })
+      .then(setSummary)
+      .catch((e) => setError(String(e)));
+  }, [rangeDays, groupBy]);
+
+  const formatCost = (cost: number) => `${cost.toFixed(cost < 1 ? 4 : 2)} ${summary?.currency ?? ''}`;
+  const groupLabel = (g: AIUsageSummary['groups'][number]) =>
+    (groupBy === 'day' ? g.day : groupBy === 'model' ? g.model : g.resourceType) || '（未知）';
+
+  return (
+    <div className="flex-1 flex flex-col min-h-0">
+      <div className="flex items-center justify-between px-6 py-3 border-b bg-muted/20 shrink-0">
+        <div className="flex items-center gap-2">
+          <BarChart3 className="h-4 w-4 text-primary" />
+          <span className="text-sm font-medium">AI 用量统计</span>
+        </div>
+        <div className="flex items-center gap-2 text-xs">
+          <select
+            value={rangeDays ?? ''}
+            onChange={(e) => setRangeDays(e.target.value ? Number(e.target.value) : null)}
+            className="h-7 rounded-md border border-input bg-background px-2 outline-none"
+          >
+            {USAGE_RANGES.map((r) => <option key={r.label} value={r.days ?? ''}>{r.label}</option>)}
+          </select>
+          <select
+            value={groupBy}
+            onChange={(e) => setGroupBy(e.target.value as AIUsageGroupBy)}
+            className="h-7 rounded-md border border-input bg-background px-2 outline-none"
+          >
+            {USAGE_GROUPS.map((g) => <option key={g.value} value={g.value}>{g.label}</option>)}
+          </select>
+        </div>
+      </div>
+      <div className="flex-1 overflow-y-auto p-4">
+        {error ? (
+          <div className="text-sm px-3 py-2 rounded-md bg-destructive/10 text-destructive">{error}</div>
+        ) : !summary ? (
+          <div className="flex items-center justify-center py-12">
+            <Loader2 className="h-5 w-5 animate-spin text-muted-foreground" />
+          </div>
+        ) : summary.total.requests === 0 ? (
+          <div className="flex flex-col items-center justify-center py-16 text-muted-foreground">
+            <BarChart3 className="h-10 w-10 mb-3 opacity-30" />
+            <p className="text-sm">所选时间范围内没有 AI 调用记录</p>
+          </div>
+        ) : (
+          <table className="w-full text-xs">
+            <thead className="text-muted-foreground border-b">
+              <tr>
+                <th className="text-left font-medium py-2">{USAGE_GROUPS.find((g) => g.value === groupBy)?.column}</th>
+                <th className="text-right font-medium">请求（失败/取消）</th>
+                <th className="text-right font-medium">输入 Token</th>
+                <th className="text-right font-medium">输出 Token</th>
+                <th className="text-right font-medium">平均耗时</th>
+                <th className="text-right font-medium">估算费用</th>
+              </tr>
+            </thead>
+            <tbody>
+              {[...summary.groups, { ...summary.total, isTotal: true }].map((g, i) => (
+                <tr key={i} className={cn('border-b last:border-0', 'isTotal' in g && 'font-semibold')}>
+                  <td className="py-2 font-mono truncate max-w-[180px]">{'isTotal' in g ? '合计' : groupLabel(g)}</td>
+                  <td className="text-right">{g.requests}（{g.failed}/{g.cancelled}）</td>
+                  <td className="text-right font-mono">{g.inputTokens.toLocaleString()}</td>
+                  <td className="text-right font-mono">{g.outputTokens.toLocaleString()}</td>
+                  <td className="text-right font-mono">{(g.avgLatencyMs / 1000).toFixed(1)} s</td>
+                  <td className="text-right font-mono">{formatCost(g.cost)}</td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        )}
+        {summary && summary.unpricedModels.length > 0 && (
+          <p className="mt-3 text-xs text-muted-foreground">
+            以下模型不在价格表中，未计入费用：{summary.unpricedModels.join('、')}
+          </p>
+        )}
+        {summary && summary.total.missingUsage > 0 && (
+          <p className="mt-1 text-xs text-muted-foreground">
+            {summary.total.missingUsage} 次请求未返回 Token 用量（失败、取消或服务端不支持）
+          </p>
+        )}
+      </div>
+      <div className="flex justify-end px-6 py-4 border-t shrink-0">
+        <Button variant="outline" onClick={onBack}>
+          返回
+        </Button>
+      </div>
+    </div>
+  );
+}
+
+// ============================================================
+// 预算与限速子组件
+// ============================================================
+
+type LimitKey = 'dailyTokens' | 'monthlyTokens' | 'dailyCost' | 'monthlyCost' | 'requestsPerMinute';
+
+const LIMIT_FIELDS: Array<{ key: LimitKey; label: string; cost?: boolean }> = [
+  { key: 'dailyTokens', label: '每日 Token' },
+  { key: 'monthlyTokens', label: '每月 Token' },
+  { key: 'dailyCost', label: '每日费用', cost: true },
+  { key: 'monthlyCost', label: '每月费用', cost: true },
+  { key: 'requestsPerMinute', label: '每分钟请求数' },
+];
+
+function LimitsPanel({
+  services,
+  onSaved,
+  onCancel,
+}: {
+  services: LocalService[];
+  onSaved: () => void;
+  onCancel: () => void;
+}) {
+  const [limits, setLimits] = useState<ServiceLimits[] | null>(null);
+  const [usage, setUsage] = useState<Record<string, BudgetUsage>>({});
+  const [saving, setSaving] = useState(false);
+  const [error, setError] = useState('');
+
+  useEffect(() => {
+    invoke<{ services: ServiceLimits[] }>('cmd_load_ai_limits')
+      .then((data) => setLimits(data.services))
+      .catch((e) => setError(String(e)));
+    Promise.all(services.map((svc) =>
+      invoke<BudgetUsage>('cmd_ai_budget_usage', { serviceId: svc.id }).catch(() => null),
+    )).then((list) => {
+      const map: Record<string, BudgetUsage> = {};
+      for (const item of list) if (item) map[item.serviceId] = item;
+      setUsage(map);
+    });
+  }, [services]);
+
+  const limitOf = (serviceId: string): ServiceLimits =>
+    limits?.find((l) => l.serviceId === serviceId) ?? { serviceId };
+
+  const update = (serviceId: string, key: LimitKey, raw: string) => {
+    const num = Math.max(0, Number(raw) || 0);
+    // token 与请求数为整数
+    const value = raw.trim() === '' ? null : key.endsWith('Cost') ? num : Math.floor(num);
+    setLimits((prev) => {
+      const list = prev ?? [];
+      const current = list.find((l) => l.serviceId === serviceId) ?? { serviceId };
+      const next = { ...current, [key]: value };
+      return [...list.filter((l) => l.serviceId !== serviceId), next];
+    });
+  };
+
+  const handleSave = async () => {
+    if (!limits) return;
+    setSaving(true);
+    setError('');
+    try {
+      // 去掉全部为空的条目，未在列表中显示的服务（如主程序服务）保持不变
+      const cleaned = limits.filter((l) => LIMIT_FIELDS.some((f) => (l[f.key] ?? 0) > 0));
+      await invoke('cmd_save_ai_limits', { limits: { services: cleaned } });
+      onSaved();
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  const usedText = (serviceId: string, key: LimitKey) => {
+    const u = usage[serviceId];
+    if (!u || key === 'requestsPerMinute') return '';
+    const value = u[key];
+    return `已用 ${key.endsWith('Cost') ? value.toFixed(4) : value.toLocaleString()}`;
+  };
+
+  return (
+    <div className="flex-1 flex flex-col min-h-0">
+      <div className="flex items-center gap-2 px-6 py-3 border-b bg-muted/20 shrink-0">
+        <Gauge className="h-4 w-4 text-primary" />
+        <span className="text-sm font-medium">预算与限速</span>
+        <span className="text-xs text-muted-foreground">留空表示不限制；费用按用量统计的价格表估算</span>
+      </div>
+      <div className="flex-1 overflow-y-auto p-4 space-y-3">
+        {!limits && !error ? (
+          <div className="flex items-center justify-center py-12">
+            <Loader2 className="h-5 w-5 animate-spin text-muted-foreground" />
+          </div>
+        ) : services.length === 0 ? (
+          <div className="flex flex-col items-center justify-center py-12 text-muted-foreground">
+            <Gauge className="h-8 w-8 mb-2 opacity-40" />
+            <p className="text-sm">暂无服务</p>
+          </div>
+        ) : (
+          services.map((svc) => {
+            const item = limitOf(svc.id);
+            return (
+              <div key={svc.id} className="p-3.5 rounded-lg border space-y-2">
+                <div className="flex items-center gap-2">
+                  <Zap className="h-4 w-4 text-muted-foreground" />
+                  <span className="text-sm font-medium truncate">{svc.name || svc.provider}</span>
+                  <span className="text-xs text-muted-foreground truncate">{svc.model}</span>
+                </div>
+                <div className="grid grid-cols-5 gap-2">
+                  {LIMIT_FIELDS.map((f) => (
+                    <div key={f.key} className="space-y-1">
+                      <label className="text-xs text-muted-foreground">{f.label}</label>
+                      <input
+                        type="number"
+                        min="0"
+                        step={f.cost ? '0.01' : '1'}
+                        value={item[f.key] ?? ''}
+                        onChange={(e) => update(svc.id, f.key, e.target.value)}
+                        className="w-full h-8 rounded-md border border-input bg-background px-2 text-xs font-mono outline-none focus:ring-1 focus:ring-ring"
+                        placeholder="不限"
+                      />
+                      <p className="text-[10px] text-muted-foreground font-mono h-3">{usedText(svc.id, f.key)}</p>
+                    </div>
+                  ))}
+                </div>
+              </div>
+            );
+          })
+        )}
+        <p className="text-xs text-muted-foreground">
+          预算用完后新的请求会被拒绝，可在确认后继续；超过每分钟请求数的请求会排队等待。
+        </p>
+        {error && (
+          <div className="flex items-start gap-1.5 text-sm px-3 py-2 rounded-md bg-destructive/10 text-destructive">
+            <AlertCircle className="h-4 w-4 shrink-0 mt-0.5" />
+            <span>{error}</span>
+          </div>
+        )}
+      </div>
+      <div className="flex justify-end gap-2 px-6 py-4 border-t shrink-0">
+        <Button variant="outline" onClick={onCancel}>
+          取消
+        </Button>
+        <Button onClick={handleSave} disabled={saving || !limits}>
+          {saving ? <Loader2 className="h-4 w-4 animate-spin" /> : <Check className="h-4 w-4" />} 保存
         </Button>
       </div>
     </div>
@@ -1,5 +1,4 @@
-import { invoke } from '@tauri-apps/api/core';
-import { listen } from '@tauri-apps/api/event';
+import { Channel, invoke } from '@tauri-apps/api/core';
 import type { AIServiceConfig } from '@aidocplus/manager-shared';
 
 /**
@@ -16,23 +15,133 @@ export async function saveAIConfig(config: AIServiceConfig): Promise<void> {
   await invoke('cmd_save_ai_config', { config });
 }
 
+/** 流式生成事件（每个请求独立的 Channel） */
+export type AIStreamEvent =
+  | { type: 'delta'; content: string }
+  | { type: 'done'; content: string; usage?: AITokenUsage | null }
+  | { type: 'cancelled'; content: string; usage?: AITokenUsage | null }
+  | { type: 'error'; content: string; error: string };
+
+export interface AITokenUsage {
+  inputTokens?: number | null;
+  outputTokens?: number | null;
+}
+
+/** 生成请求 ID，用于 cmd_ai_cancel */
+function newRequestId(): string {
+  return crypto.randomUUID();
+}
+
+/**
+ * 取消进行中的 AI 请求
+ */
+export async function cancelAIRequest(requestId: string): Promise<boolean> {
+  return await invoke<boolean>('cmd_ai_cancel', { requestId });
+}
+
 /**
  * AI 生成资源（非流式）
+ *
+ * @deprecated 请改用 aiGenerateWithService（API Key 由后端解析）；
+ * 此函数只能使用调用方自带的完整 API Key，设置界面中的脱敏 Key 会被拒绝
  */
 export async function aiGenerate(
   config: AIServiceConfig,
   systemPrompt: string,
-  userPrompt: string
+  userPrompt: string,
+  requestId?: string,
+  resourceType?: string
 ): Promise<string> {
   return await invoke<string>('cmd_ai_generate', {
     config,
     systemPrompt,
     userPrompt,
+    requestId,
+    resourceType,
   });
 }
 
+/** 流式生成的回调 */
+export interface AIStreamHandlers {
+  onDelta: (delta: string) => void;
+  onDone: (fullContent: string) => void;
+  onError?: (error: string, partialContent: string) => void;
+  onCancelled?: (partialContent: string) => void;
+}
+
+/** 按服务生成时对服务设置的覆盖 */
+export interface AIGenerateOverrides {
+  temperature?: number;
+  maxTokens?: number;
+  /** 生成的资源类型，仅用于用量统计 */
+  resourceType?: string;
+  /** 预算已用完时仍然发送（用户已确认） */
+  allowOverBudget?: boolean;
+}
+
+/** 预算已用完时后端错误信息的前缀 */
+export const AI_BUDGET_ERROR = '[BUDGET]';
+
+/** 是否为预算已用完的错误（可在用户确认后以 allowOverBudget 重试） */
+export function isAIBudgetError(error: string): boolean {
+  return error.startsWith(AI_BUDGET_ERROR);
+}
+
+/**
+ * 发起流式生成命令：创建独立的事件通道并返回取消函数
+ */
+function startStream(
+  command: string,
+  args: Record<string, unknown>,
+  handlers: AIStreamHandlers
+): () => void {
+  const requestId = newRequestId();
+  let finished = false;
+
+  // 每个请求独立的事件通道，并发生成不会互相串流
+  const channel = new Channel<AIStreamEvent>();
+  channel.onmessage = (event) => {
+    switch (event.type) {
+      case 'delta':
+        handlers.onDelta(event.content);
+        break;
+      case 'done':
+        finished = true;
+        handlers.onDone(event.content);
+        break;
+      case 'cancelled':
+        finished = true;
+        handlers.onCancelled?.(event.content);
+        break;
+      case 'error':
+        finished = true;
+        handlers.onError?.(event.error, event.content);
+        break;
+    }
+  };
+
+  // 触发生成（最终结果通过通道送达，这里只处理调用本身的失败）
+  invoke(command, { ...args, requestId, onEvent: channel }).catch((e) => {
+    console.error('AI 流式生成失败:', e);
+    if (!finished) {
+      finished = true;
+      handlers.onError?.(String(e), '');
+    }
+  });
+
+  return () => {
+    if (!finished) {
+      cancelAIRequest(requestId).catch(() => {});
+    }
+  };
+}
+
 /**
  * AI 生成资源（流式）
+ *
+ * 返回取消函数：调用后后端中止 HTTP 请求，onCancelled 收到已生成的部分内容
+ *
+ * @deprecated 请改用 aiGenerateStreamWithService（API Key 由后端解析）
  */
 export async function aiGenerateStream(
   config: AIServiceConfig,
@@ -40,29 +149,105 @@ export async function aiGenerateStream(
   userPrompt: string,
   onDelta: (delta: string) => void,
   onDone: (fullContent: string) => void,
-  onError?: (error: string) => void
+  onError?: (error: string, partialContent: string) => void,
+  onCancelled?: (partialContent: string) => void,
+  resourceType?: string
 ): Promise<() => void> {
-  // 监听流式事件
-  const unlisten = await listen<{ type: string; content: string }>(
-    'ai-generate-stream',
-    (event) => {
-      if (event.payload.type === 'delta') {
-        onDelta(event.payload.content);
-      } else if (event.payload.type === 'done') {
-        onDone(event.payload.content);
-      }
-    }
+  return startStream(
+    'cmd_ai_generate_stream',
+    { config, systemPrompt, userPrompt, resourceType },
+    { onDelta, onDone, onError, onCancelled }
   );
+}
 
-  // 触发生成
-  invoke('cmd_ai_generate_stream', {
-    config,
+/**
+ * 按服务 ID 生成（非流式），API Key 由后端解析；serviceId 为空时使用默认服务
+ */
+export async function aiGenerateWithService(
+  serviceId: string,
+  systemPrompt: string,
+  userPrompt: string,
+  overrides: AIGenerateOverrides = {},
+  requestId?: string
+): Promise<string> {
+  return await invoke<string>('cmd_ai_generate_with_service', {
+    serviceId,
     systemPrompt,
     userPrompt,
-  }).catch((e) => {
-    console.error('AI 流式生成失败:', e);
-    onError?.(String(e));
+    temperature: overrides.temperature,
+    maxTokens: overrides.maxTokens,
+    requestId,
+    resourceType: overrides.resourceType,
+    allowOverBudget: overrides.allowOverBudget,
   });
+}
+
+/**
+ * 按服务 ID 流式生成，返回取消函数
+ */
+export async function aiGenerateStreamWithService(
+  serviceId: string,
+  systemPrompt: string,
+  userPrompt: string,
+  handlers: AIStreamHandlers,
+  overrides: AIGenerateOverrides = {}
+): Promise<() => void> {
+  return startStream(
+    'cmd_ai_generate_stream_with_service',
+    {
+      serviceId,
+      systemPrompt,
+      userPrompt,
+      temperature: overrides.temperature,
+      maxTokens: overrides.maxTokens,
+      resourceType: overrides.resourceType,
+      allowOverBudget: overrides.allowOverBudget,
+    },
+    handlers
+  );
+}
+
+// ============================================================
+// 用量统计
+// ============================================================
+
+export type AIUsageGroupBy = 'day' | 'month' | 'model' | 'provider' | 'service' | 'resourceType';
+
+/** 用量汇总条件，日期为本地日期 YYYY-MM-DD（含首尾） */
+export interface AIUsageQuery {
+  from?: string;
+  to?: string;
+  groupBy?: AIUsageGroupBy[];
+}
+
+export interface AIUsageGroup {
+  day?: string;
+  month?: string;
+  model?: string;
+  provider?: string;
+  serviceId?: string;
+  resourceType?: string;
+  requests: number;
+  succeeded: number;
+  cancelled: number;
+  failed: number;
+  inputTokens: number;
+  outputTokens: number;
+  missingUsage: number;
+  avgLatencyMs: number;
+  cost: number;
+}
 
-  return unlisten;
+export interface AIUsageSummary {
+  currency: string;
+  groups: AIUsageGroup[];
+  total: AIUsageGroup;
+  unpricedModels: string[];
+}
+
+/**
+ * 按维度汇总 AI 用量并估算费用
+ */
+export async function getAIUsageSummary(query: AIUsageQuery = {}): Promise<AIUsageSummary> {
+  return await invoke<AIUsageSummary>('cmd_ai_usage_summary', { query });
 }
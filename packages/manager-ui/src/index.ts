@@ -15,4 +15,5 @@ export { useResourceStore } from './stores/useResourceStore';
 
 export { loadResources, loadResourceDetail, saveResource, createResource, deleteResource, reorderResources, batchSetEnabled, batchMoveCategory, runBuildScript } from './hooks/useResources';
 export { loadCategories, saveCategories } from './hooks/useCategories';
-export { loadAIConfig, saveAIConfig, aiGenerate, aiGenerateStream } from './hooks/useAIGenerate';
+export { loadAIConfig, saveAIConfig, aiGenerate, aiGenerateStream, aiGenerateWithService, aiGenerateStreamWithService, cancelAIRequest, getAIUsageSummary, isAIBudgetError } from './hooks/useAIGenerate';
+export type { AIStreamEvent, AITokenUsage, AIStreamHandlers, AIGenerateOverrides, AIUsageGroupBy, AIUsageQuery, AIUsageGroup, AIUsageSummary } from './hooks/useAIGenerate';